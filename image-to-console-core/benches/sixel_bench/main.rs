@@ -0,0 +1,46 @@
+mod legacy;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use image_to_console_core::{
+    converter::{ImageConverterOption, sixel},
+    indexed_image::IndexedImage,
+};
+use std::hint::black_box;
+
+/// Build a colorful test image, so the palette is fully used
+fn test_image(width: u32, height: u32) -> image::RgbImage {
+    image::RgbImage::from_fn(width, height, |x, y| {
+        image::Rgb([
+            (x * 255 / width) as u8,
+            (y * 255 / height) as u8,
+            ((x ^ y) & 0xff) as u8,
+        ])
+    })
+}
+
+fn bench_sixel_encode(c: &mut Criterion) {
+    let option = ImageConverterOption::default();
+    let mut group = c.benchmark_group("sixel_encode");
+    group.sample_size(10);
+    for (width, height) in [(320, 240), (1280, 720), (1920, 1080)] {
+        let img = IndexedImage::from_image(
+            &test_image(width, height),
+            256,
+            false,
+            quantette::QuantizeMethod::wu(),
+            quantette::ColorSpace::Srgb,
+        )
+        .unwrap();
+        let size = format!("{width}x{height}");
+        group.bench_with_input(BenchmarkId::new("legacy", &size), &img, |b, img| {
+            b.iter(|| legacy::encode(black_box(img), true))
+        });
+        group.bench_with_input(BenchmarkId::new("bytes", &size), &img, |b, img| {
+            b.iter(|| sixel::encode(black_box(img), true, &option).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_sixel_encode);
+criterion_main!(benches);
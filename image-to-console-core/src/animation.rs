@@ -0,0 +1,112 @@
+use crate::gif_processor::GifAnimation;
+use image::{
+    AnimationDecoder, Frames, ImageFormat, ImageReader, ImageResult, RgbaImage,
+    codecs::{png::PngDecoder, webp::WebPDecoder},
+    metadata::LoopCount,
+};
+use std::{path::Path, time::Duration};
+
+/// A source of the frames of an animation
+///
+/// The frames are composed on the canvas of the animation, so every frame is a whole image.
+pub trait AnimationSource {
+    /// Decode the next frame
+    ///
+    /// # Returns
+    ///
+    /// Returns the frame and how long it is shown, `None` at the end of the animation,
+    /// or the error of decoding the frame
+    fn next_frame(&mut self) -> Option<ImageResult<(RgbaImage, Duration)>>;
+
+    /// Get how many times the animation is played, read from the file
+    fn loop_count(&self) -> LoopCount;
+
+    /// Get whether the frames are GIF frames, whose delays are in hundredths of a second
+    fn is_gif(&self) -> bool {
+        false
+    }
+}
+
+/// An animation decoded by an [`AnimationDecoder`] of the `image` crate, e.g. APNG and animated WebP
+pub struct ImageAnimation<'a> {
+    frames: Frames<'a>,
+    loop_count: LoopCount,
+}
+
+impl<'a> ImageAnimation<'a> {
+    /// Create an animation from a decoder
+    ///
+    /// # Arguments
+    ///
+    /// * `decoder` - The decoder of the animation
+    ///
+    /// # Returns
+    ///
+    /// Returns a new animation before the first frame
+    pub fn new(decoder: impl AnimationDecoder<'a>) -> Self {
+        Self {
+            loop_count: decoder.loop_count(),
+            frames: decoder.into_frames(),
+        }
+    }
+}
+
+impl AnimationSource for ImageAnimation<'_> {
+    fn next_frame(&mut self) -> Option<ImageResult<(RgbaImage, Duration)>> {
+        self.frames.next().map(|frame| {
+            frame.map(|frame| {
+                let delay = Duration::from(frame.delay());
+                (frame.into_buffer(), delay)
+            })
+        })
+    }
+
+    fn loop_count(&self) -> LoopCount {
+        self.loop_count
+    }
+}
+
+/// Open an animated image, the format is detected from the content of the file
+///
+/// GIF is decoded by [`GifAnimation`], APNG and animated WebP by [`ImageAnimation`].
+///
+/// # Arguments
+///
+/// * `path` - The path of the image
+///
+/// # Returns
+///
+/// Returns the animation, `None` if the image is not animated (a GIF with one frame is not
+/// animated), or the error of reading the file
+pub fn open_animation(path: impl AsRef<Path>) -> ImageResult<Option<Box<dyn AnimationSource>>> {
+    let path = path.as_ref();
+    let reader = ImageReader::open(path)?.with_guessed_format()?;
+    let format = reader.format();
+    let reader = reader.into_inner();
+    match format {
+        Some(ImageFormat::Gif) => {
+            let mut animation = GifAnimation::new(reader)?;
+            for _ in 0..2 {
+                if animation.next_frame().transpose()?.is_none() {
+                    return Ok(None);
+                }
+            }
+            Ok(Some(Box::new(GifAnimation::open(path)?)))
+        }
+        Some(ImageFormat::Png) => {
+            let decoder = PngDecoder::new(reader)?;
+            if !decoder.is_apng()? {
+                return Ok(None);
+            }
+            Ok(Some(Box::new(ImageAnimation::new(decoder.apng()?))))
+        }
+        Some(ImageFormat::WebP) => {
+            let decoder = WebPDecoder::new(reader)?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            Ok(Some(Box::new(ImageAnimation::new(decoder))))
+        }
+        _ => Ok(None),
+    }
+}
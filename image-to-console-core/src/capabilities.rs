@@ -0,0 +1,580 @@
+#[cfg(unix)]
+use crate::passthrough::Multiplexer;
+use crate::protocol::Protocol;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// The queries sent to the terminal in one burst
+///
+/// 1. The kitty graphics query (`a=q`) with a 1x1 image, answered only by terminals
+///    that support the kitty graphics protocol
+/// 2. `XTVERSION`, the name and the version of the terminal
+/// 3. `XTSMGRAPHICS`, the number of sixel color registers and the maximum sixel size
+/// 4. The size of a cell in pixels (`CSI 16 t`)
+/// 5. `DECRQM` for the synchronized output mode (2026)
+/// 6. `XTGETTCAP` for the `RGB` capability (truecolor)
+/// 7. The primary device attributes (`DA1`), which every terminal answers,
+///    so its reply marks the end of the replies
+pub const QUERY: &str = concat!(
+    "\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\",
+    "\x1b[>0q",
+    "\x1b[?1;1;0S",
+    "\x1b[?2;1;0S",
+    "\x1b[16t",
+    "\x1b[?2026$p",
+    "\x1bP+q524742\x1b\\",
+    "\x1b[c",
+);
+
+/// How long to wait for the replies of the terminal
+pub const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+
+/// A reply of the terminal to one of the queries in [`QUERY`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    /// Primary device attributes (`CSI ? Ps ; ... c`)
+    DeviceAttributes(Vec<u32>),
+    /// The reply to the kitty graphics query (`APC G i=<id> ; <message> ST`)
+    KittyGraphics {
+        /// The image id of the query
+        id: Option<u32>,
+        /// The message of the terminal, `OK` on success
+        message: String,
+    },
+    /// The name and the version of the terminal (`DCS > | <text> ST`)
+    Version(String),
+    /// An `XTSMGRAPHICS` reply (`CSI ? <item> ; <status> ; <values> S`)
+    Graphics {
+        /// 1 for the color registers, 2 for the sixel geometry
+        item: u32,
+        /// 0 on success
+        status: u32,
+        values: Vec<u32>,
+    },
+    /// The size of a cell in pixels (`CSI 6 ; <height> ; <width> t`)
+    CellSize { width: u32, height: u32 },
+    /// A `DECRPM` reply (`CSI ? <mode> ; <status> $ y`)
+    Mode {
+        mode: u32,
+        /// 0 not recognized, 1 set, 2 reset, 3 permanently set, 4 permanently reset
+        status: u32,
+    },
+    /// An `XTGETTCAP` reply (`DCS <valid> + r <name>=<value> ST`), names and values are hex decoded
+    TermCap {
+        valid: bool,
+        capabilities: Vec<(String, Option<String>)>,
+    },
+    /// Any other complete sequence
+    Unknown(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StringKind {
+    /// Device control string (`ESC P`)
+    Dcs,
+    /// Application program command (`ESC _`)
+    Apc,
+    /// Operating system command (`ESC ]`)
+    Osc,
+    /// Privacy message and start of string (`ESC ^`, `ESC X`)
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    Csi,
+    String(StringKind),
+    /// `ESC` in a string, the start of the string terminator
+    StringEscape(StringKind),
+}
+
+/// A state machine to split the input of the terminal into replies
+///
+/// The input may be fed in pieces of any size. The bytes outside the escape sequences
+/// (e.g. keys pressed during the probe) are dropped.
+#[derive(Debug, Clone)]
+pub struct ReplyParser {
+    state: State,
+    buffer: Vec<u8>,
+}
+
+impl Default for ReplyParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReplyParser {
+    pub fn new() -> Self {
+        Self {
+            state: State::Ground,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Feed the input of the terminal
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The next bytes of the input
+    ///
+    /// # Returns
+    ///
+    /// Returns the replies completed by `data`
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Reply> {
+        let mut replies = Vec::new();
+        for &byte in data {
+            self.state = match (self.state, byte) {
+                // CAN and SUB cancel the sequence
+                (_, 0x18 | 0x1a) => State::Ground,
+                (State::Ground, 0x1b) => State::Escape,
+                (State::Ground, _) => State::Ground,
+                (State::Escape, _) => self.escape(byte),
+                (State::Csi, 0x1b) => State::Escape,
+                (State::Csi, 0x20..=0x3f) => {
+                    self.buffer.push(byte);
+                    State::Csi
+                }
+                (State::Csi, 0x40..=0x7e) => {
+                    replies.push(parse_csi(&self.buffer, byte));
+                    State::Ground
+                }
+                // Other control characters are executed by the terminal, not a part of the sequence
+                (State::Csi, _) => State::Csi,
+                (State::String(kind), 0x1b) => State::StringEscape(kind),
+                (State::String(StringKind::Osc), 0x07) => {
+                    replies.push(Reply::Unknown(std::mem::take(&mut self.buffer)));
+                    State::Ground
+                }
+                (State::String(kind), _) => {
+                    self.buffer.push(byte);
+                    State::String(kind)
+                }
+                (State::StringEscape(kind), b'\\') => {
+                    replies.push(parse_string(kind, &self.buffer));
+                    State::Ground
+                }
+                // A new sequence starts before the string is terminated
+                (State::StringEscape(_), _) => self.escape(byte),
+            };
+        }
+        replies
+    }
+
+    /// Handle the byte after `ESC`
+    fn escape(&mut self, byte: u8) -> State {
+        let state = match byte {
+            b'[' => State::Csi,
+            b'P' => State::String(StringKind::Dcs),
+            b'_' => State::String(StringKind::Apc),
+            b']' => State::String(StringKind::Osc),
+            b'^' | b'X' => State::String(StringKind::Other),
+            0x1b => return State::Escape,
+            _ => return State::Ground,
+        };
+        self.buffer.clear();
+        state
+    }
+}
+
+/// Split the parameters of a sequence, empty parameters are 0
+fn parse_params(data: &[u8]) -> Vec<u32> {
+    data.split(|&c| c == b';')
+        .map(|param| {
+            param
+                .iter()
+                .filter(|c| c.is_ascii_digit())
+                .fold(0u32, |value, c| {
+                    value.saturating_mul(10).saturating_add((c - b'0') as u32)
+                })
+        })
+        .collect()
+}
+
+fn parse_csi(data: &[u8], final_byte: u8) -> Reply {
+    let private = data.first() == Some(&b'?');
+    let params = if private { &data[1..] } else { data };
+    match (private, final_byte) {
+        (true, b'c') => Reply::DeviceAttributes(parse_params(params)),
+        (true, b'S') => {
+            let params = parse_params(params);
+            if params.len() < 2 {
+                return Reply::Unknown(data.to_vec());
+            }
+            Reply::Graphics {
+                item: params[0],
+                status: params[1],
+                values: params[2..].to_vec(),
+            }
+        }
+        (true, b'y') if params.last() == Some(&b'$') => {
+            match parse_params(&params[..params.len() - 1])[..] {
+                [mode, status] => Reply::Mode { mode, status },
+                _ => Reply::Unknown(data.to_vec()),
+            }
+        }
+        (false, b't') => match parse_params(params)[..] {
+            [6, height, width] => Reply::CellSize { width, height },
+            _ => Reply::Unknown(data.to_vec()),
+        },
+        _ => Reply::Unknown(data.to_vec()),
+    }
+}
+
+fn decode_hex(data: &[u8]) -> Option<String> {
+    if data.len() % 2 != 0 {
+        return None;
+    }
+    let bytes = data
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+fn parse_string(kind: StringKind, data: &[u8]) -> Reply {
+    match kind {
+        StringKind::Dcs if data.starts_with(b">|") => {
+            Reply::Version(String::from_utf8_lossy(&data[2..]).into_owned())
+        }
+        StringKind::Dcs if data.len() >= 3 && &data[1..3] == b"+r" => Reply::TermCap {
+            valid: data[0] == b'1',
+            capabilities: data[3..]
+                .split(|&c| c == b';')
+                .filter(|entry| !entry.is_empty())
+                .filter_map(|entry| {
+                    let mut parts = entry.splitn(2, |&c| c == b'=');
+                    let name = decode_hex(parts.next()?)?;
+                    Some((name, parts.next().and_then(decode_hex)))
+                })
+                .collect(),
+        },
+        StringKind::Apc if data.first() == Some(&b'G') => {
+            let (keys, message) = match data.iter().position(|&c| c == b';') {
+                Some(split) => (&data[1..split], &data[split + 1..]),
+                None => (&data[1..], &data[data.len()..]),
+            };
+            let id = keys
+                .split(|&c| c == b',')
+                .find_map(|pair| pair.strip_prefix(b"i="))
+                .and_then(|id| std::str::from_utf8(id).ok()?.parse().ok());
+            Reply::KittyGraphics {
+                id,
+                message: String::from_utf8_lossy(message).into_owned(),
+            }
+        }
+        _ => Reply::Unknown(data.to_vec()),
+    }
+}
+
+/// The graphics features of the terminal
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Whether the terminal answered the probe, `false` if the capabilities only come
+    /// from the environment variables
+    pub responded: bool,
+    /// The primary device attributes
+    pub device_attributes: Vec<u32>,
+    /// The name and the version of the terminal
+    pub name: Option<String>,
+    /// Whether the kitty graphics protocol is supported
+    pub kitty_graphics: bool,
+    /// Whether the iTerm2 inline images protocol (OSC 1337) is supported
+    pub iterm2: bool,
+    /// Whether the sixel graphics are supported
+    pub sixel: bool,
+    /// The number of sixel color registers
+    pub sixel_colors: Option<u32>,
+    /// The maximum size of a sixel image in pixels
+    pub sixel_max_size: Option<(u32, u32)>,
+    /// Whether 24-bit colors are supported
+    pub truecolor: bool,
+    /// Whether the synchronized output mode (2026) is supported
+    pub sync_output: bool,
+    /// The size of a cell in pixels (width, height)
+    pub cell_size: Option<(u32, u32)>,
+}
+
+impl Capabilities {
+    /// Build the capabilities from the replies of the terminal
+    ///
+    /// # Arguments
+    ///
+    /// * `replies` - The replies to [`QUERY`]
+    ///
+    /// # Returns
+    ///
+    /// Returns the capabilities, `responded` is set if the device attributes were received
+    pub fn from_replies(replies: &[Reply]) -> Self {
+        let mut capabilities = Self::default();
+        for reply in replies {
+            match reply {
+                Reply::DeviceAttributes(attributes) => {
+                    capabilities.responded = true;
+                    // The first parameter is the conformance level, 4 is the sixel graphics
+                    capabilities.sixel |= attributes.iter().skip(1).any(|&a| a == 4);
+                    capabilities.device_attributes = attributes.clone();
+                }
+                Reply::KittyGraphics { message, .. } => {
+                    capabilities.kitty_graphics |= message == "OK";
+                }
+                Reply::Version(version) => {
+                    let name = version.to_lowercase();
+                    capabilities.iterm2 |=
+                        name.starts_with("iterm2") || name.starts_with("wezterm");
+                    capabilities.name = Some(version.clone());
+                }
+                Reply::Graphics {
+                    item: 1,
+                    status: 0,
+                    values,
+                } => capabilities.sixel_colors = values.first().copied(),
+                Reply::Graphics {
+                    item: 2,
+                    status: 0,
+                    values,
+                } if values.len() >= 2 => {
+                    capabilities.sixel_max_size = Some((values[0], values[1]));
+                }
+                Reply::CellSize { width, height } if *width > 0 && *height > 0 => {
+                    capabilities.cell_size = Some((*width, *height));
+                }
+                Reply::Mode { mode: 2026, status } => {
+                    capabilities.sync_output = matches!(status, 1..=3);
+                }
+                Reply::TermCap {
+                    valid: true,
+                    capabilities: caps,
+                } => {
+                    capabilities.truecolor |= caps.iter().any(|(name, _)| name == "RGB");
+                }
+                _ => {}
+            }
+        }
+        capabilities
+    }
+
+    /// Parse the input the terminal sent back
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The bytes the terminal sent back after [`QUERY`]
+    ///
+    /// # Returns
+    ///
+    /// Returns the capabilities
+    pub fn parse(data: &[u8]) -> Self {
+        Self::from_replies(&ReplyParser::new().feed(data))
+    }
+
+    /// Add the capabilities guessed from the environment variables
+    ///
+    /// Not every terminal answers every query, an older iTerm2 only answers the device
+    /// attributes. When the replies do not name the terminal, the graphics protocols of the
+    /// environment are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `env` - The capabilities from [`Capabilities::from_env`]
+    ///
+    /// # Returns
+    ///
+    /// Returns the capabilities with the ones of the environment
+    pub fn with_env(mut self, env: &Self) -> Self {
+        if self.name.is_none() {
+            self.kitty_graphics |= env.kitty_graphics;
+            self.iterm2 |= env.iterm2;
+        }
+        // Not every terminal answers `XTGETTCAP`
+        self.truecolor |= env.truecolor;
+        self
+    }
+
+    /// Guess the capabilities from the environment variables
+    ///
+    /// This is the fallback for terminals that don't answer the probe.
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).unwrap_or_default().to_lowercase();
+        let term_program = var("TERM_PROGRAM");
+        let term = var("TERM");
+        let is = |name: &str| term_program.contains(name) || term.contains(name);
+        Self {
+            name: std::env::var("TERM_PROGRAM").ok(),
+            kitty_graphics: is("kitty") || std::env::var("KITTY_WINDOW_ID").is_ok(),
+            iterm2: is("iterm") || is("wezterm") || std::env::var("ITERM_SESSION").is_ok(),
+            truecolor: env_truecolor(),
+            ..Default::default()
+        }
+    }
+
+    /// Select the best protocol of the terminal
+    ///
+    /// Detection priority:
+    /// 1. WezTerm - the name of the terminal is WezTerm
+    /// 2. Kitty - the kitty graphics query succeeded
+    /// 3. ITerm2 - the terminal is iTerm2 (or another terminal with OSC 1337)
+    /// 4. Sixel - the device attributes contain the sixel graphics (requires the `sixel` feature)
+    /// 5. Normal - fallback if no graphics protocol is supported
+    pub fn protocol(&self) -> Protocol {
+        let name = self.name.as_deref().unwrap_or_default().to_lowercase();
+        if name.contains("wezterm") {
+            Protocol::WezTerm
+        } else if self.kitty_graphics {
+            Protocol::Kitty
+        } else if self.iterm2 {
+            Protocol::ITerm2
+        } else {
+            #[cfg(feature = "sixel")]
+            if self.sixel {
+                return Protocol::Sixel;
+            }
+            Protocol::Normal
+        }
+    }
+}
+
+fn env_truecolor() -> bool {
+    matches!(
+        std::env::var("COLORTERM").as_deref(),
+        Ok("truecolor" | "24bit")
+    )
+}
+
+/// Restores the terminal mode when dropped
+#[cfg(unix)]
+struct RawMode {
+    fd: i32,
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawMode {
+    /// Disable the line buffering and the echo, so the replies are not shown
+    fn enable(fd: i32) -> std::io::Result<Self> {
+        let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self { fd, original })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(self.fd, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Send [`QUERY`] to the controlling terminal and parse the replies
+///
+/// The terminal is opened with `/dev/tty`, so the probe works when stdin or stdout
+/// are redirected. In tmux or GNU screen, the queries are passed through to the terminal.
+/// The replies are read until the device attributes arrive or `timeout` passes, no thread
+/// is left behind.
+///
+/// # Arguments
+///
+/// * `timeout` - How long to wait for the replies
+///
+/// # Returns
+///
+/// Returns the capabilities, `responded` is `false` if the terminal did not answer in time
+#[cfg(unix)]
+pub fn probe(timeout: Duration) -> std::io::Result<Capabilities> {
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")?;
+    let fd = tty.as_raw_fd();
+    let _raw_mode = RawMode::enable(fd)?;
+    // In a multiplexer the queries are passed through to the terminal,
+    // otherwise the multiplexer itself would answer them
+    let query = match Multiplexer::detect() {
+        Some(multiplexer) => multiplexer.wrap_sequence(QUERY),
+        None => QUERY.to_string(),
+    };
+    tty.write_all(query.as_bytes())?;
+    tty.flush()?;
+
+    let deadline = std::time::Instant::now() + timeout;
+    let mut parser = ReplyParser::new();
+    let mut replies = Vec::new();
+    let mut buffer = [0u8; 1024];
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut pollfd, 1, remaining.as_millis().max(1) as i32) };
+        if ready < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err);
+        }
+        if ready == 0 {
+            break;
+        }
+        let len = tty.read(&mut buffer)?;
+        if len == 0 {
+            break;
+        }
+        let new_replies = parser.feed(&buffer[..len]);
+        let fenced = new_replies
+            .iter()
+            .any(|reply| matches!(reply, Reply::DeviceAttributes(_)));
+        replies.extend(new_replies);
+        if fenced {
+            break;
+        }
+    }
+    Ok(Capabilities::from_replies(&replies))
+}
+
+/// Get the capabilities of the terminal
+///
+/// The terminal is probed on the first call, and the result is cached for the process.
+/// If the terminal does not answer (or the platform can't probe it), the capabilities
+/// are guessed from the environment variables.
+pub fn capabilities() -> &'static Capabilities {
+    CAPABILITIES.get_or_init(|| {
+        #[cfg(unix)]
+        if let Ok(capabilities) = probe(PROBE_TIMEOUT)
+            && capabilities.responded
+        {
+            return capabilities.with_env(&Capabilities::from_env());
+        }
+        Capabilities::from_env()
+    })
+}
+
+/// Get the capabilities of the terminal if it was already probed
+///
+/// Unlike [`capabilities`], the terminal is never probed here, so it can be used for the
+/// optional features when the protocol was not detected automatically.
+pub fn probed_capabilities() -> Option<&'static Capabilities> {
+    CAPABILITIES.get()
+}
@@ -1,12 +1,20 @@
+pub mod cell;
+mod iterm2;
 #[cfg(target_os = "linux")]
 pub mod kitty_shm;
-mod sixel;
+pub mod raster;
+#[cfg(feature = "sixel")]
+pub mod sixel;
 mod unicode;
 
+pub use iterm2::{Iterm2Options, Iterm2Size};
+
 use crate::{
     DisplayMode::{self, *},
     ProcessedImage,
+    converter::cell::{Cell, CellGrid},
     error::{ConvertError, ConvertErrorContext, ConvertErrorContextSource, ConvertResult},
+    passthrough::Multiplexer,
 };
 use base64::{Engine, engine::general_purpose::STANDARD};
 use rayon::iter::*;
@@ -72,8 +80,26 @@ pub struct ImageConverterOption {
     /// Color space to use (requires `sixel` feature)
     #[cfg(feature = "sixel")]
     pub color_space: quantette::ColorSpace,
+    /// Whether to keep the transparent pixels unpainted (requires `sixel` feature)
+    ///
+    /// It only works with an RGBA image (`ProcessedImage::Color`).
+    #[cfg(feature = "sixel")]
+    pub transparent: bool,
+    /// Whether to write the raster attributes of the sixel image (requires `sixel` feature)
+    #[cfg(feature = "sixel")]
+    pub raster_attributes: bool,
+    /// The palette shared by the frames of an animation (requires `sixel` feature)
+    ///
+    /// When it is set, the image is mapped to its colors instead of being quantized,
+    /// and only the color registers that are not defined yet are written.
+    #[cfg(feature = "sixel")]
+    pub sixel_palette: Option<std::sync::Arc<crate::indexed_image::SixelPalette>>,
     /// Terminal size
     pub terminal_size: (u32, u32),
+    /// Options of the iTerm2 protocol, also used by the WezTerm protocol
+    pub iterm2: Iterm2Options,
+    /// The multiplexer to pass the graphics sequences through, `None` to write them as is
+    pub passthrough: Option<Multiplexer>,
 }
 
 impl Default for ImageConverterOption {
@@ -94,7 +120,15 @@ impl Default for ImageConverterOption {
             quantize_method: quantette::QuantizeMethod::wu(),
             #[cfg(feature = "sixel")]
             color_space: quantette::ColorSpace::Srgb,
+            #[cfg(feature = "sixel")]
+            transparent: false,
+            #[cfg(feature = "sixel")]
+            raster_attributes: true,
+            #[cfg(feature = "sixel")]
+            sixel_palette: None,
             terminal_size: (0, 0),
+            iterm2: Iterm2Options::default(),
+            passthrough: None,
         }
     }
 }
@@ -278,6 +312,82 @@ impl ImageConverterOption {
         self
     }
 
+    /// Sets whether to keep the transparent pixels unpainted (requires `sixel` feature)
+    ///
+    /// # Arguments
+    ///
+    /// * `transparent` - Whether to keep the transparent pixels unpainted
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for chaining
+    #[cfg(feature = "sixel")]
+    pub fn transparent(&mut self, transparent: bool) -> &mut Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Sets whether to write the raster attributes of the sixel image (requires `sixel` feature)
+    ///
+    /// # Arguments
+    ///
+    /// * `raster_attributes` - Whether to write the raster attributes
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for chaining
+    #[cfg(feature = "sixel")]
+    pub fn raster_attributes(&mut self, raster_attributes: bool) -> &mut Self {
+        self.raster_attributes = raster_attributes;
+        self
+    }
+
+    /// Sets the palette shared by the frames of an animation (requires `sixel` feature)
+    ///
+    /// # Arguments
+    ///
+    /// * `sixel_palette` - The shared palette, `None` quantizes every image on its own
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for chaining
+    #[cfg(feature = "sixel")]
+    pub fn sixel_palette(
+        &mut self,
+        sixel_palette: Option<std::sync::Arc<crate::indexed_image::SixelPalette>>,
+    ) -> &mut Self {
+        self.sixel_palette = sixel_palette;
+        self
+    }
+
+    /// Sets the options of the iTerm2 protocol (also used by the WezTerm protocol)
+    ///
+    /// # Arguments
+    ///
+    /// * `iterm2` - The iTerm2 protocol options
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for chaining
+    pub fn iterm2(&mut self, iterm2: Iterm2Options) -> &mut Self {
+        self.iterm2 = iterm2;
+        self
+    }
+
+    /// Sets the multiplexer to pass the graphics sequences through
+    ///
+    /// # Arguments
+    ///
+    /// * `passthrough` - The multiplexer, usually `Multiplexer::detect()`
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for chaining
+    pub fn passthrough(&mut self, passthrough: Option<Multiplexer>) -> &mut Self {
+        self.passthrough = passthrough;
+        self
+    }
+
     /// Returns a copy of the current converter options
     ///
     /// # Returns
@@ -329,6 +439,172 @@ impl ImageConverter {
                 expect_type: self.option.mode.expect_image_type().to_string(),
             });
         }
+        let lines = self.convert_mode()?;
+        match self.option.passthrough {
+            Some(multiplexer) if !self.option.mode.is_normal() => {
+                Ok(lines.iter().map(|line| multiplexer.wrap(line)).collect())
+            }
+            _ => Ok(lines),
+        }
+    }
+
+    /// Convert the image to a grid of terminal cells
+    ///
+    /// Only the character-cell modes (`HalfColor`, `FullColor`, `FullNoColor` and `Ascii`) are made of cells.
+    /// The grid is serialized with `CellGrid::to_ansi_lines`, `CellGrid::to_html` or
+    /// `CellGrid::to_plain_lines` instead of [`ImageConverter::convert`].
+    ///
+    /// # Returns
+    ///
+    /// Returns the cells of the image, the indent of `line_init` is kept in `left`
+    pub fn convert_cells(&self) -> ConvertResult<CellGrid> {
+        if !self.option.mode.is_normal() {
+            return Err(ConvertError::UnsupportedMode(
+                self.option.mode.mode().to_string(),
+            ));
+        }
+        if !self.option.mode.check_image_type(&self.img) {
+            return Err(ConvertError::WrongImageType {
+                actual_type: self.img.mode().to_string(),
+                expect_type: self.option.mode.expect_image_type().to_string(),
+            });
+        }
+        let mut grid = if self.full {
+            CellGrid::new(self.option.width, self.option.height.div_ceil(2))
+        } else {
+            CellGrid::new(self.option.width * 2, self.option.height)
+        };
+        grid.left = self.option.line_init.chars().count() as u32;
+        grid.cells = (0..grid.height)
+            .into_par_iter()
+            .map(|y| {
+                let mut row = Vec::with_capacity(grid.width as usize);
+                for x in 0..self.option.width {
+                    match self.option.mode {
+                        FullColor => row.push(self.full_cell(x, y)),
+                        FullNoColor => row.push(self.no_color_cell(x, y)),
+                        // A pixel is two columns wide
+                        _ => {
+                            let cell = self.half_cell(x, y);
+                            row.extend([cell, cell]);
+                        }
+                    }
+                }
+                row
+            })
+            .flatten()
+            .collect();
+        Ok(grid)
+    }
+
+    /// Convert a pixel to a cell in half-height mode (`HalfColor` or `Ascii`)
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X coordinate
+    /// * `y` - Y coordinate
+    ///
+    /// # Returns
+    ///
+    /// Returns the cell of one of the two columns of the pixel
+    fn half_cell(&self, x: u32, y: u32) -> Cell {
+        match &self.img {
+            ProcessedImage::Color(rgba_img) => {
+                let [r, g, b, a] = rgba_img.get_pixel(x, y).0;
+                Cell::new(' ', None, (a >= 128).then_some([r, g, b]))
+            }
+            ProcessedImage::NoColor(luma_img) => Cell::new(
+                unicode::ascii_char(luma_img.get_pixel(x, y).0[0]),
+                None,
+                self.no_color_background(),
+            ),
+            _ => panic!("Invalid image type"),
+        }
+    }
+
+    /// Convert two pixels to a cell in no-color mode
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X coordinate
+    /// * `y` - Y coordinate of the cell, the pixels are at `y * 2` and `y * 2 + 1`
+    ///
+    /// # Returns
+    ///
+    /// Returns the cell of the two pixels
+    fn no_color_cell(&self, x: u32, y: u32) -> Cell {
+        if let ProcessedImage::NoColor(luma_img) = &self.img {
+            // The last row of an odd height only has the top pixel
+            let glyph = if y * 2 + 1 >= self.option.height {
+                if luma_img.get_pixel(x, y * 2).0[0] > 128 {
+                    '▀'
+                } else {
+                    ' '
+                }
+            } else {
+                unicode::luma_convert(luma_img, x, y)
+                    .chars()
+                    .next()
+                    .unwrap_or(' ')
+            };
+            Cell::new(glyph, None, self.no_color_background())
+        } else {
+            panic!("Invalid image type")
+        }
+    }
+
+    /// Get the background of the no-color cells
+    ///
+    /// # Returns
+    ///
+    /// Returns black if `black_background` is enabled, otherwise the default background
+    fn no_color_background(&self) -> Option<[u8; 3]> {
+        self.option.black_background.then_some([0, 0, 0])
+    }
+
+    /// Convert two pixels to a cell in full-height color mode
+    ///
+    /// The brighter pixel is drawn with the half block, as `full_convert` does.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X coordinate
+    /// * `y` - Y coordinate of the cell, the pixels are at `y * 2` and `y * 2 + 1`
+    ///
+    /// # Returns
+    ///
+    /// Returns the cell of the two pixels
+    fn full_cell(&self, x: u32, y: u32) -> Cell {
+        if let ProcessedImage::Both(rgba_img, luma_img) = &self.img {
+            let [r, g, b, a] = rgba_img.get_pixel(x, y * 2).0;
+            let top = (a >= 128).then_some([r, g, b]);
+            // The last row of an odd height only has the top pixel
+            if y * 2 + 1 >= self.option.height {
+                return Cell::new(if top.is_some() { '▀' } else { ' ' }, top, None);
+            }
+            let [r, g, b, a] = rgba_img.get_pixel(x, y * 2 + 1).0;
+            let bottom = (a >= 128).then_some([r, g, b]);
+            let p1 = luma_img.get_pixel(x, y * 2).0[0];
+            let p2 = luma_img.get_pixel(x, y * 2 + 1).0[0];
+            match (top, bottom) {
+                (None, None) => Cell::default(),
+                (None, Some(_)) => Cell::new('▄', bottom, None),
+                (Some(_), None) => Cell::new('▀', top, None),
+                _ if top == bottom => Cell::new('█', top, None),
+                _ if p1 >= p2 => Cell::new('▀', top, bottom),
+                _ => Cell::new('▄', bottom, top),
+            }
+        } else {
+            panic!("Invalid image type")
+        }
+    }
+
+    /// Convert the image with the protocol of the display mode
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of strings representing the converted image
+    fn convert_mode(&self) -> ConvertResult<Vec<String>> {
         match self.option.mode {
             Kitty | KittyNoColor => self.kitty_convert(),
             Iterm2 | Iterm2NoColor => self.iterm2_convert(),
@@ -541,7 +817,7 @@ impl ImageConverter {
     /// Returns a string representing the converted pixel
     fn no_color_convert(&self, x: u32, y: u32) -> String {
         if let ProcessedImage::NoColor(luma_img) = &self.img {
-            unicode::luma_convert(luma_img, x, y)
+            unicode::luma_convert(luma_img, x, y).to_string()
         } else {
             panic!("Invalid image type")
         }
@@ -593,51 +869,68 @@ impl ImageConverter {
     /// Returns a string representing the converted pixel using ASCII characters
     fn ascii_convert(&self, x: u32, y: u32) -> String {
         if let ProcessedImage::NoColor(luma_img) = &self.img {
-            const ASCII_CHARS: [&str; 12] =
-                [" ", ".", ",", ":", ";", "+", "*", "?", "%", "S", "#", "@"];
-            let pixel = luma_img.get_pixel(x, y);
-            let p = pixel.0[0] as usize;
-            let unit = 256 / ASCII_CHARS.len();
-            for i in (0..ASCII_CHARS.len()).rev() {
-                if i * unit <= p {
-                    return ASCII_CHARS[i].repeat(2);
-                }
-            }
-            "  ".to_string()
+            unicode::ascii_char(luma_img.get_pixel(x, y).0[0])
+                .to_string()
+                .repeat(2)
         } else {
             panic!("Invalid image type")
         }
     }
 
-    /// Convert image using WezTerm protocol
+    /// Get the size that fits the image into the terminal
+    ///
+    /// Only one of the width and height is returned, the other one is kept by the aspect ratio.
+    /// Both are `None` if the image is not centered.
     ///
     /// # Returns
     ///
-    /// Returns a vector of strings representing the converted image
-    fn wezterm_convert(&self) -> ConvertResult<Vec<String>> {
+    /// Returns the width and height in character cells
+    fn iterm2_fit_size(&self) -> (Option<Iterm2Size>, Option<Iterm2Size>) {
+        if !self.option.center {
+            return (None, None);
+        }
+        let (w, h) = self.option.terminal_size;
+        let r = self.option.width as f32 / self.option.height as f32;
+        let tr = w as f32 / h as f32;
+        if r < tr {
+            (None, Some(Iterm2Size::Cells(h)))
+        } else {
+            (Some(Iterm2Size::Cells(w)), None)
+        }
+    }
+
+    /// Convert image with the `OSC 1337` protocol that shared by iTerm2 and WezTerm
+    ///
+    /// # Arguments
+    ///
+    /// * `terminator` - The string terminator of the escape sequence
+    ///
+    /// # Returns
+    ///
+    /// Returns the escape sequence(s) of the image
+    fn osc1337_convert(&self, terminator: &str) -> ConvertResult<String> {
         let image_data = self.get_image_data()?;
-        // Add space to prevent misalignment
-        let mut lines: Vec<String> = vec![String::new()];
-        lines[0] = if !self.option.center {
-            format!(
-                "\x1b]1337;File=size={};inline=1:{}\x1b\\",
-                image_data.len(),
-                STANDARD.encode(image_data)
-            )
+        let mut line = if self.option.center {
+            self.option.line_init.clone()
         } else {
-            let (w, h) = self.option.terminal_size;
-            let r = self.option.width as f32 / self.option.height as f32;
-            let tr = w as f32 / h as f32;
-            format!(
-                "{}\x1b]1337;File=size={};{}={};inline=1:{}\x1b\\",
-                self.option.line_init,
-                image_data.len(),
-                if r < tr { "height" } else { "width" },
-                if r < tr { h } else { w },
-                STANDARD.encode(image_data)
-            )
+            String::new()
         };
-        Ok(lines)
+        line.push_str(&iterm2::encode(
+            &image_data,
+            &self.option.iterm2,
+            self.iterm2_fit_size(),
+            terminator,
+        ));
+        Ok(line)
+    }
+
+    /// Convert image using WezTerm protocol
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of strings representing the converted image
+    fn wezterm_convert(&self) -> ConvertResult<Vec<String>> {
+        Ok(vec![self.osc1337_convert("\x1b\\")?])
     }
 
     /// Convert image using Kitty protocol
@@ -684,26 +977,7 @@ impl ImageConverter {
     ///
     /// Returns a vector of strings representing the converted image
     fn iterm2_convert(&self) -> ConvertResult<Vec<String>> {
-        let image_data = self.get_image_data()?;
-        Ok(vec![if !self.option.center {
-            format!(
-                "\x1b]1337;File=size={};inline=1:{}\x07",
-                image_data.len(),
-                STANDARD.encode(image_data)
-            )
-        } else {
-            let (w, h) = self.option.terminal_size;
-            let r = self.option.width as f32 / self.option.height as f32;
-            let tr = w as f32 / h as f32;
-            format!(
-                "{}\x1b]1337;File=size={};{}={};inline=1:{}\x07",
-                self.option.line_init,
-                image_data.len(),
-                if r < tr { "height" } else { "width" },
-                if r < tr { h } else { w },
-                STANDARD.encode(image_data)
-            )
-        }])
+        Ok(vec![self.osc1337_convert("\x07")?])
     }
 
     /// Convert image using Sixel protocol
@@ -713,7 +987,17 @@ impl ImageConverter {
     /// Returns a vector of strings representing the converted image
     #[cfg(feature = "sixel")]
     fn sixel_convert(&self) -> ConvertResult<Vec<String>> {
-        sixel::convert(self.img.rgb().unwrap(), self.full, &self.option)
+        match &self.img {
+            ProcessedImage::Color(img) if self.option.transparent => {
+                sixel::convert_rgba(img, self.full, &self.option)
+            }
+            ProcessedImage::Color(img) => sixel::convert(
+                &image::DynamicImage::from(img.clone()).to_rgb8(),
+                self.full,
+                &self.option,
+            ),
+            _ => sixel::convert(self.img.rgb().unwrap(), self.full, &self.option),
+        }
     }
 
     #[cfg(target_os = "linux")]
@@ -0,0 +1,556 @@
+/// The text attributes of a cell
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CellAttributes {
+    /// Bold or increased intensity
+    pub bold: bool,
+    /// Italic
+    pub italic: bool,
+    /// Underlined
+    pub underline: bool,
+    /// The foreground and background colors are swapped
+    pub reverse: bool,
+}
+
+impl CellAttributes {
+    /// Check whether no attribute is set
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the cell is drawn with the plain text style
+    pub fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A terminal cell of a character-cell image
+///
+/// `None` colors are the default colors of the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    /// The character in the cell
+    pub glyph: char,
+    /// The foreground color
+    pub fg: Option<[u8; 3]>,
+    /// The background color
+    pub bg: Option<[u8; 3]>,
+    /// The text attributes
+    pub attrs: CellAttributes,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::new(' ', None, None)
+    }
+}
+
+impl Cell {
+    /// Create a cell without attributes
+    ///
+    /// # Arguments
+    ///
+    /// * `glyph` - The character in the cell
+    /// * `fg` - The foreground color
+    /// * `bg` - The background color
+    ///
+    /// # Returns
+    ///
+    /// Returns a new cell
+    pub fn new(glyph: char, fg: Option<[u8; 3]>, bg: Option<[u8; 3]>) -> Self {
+        Self {
+            glyph,
+            fg,
+            bg,
+            attrs: CellAttributes::default(),
+        }
+    }
+
+    /// Set the text attributes
+    ///
+    /// # Arguments
+    ///
+    /// * `attrs` - The text attributes
+    ///
+    /// # Returns
+    ///
+    /// Returns the cell with the attributes
+    pub fn with_attributes(mut self, attrs: CellAttributes) -> Self {
+        self.attrs = attrs;
+        self
+    }
+
+    /// Whether the cell has the same style (colors and attributes) as `other`
+    fn same_style(&self, other: &Self) -> bool {
+        (self.fg, self.bg, self.attrs) == (other.fg, other.bg, other.attrs)
+    }
+}
+
+/// The colors used by the ANSI serializer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// 24-bit colors (`38;2;r;g;b`)
+    #[default]
+    TrueColor,
+    /// The 256 colors of xterm (`38;5;n`), for the terminals without truecolor
+    Ansi256,
+}
+
+/// Map a color to the nearest of the xterm 256 colors
+///
+/// Only the 6x6x6 cube and the gray ramp are used, the first 16 colors depend on the theme.
+///
+/// # Arguments
+///
+/// * `color` - The color
+///
+/// # Returns
+///
+/// Returns the color index
+pub fn ansi256(color: [u8; 3]) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |v: u8| match v {
+        0..48 => 0,
+        48..115 => 1,
+        _ => (v - 35) / 40,
+    };
+    let cube = color.map(level);
+    let cube_color = cube.map(|i| LEVELS[i as usize]);
+    let average = color.iter().map(|&v| v as u32).sum::<u32>() / 3;
+    let gray = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_value = 8 + gray * 10;
+    let distance = |other: [u8; 3]| {
+        color
+            .iter()
+            .zip(other)
+            .map(|(&a, b)| (a as i32 - b as i32).pow(2))
+            .sum::<i32>()
+    };
+    if distance([gray_value; 3]) < distance(cube_color) {
+        232 + gray
+    } else {
+        16 + 36 * cube[0] + 6 * cube[1] + cube[2]
+    }
+}
+
+/// The cells of a character-cell image (`HalfColor`, `FullColor`, `FullNoColor` and `Ascii`),
+/// one per terminal column
+///
+/// The grid can be serialized as ANSI (truecolor or 256 colors), HTML, SVG or plain text,
+/// and the renderer compares the grids of two frames to redraw only the changed cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellGrid {
+    /// Width in terminal columns
+    pub width: u32,
+    /// Height in terminal rows
+    pub height: u32,
+    /// Blank columns before every row, used to center the image
+    pub left: u32,
+    /// Blank rows before the first row, used to center the image
+    pub top: u32,
+    /// The cells, row by row
+    pub cells: Vec<Cell>,
+}
+
+impl CellGrid {
+    /// Create a grid filled with blank cells
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width in terminal columns
+    /// * `height` - Height in terminal rows
+    ///
+    /// # Returns
+    ///
+    /// Returns a new grid
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            left: 0,
+            top: 0,
+            cells: vec![Cell::default(); width as usize * height as usize],
+        }
+    }
+
+    /// Get the cells of a row
+    ///
+    /// # Arguments
+    ///
+    /// * `y` - The row
+    ///
+    /// # Returns
+    ///
+    /// Returns the cells of the row, it panics if `y` is out of the grid
+    pub fn row(&self, y: u32) -> &[Cell] {
+        let start = y as usize * self.width as usize;
+        &self.cells[start..start + self.width as usize]
+    }
+
+    /// Get a cell
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The column
+    /// * `y` - The row
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the cell is out of the grid
+    pub fn get(&self, x: u32, y: u32) -> Option<&Cell> {
+        if x < self.width && y < self.height {
+            self.cells
+                .get(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Check whether the grid is drawn at the same place with the same size as `other`
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The other grid
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the cells of both grids can be compared one by one
+    pub fn same_layout(&self, other: &Self) -> bool {
+        (self.width, self.height, self.left, self.top)
+            == (other.width, other.height, other.left, other.top)
+    }
+
+    /// Write a run of cells as ANSI text, the style is only set when it changes
+    ///
+    /// The run starts and ends with the default style.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The string to write to
+    /// * `cells` - The cells of the run
+    /// * `depth` - The colors to use
+    pub fn write_run(output: &mut String, cells: &[Cell], depth: ColorDepth) {
+        let plain = Cell::default();
+        let mut current = plain;
+        for cell in cells {
+            if !cell.same_style(&current) {
+                // A color or an attribute cannot be unset alone, so reset everything
+                let unset = (current.fg.is_some() && cell.fg.is_none())
+                    || (current.bg.is_some() && cell.bg.is_none())
+                    || (current.attrs != cell.attrs && !current.attrs.is_plain());
+                if unset {
+                    output.push_str("\x1b[0m");
+                    current = plain;
+                }
+                if cell.attrs != current.attrs {
+                    let CellAttributes {
+                        bold,
+                        italic,
+                        underline,
+                        reverse,
+                    } = cell.attrs;
+                    for (set, code) in [(bold, 1), (italic, 3), (underline, 4), (reverse, 7)] {
+                        if set {
+                            output.push_str(&format!("\x1b[{code}m"));
+                        }
+                    }
+                }
+                if let Some(color) = cell.fg.filter(|_| cell.fg != current.fg) {
+                    push_color(output, 38, color, depth);
+                }
+                if let Some(color) = cell.bg.filter(|_| cell.bg != current.bg) {
+                    push_color(output, 48, color, depth);
+                }
+                current = *cell;
+            }
+            output.push(cell.glyph);
+        }
+        if !current.same_style(&plain) {
+            output.push_str("\x1b[0m");
+        }
+    }
+
+    /// Serialize the grid as ANSI text
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - The colors to use
+    ///
+    /// # Returns
+    ///
+    /// Returns the lines of the image indented by `left`, without the blank rows of `top`
+    pub fn to_ansi_lines(&self, depth: ColorDepth) -> Vec<String> {
+        (0..self.height)
+            .map(|y| {
+                let mut line = " ".repeat(self.left as usize);
+                Self::write_run(&mut line, self.row(y), depth);
+                line
+            })
+            .collect()
+    }
+
+    /// Serialize the glyphs of the grid, without any color
+    ///
+    /// # Returns
+    ///
+    /// Returns the lines of the image indented by `left`, without the blank rows of `top`
+    pub fn to_plain_lines(&self) -> Vec<String> {
+        (0..self.height)
+            .map(|y| {
+                let mut line = " ".repeat(self.left as usize);
+                line.extend(self.row(y).iter().map(|cell| cell.glyph));
+                line
+            })
+            .collect()
+    }
+
+    /// Serialize the grid as an HTML `<pre>` element
+    ///
+    /// The cells with the same style are joined in one `<span>`.
+    /// `left` and `top` are ignored, the page places the element.
+    ///
+    /// # Returns
+    ///
+    /// Returns the HTML of the image
+    pub fn to_html(&self) -> String {
+        let mut html = String::from("<pre style=\"line-height:1;font-family:monospace\">");
+        for y in 0..self.height {
+            if y > 0 {
+                html.push('\n');
+            }
+            for run in self.row(y).chunk_by(Cell::same_style) {
+                let style = html_style(&run[0]);
+                if !style.is_empty() {
+                    html.push_str(&format!("<span style=\"{style}\">"));
+                }
+                for cell in run {
+                    match cell.glyph {
+                        '&' => html.push_str("&amp;"),
+                        '<' => html.push_str("&lt;"),
+                        '>' => html.push_str("&gt;"),
+                        glyph => html.push(glyph),
+                    }
+                }
+                if !style.is_empty() {
+                    html.push_str("</span>");
+                }
+            }
+        }
+        html.push_str("</pre>");
+        html
+    }
+
+    /// Serialize the grid as a standalone HTML page
+    ///
+    /// The default colors of the terminal are white on black.
+    ///
+    /// # Returns
+    ///
+    /// Returns the HTML page of the image
+    pub fn to_html_page(&self) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>image_to_console</title>\n</head>\n\
+             <body style=\"background-color:#{};color:#{}\">\n\
+             {}\n</body>\n</html>\n",
+            hex(DEFAULT_BACKGROUND),
+            hex(DEFAULT_FOREGROUND),
+            self.to_html()
+        )
+    }
+
+    /// Serialize the grid as an SVG image
+    ///
+    /// A cell is [`SVG_CELL_WIDTH`] x [`SVG_CELL_HEIGHT`] pixels. The backgrounds and the block
+    /// glyphs (`▀`, `▄` and `█`) are drawn as rects, so the image has no gap between the rows,
+    /// the other glyphs are drawn as text. The default colors of the terminal are white on black.
+    ///
+    /// # Returns
+    ///
+    /// Returns the SVG document of the image
+    pub fn to_svg(&self) -> String {
+        let (width, height) = (self.width * SVG_CELL_WIDTH, self.height * SVG_CELL_HEIGHT);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             viewBox=\"0 0 {width} {height}\" shape-rendering=\"crispEdges\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"#{}\"/>\n",
+            hex(DEFAULT_BACKGROUND)
+        );
+        for y in 0..self.height {
+            let mut x = 0;
+            // Backgrounds, then the block glyphs, joined with the next cells of the same color
+            for run in self
+                .row(y)
+                .chunk_by(|a, b| colors(a, DEFAULTS).1 == colors(b, DEFAULTS).1)
+            {
+                if let Some(bg) = colors(&run[0], DEFAULTS).1 {
+                    push_svg_rect(&mut svg, x, y, run.len() as u32, Block::Full, Some(bg));
+                }
+                x += run.len() as u32;
+            }
+            x = 0;
+            for run in self.row(y).chunk_by(|a, b| {
+                a.glyph == b.glyph && colors(a, DEFAULTS).0 == colors(b, DEFAULTS).0
+            }) {
+                let fg = colors(&run[0], DEFAULTS).0;
+                match Block::from_glyph(run[0].glyph) {
+                    Some(block) => push_svg_rect(&mut svg, x, y, run.len() as u32, block, fg),
+                    None => {
+                        for (i, cell) in run.iter().enumerate() {
+                            push_svg_text(&mut svg, x + i as u32, y, cell, fg);
+                        }
+                    }
+                }
+                x += run.len() as u32;
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// The width of a cell in the SVG serializer, in pixels
+pub const SVG_CELL_WIDTH: u32 = 8;
+/// The height of a cell in the SVG serializer, in pixels
+pub const SVG_CELL_HEIGHT: u32 = 16;
+
+/// The default foreground color of the HTML and SVG serializers
+const DEFAULT_FOREGROUND: [u8; 3] = [255, 255, 255];
+/// The default background color of the HTML and SVG serializers
+const DEFAULT_BACKGROUND: [u8; 3] = [0, 0, 0];
+/// The default colors of the HTML and SVG serializers, as passed to [`colors`]
+const DEFAULTS: ([u8; 3], [u8; 3]) = (DEFAULT_FOREGROUND, DEFAULT_BACKGROUND);
+
+/// The part of a cell covered by a block glyph
+#[derive(Clone, Copy)]
+enum Block {
+    Upper,
+    Lower,
+    Full,
+}
+
+impl Block {
+    fn from_glyph(glyph: char) -> Option<Self> {
+        match glyph {
+            '▀' => Some(Self::Upper),
+            '▄' => Some(Self::Lower),
+            '█' => Some(Self::Full),
+            _ => None,
+        }
+    }
+}
+
+/// Get the colors a cell is drawn with, swapped if the cell is reversed
+///
+/// The default colors of a reversed cell are resolved before they are swapped, so its
+/// foreground is the default background and not the default foreground.
+///
+/// # Arguments
+///
+/// * `cell` - The cell
+/// * `defaults` - The default foreground and background colors
+pub(crate) fn colors(
+    cell: &Cell,
+    (foreground, background): ([u8; 3], [u8; 3]),
+) -> (Option<[u8; 3]>, Option<[u8; 3]>) {
+    if cell.attrs.reverse {
+        (
+            Some(cell.bg.unwrap_or(background)),
+            Some(cell.fg.unwrap_or(foreground)),
+        )
+    } else {
+        (cell.fg, cell.bg)
+    }
+}
+
+/// Get the hex code of a color
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("{r:02x}{g:02x}{b:02x}")
+}
+
+/// Get the hex code of a color, `None` is the default foreground color
+fn svg_fill(color: Option<[u8; 3]>) -> String {
+    hex(color.unwrap_or(DEFAULT_FOREGROUND))
+}
+
+/// Write the rect of a block over `columns` cells
+fn push_svg_rect(
+    svg: &mut String,
+    x: u32,
+    y: u32,
+    columns: u32,
+    block: Block,
+    color: Option<[u8; 3]>,
+) {
+    let half = SVG_CELL_HEIGHT / 2;
+    let (top, height) = match block {
+        Block::Upper => (y * SVG_CELL_HEIGHT, half),
+        Block::Lower => (y * SVG_CELL_HEIGHT + half, half),
+        Block::Full => (y * SVG_CELL_HEIGHT, SVG_CELL_HEIGHT),
+    };
+    svg.push_str(&format!(
+        "<rect x=\"{}\" y=\"{top}\" width=\"{}\" height=\"{height}\" fill=\"#{}\"/>\n",
+        x * SVG_CELL_WIDTH,
+        columns * SVG_CELL_WIDTH,
+        svg_fill(color)
+    ));
+}
+
+/// Write the text of a cell, nothing is written for a blank cell
+fn push_svg_text(svg: &mut String, x: u32, y: u32, cell: &Cell, color: Option<[u8; 3]>) {
+    if cell.glyph.is_whitespace() {
+        return;
+    }
+    let mut attributes = String::new();
+    if cell.attrs.bold {
+        attributes.push_str(" font-weight=\"bold\"");
+    }
+    if cell.attrs.italic {
+        attributes.push_str(" font-style=\"italic\"");
+    }
+    if cell.attrs.underline {
+        attributes.push_str(" text-decoration=\"underline\"");
+    }
+    let glyph = match cell.glyph {
+        '&' => String::from("&amp;"),
+        '<' => String::from("&lt;"),
+        '>' => String::from("&gt;"),
+        glyph => glyph.to_string(),
+    };
+    // The baseline is near the bottom of the cell
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"{}\" fill=\"#{}\"{attributes}>{glyph}</text>\n",
+        x * SVG_CELL_WIDTH,
+        (y + 1) * SVG_CELL_HEIGHT - SVG_CELL_HEIGHT / 4,
+        SVG_CELL_HEIGHT * 7 / 8,
+        svg_fill(color)
+    ));
+}
+
+/// Write the SGR sequence of a foreground (`38`) or background (`48`) color
+fn push_color(output: &mut String, target: u8, [r, g, b]: [u8; 3], depth: ColorDepth) {
+    match depth {
+        ColorDepth::TrueColor => output.push_str(&format!("\x1b[{target};2;{r};{g};{b}m")),
+        ColorDepth::Ansi256 => output.push_str(&format!("\x1b[{target};5;{}m", ansi256([r, g, b]))),
+    }
+}
+
+/// Get the CSS of the style of a cell
+fn html_style(cell: &Cell) -> String {
+    let (fg, bg) = colors(cell, DEFAULTS);
+    let mut style = Vec::new();
+    if let Some(fg) = fg {
+        style.push(format!("color:#{}", hex(fg)));
+    }
+    if let Some(bg) = bg {
+        style.push(format!("background-color:#{}", hex(bg)));
+    }
+    if cell.attrs.bold {
+        style.push(String::from("font-weight:bold"));
+    }
+    if cell.attrs.italic {
+        style.push(String::from("font-style:italic"));
+    }
+    if cell.attrs.underline {
+        style.push(String::from("text-decoration:underline"));
+    }
+    style.join(";")
+}
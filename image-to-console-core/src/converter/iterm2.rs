@@ -0,0 +1,257 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use std::fmt::{Display, Formatter};
+
+/// Size of an inline image in the iTerm2 `File=` arguments
+///
+/// The iTerm2 protocol accepts the width and height of an image in character cells,
+/// pixels or as a percentage of the session size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Iterm2Size {
+    /// Let the terminal decide the size (`auto`)
+    #[default]
+    Auto,
+    /// Size in character cells (`N`)
+    Cells(u32),
+    /// Size in pixels (`Npx`)
+    Pixels(u32),
+    /// Size in percent of the session width or height (`N%`)
+    Percent(u32),
+}
+
+impl Display for Iterm2Size {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Cells(cells) => write!(f, "{cells}"),
+            Self::Pixels(pixels) => write!(f, "{pixels}px"),
+            Self::Percent(percent) => write!(f, "{percent}%"),
+        }
+    }
+}
+
+/// Options of the iTerm2 inline image protocol
+///
+/// These options are used by both the `Iterm2` and the `WezTerm` display modes,
+/// since WezTerm implements the same `OSC 1337` protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Iterm2Options {
+    /// Width of the image, `None` lets the converter fit the image to the terminal when centered
+    pub width: Option<Iterm2Size>,
+    /// Height of the image, `None` lets the converter fit the image to the terminal when centered
+    pub height: Option<Iterm2Size>,
+    /// Whether to keep the aspect ratio when both width and height are given
+    pub preserve_aspect_ratio: bool,
+    /// File name of the image, it is base64 encoded before sending
+    pub name: Option<String>,
+    /// Whether to keep the cursor in place after the image is drawn
+    pub do_not_move_cursor: bool,
+    /// Send the image with `MultipartFile`/`FilePart`/`FileEnd` in chunks of this many bytes
+    ///
+    /// Large images may exceed the buffer limits of the terminal when they are sent
+    /// in a single sequence. `None` sends the whole image in one `File=` sequence.
+    pub multipart_chunk_size: Option<usize>,
+}
+
+impl Default for Iterm2Options {
+    fn default() -> Self {
+        Self {
+            width: None,
+            height: None,
+            preserve_aspect_ratio: true,
+            name: None,
+            do_not_move_cursor: false,
+            multipart_chunk_size: None,
+        }
+    }
+}
+
+impl Iterm2Options {
+    /// The default chunk size of the multipart transfer
+    ///
+    /// It is a multiple of 3, so every chunk can be base64 encoded without padding.
+    pub const DEFAULT_CHUNK_SIZE: usize = 768 * 1024;
+
+    /// Sets the width of the image
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the image
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for chaining
+    pub fn width(&mut self, width: Iterm2Size) -> &mut Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Sets the height of the image
+    ///
+    /// # Arguments
+    ///
+    /// * `height` - Height of the image
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for chaining
+    pub fn height(&mut self, height: Iterm2Size) -> &mut Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Sets whether to keep the aspect ratio of the image
+    ///
+    /// # Arguments
+    ///
+    /// * `preserve_aspect_ratio` - Whether to keep the aspect ratio
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for chaining
+    pub fn preserve_aspect_ratio(&mut self, preserve_aspect_ratio: bool) -> &mut Self {
+        self.preserve_aspect_ratio = preserve_aspect_ratio;
+        self
+    }
+
+    /// Sets the file name of the image
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - File name of the image
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for chaining
+    pub fn name(&mut self, name: String) -> &mut Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Sets whether to keep the cursor in place after the image is drawn
+    ///
+    /// # Arguments
+    ///
+    /// * `do_not_move_cursor` - Whether to keep the cursor in place
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for chaining
+    pub fn do_not_move_cursor(&mut self, do_not_move_cursor: bool) -> &mut Self {
+        self.do_not_move_cursor = do_not_move_cursor;
+        self
+    }
+
+    /// Sets the chunk size of the multipart transfer
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_size` - Chunk size in raw bytes, `None` disables the multipart transfer
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for chaining
+    pub fn multipart_chunk_size(&mut self, chunk_size: Option<usize>) -> &mut Self {
+        self.multipart_chunk_size = chunk_size;
+        self
+    }
+
+    /// Returns a copy of the current options
+    pub fn get_options(&self) -> Self {
+        self.clone()
+    }
+
+    /// Build the `key=value` arguments of the `File=`/`MultipartFile=` sequence
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Size of the image data in bytes
+    /// * `fit` - The size used for the dimensions that are not set explicitly
+    fn args(&self, size: usize, fit: (Option<Iterm2Size>, Option<Iterm2Size>)) -> String {
+        let mut args = Vec::with_capacity(7);
+        if let Some(name) = &self.name {
+            args.push(format!("name={}", STANDARD.encode(name)));
+        }
+        args.push(format!("size={size}"));
+        if let Some(width) = self.width.or(fit.0) {
+            args.push(format!("width={width}"));
+        }
+        if let Some(height) = self.height.or(fit.1) {
+            args.push(format!("height={height}"));
+        }
+        if !self.preserve_aspect_ratio {
+            args.push(String::from("preserveAspectRatio=0"));
+        }
+        args.push(String::from("inline=1"));
+        if self.do_not_move_cursor {
+            args.push(String::from("doNotMoveCursor=1"));
+        }
+        args.join(";")
+    }
+}
+
+/// Encode the image data with the `OSC 1337` protocol
+///
+/// # Arguments
+///
+/// * `data` - The encoded image file (e.g. PNG)
+/// * `options` - Options of the protocol
+/// * `fit` - The width and height used when they are not given in `options`
+/// * `terminator` - The string terminator (`BEL` for iTerm2, `ST` for WezTerm)
+///
+/// # Returns
+///
+/// Returns the escape sequence(s) of the image
+pub(super) fn encode(
+    data: &[u8],
+    options: &Iterm2Options,
+    fit: (Option<Iterm2Size>, Option<Iterm2Size>),
+    terminator: &str,
+) -> String {
+    let args = options.args(data.len(), fit);
+    match options.multipart_chunk_size {
+        Some(chunk_size) => {
+            // Keep every chunk aligned to 3 bytes, then no padding is in the middle of the data
+            let chunk_size = (chunk_size.max(3) / 3) * 3;
+            let mut result = format!("\x1b]1337;MultipartFile={args}{terminator}");
+            for chunk in data.chunks(chunk_size) {
+                result.push_str("\x1b]1337;FilePart=");
+                result.push_str(&STANDARD.encode(chunk));
+                result.push_str(terminator);
+            }
+            result.push_str("\x1b]1337;FileEnd");
+            result.push_str(terminator);
+            result
+        }
+        None => format!(
+            "\x1b]1337;File={args}:{}{terminator}",
+            STANDARD.encode(data)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iterm2_size_display() {
+        assert_eq!(Iterm2Size::Auto.to_string(), "auto");
+        assert_eq!(Iterm2Size::Cells(20).to_string(), "20");
+        assert_eq!(Iterm2Size::Pixels(320).to_string(), "320px");
+        assert_eq!(Iterm2Size::Percent(50).to_string(), "50%");
+    }
+
+    #[test]
+    fn test_iterm2_args() {
+        let options = Iterm2Options::default()
+            .name(String::from("a.png"))
+            .width(Iterm2Size::Pixels(100))
+            .preserve_aspect_ratio(false)
+            .do_not_move_cursor(true)
+            .get_options();
+        assert_eq!(
+            options.args(42, (None, Some(Iterm2Size::Cells(10)))),
+            "name=YS5wbmc=;size=42;width=100px;height=10;preserveAspectRatio=0;inline=1;doNotMoveCursor=1"
+        );
+    }
+}
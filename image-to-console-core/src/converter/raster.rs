@@ -0,0 +1,355 @@
+use super::cell::{CellGrid, colors};
+use crate::error::{ConvertError, ConvertErrorContext, ConvertErrorContextSource, ConvertResult};
+use image::{Rgba, RgbaImage};
+use std::{borrow::Cow, io::Cursor};
+
+/// A monospace bitmap font
+///
+/// Every glyph is `height` rows of `width.div_ceil(8)` bytes, the leftmost pixel is the lowest bit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitmapFont {
+    /// Width of a glyph in pixels
+    width: u32,
+    /// Height of a glyph in pixels
+    height: u32,
+    /// The character of the first glyph, the other glyphs follow it
+    first: char,
+    /// The rows of the glyphs
+    glyphs: Cow<'static, [u8]>,
+}
+
+impl Default for BitmapFont {
+    fn default() -> Self {
+        Self::font8x8()
+    }
+}
+
+impl BitmapFont {
+    /// Create a font from its glyphs
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of a glyph in pixels
+    /// * `height` - Height of a glyph in pixels
+    /// * `first` - The character of the first glyph, the next glyphs are the next characters
+    /// * `glyphs` - The rows of the glyphs, `width.div_ceil(8)` bytes per row
+    ///   with the leftmost pixel in the lowest bit
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the size is zero or `glyphs` is not made of whole glyphs
+    pub fn new(width: u32, height: u32, first: char, glyphs: Vec<u8>) -> Option<Self> {
+        let glyph_size = width.div_ceil(8) as usize * height as usize;
+        if glyph_size == 0 || glyphs.is_empty() || glyphs.len() % glyph_size != 0 {
+            return None;
+        }
+        Some(Self {
+            width,
+            height,
+            first,
+            glyphs: Cow::Owned(glyphs),
+        })
+    }
+
+    /// Get the bundled 8x8 font, the printable ASCII characters of the public domain
+    /// [font8x8](https://github.com/dhepper/font8x8)
+    ///
+    /// # Returns
+    ///
+    /// Returns the bundled font
+    pub fn font8x8() -> Self {
+        Self {
+            width: 8,
+            height: 8,
+            first: ' ',
+            glyphs: Cow::Borrowed(FONT_8X8.as_flattened()),
+        }
+    }
+
+    /// Get the width of a glyph in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get the height of a glyph in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get the rows of a glyph, `?` is used for the characters without a glyph
+    fn glyph(&self, glyph: char) -> Option<&[u8]> {
+        let glyph_size = self.width.div_ceil(8) as usize * self.height as usize;
+        let get = |glyph: char| {
+            let index = (glyph as u32).checked_sub(self.first as u32)? as usize;
+            self.glyphs
+                .get(index * glyph_size..(index + 1) * glyph_size)
+        };
+        get(glyph).or_else(|| get('?'))
+    }
+
+    /// Check whether a pixel of a glyph is set
+    fn pixel(&self, glyph: &[u8], x: u32, y: u32) -> bool {
+        let row = y as usize * self.width.div_ceil(8) as usize;
+        glyph[row + x as usize / 8] >> (x % 8) & 1 == 1
+    }
+}
+
+/// The options of the rasterizer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RasterOptions {
+    /// Width of a cell in pixels
+    pub cell_width: u32,
+    /// Height of a cell in pixels
+    pub cell_height: u32,
+    /// The font of the text glyphs, scaled to the cell
+    pub font: BitmapFont,
+    /// The default foreground color of the terminal
+    pub foreground: [u8; 3],
+    /// The default background color of the terminal
+    pub background: [u8; 3],
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        Self {
+            cell_width: 8,
+            cell_height: 16,
+            font: BitmapFont::default(),
+            foreground: [255, 255, 255],
+            background: [0, 0, 0],
+        }
+    }
+}
+
+impl RasterOptions {
+    /// Sets the size of a cell
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of a cell in pixels
+    /// * `height` - Height of a cell in pixels
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn cell_size(&mut self, width: u32, height: u32) -> &mut Self {
+        self.cell_width = width.max(1);
+        self.cell_height = height.max(1);
+        self
+    }
+
+    /// Sets the font of the text glyphs
+    ///
+    /// # Arguments
+    ///
+    /// * `font` - The bitmap font
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn font(&mut self, font: BitmapFont) -> &mut Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the default colors of the terminal, used by the cells without a color
+    ///
+    /// # Arguments
+    ///
+    /// * `foreground` - The default foreground color
+    /// * `background` - The default background color
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn default_colors(&mut self, foreground: [u8; 3], background: [u8; 3]) -> &mut Self {
+        self.foreground = foreground;
+        self.background = background;
+        self
+    }
+
+    /// Get a copy of the options
+    ///
+    /// # Returns
+    ///
+    /// Returns a clone of the options
+    pub fn get_options(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl CellGrid {
+    /// Rasterize the grid, like a terminal draws it
+    ///
+    /// The block glyphs (`▀`, `▄` and `█`) fill their part of the cell, the other glyphs
+    /// are drawn with the font. Bold glyphs are one pixel wider and underlined cells
+    /// get a line on their last row, italic is not drawn. `left` and `top` are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The cell size, the font and the default colors
+    ///
+    /// # Returns
+    ///
+    /// Returns the image of the grid
+    pub fn to_image(&self, options: &RasterOptions) -> RgbaImage {
+        let (cell_width, cell_height) = (options.cell_width.max(1), options.cell_height.max(1));
+        let font = &options.font;
+        let mut image = RgbaImage::new(self.width * cell_width, self.height * cell_height);
+        for (index, cell) in self.cells.iter().enumerate() {
+            let (x, y) = (
+                index as u32 % self.width * cell_width,
+                index as u32 / self.width * cell_height,
+            );
+            let (fg, bg) = colors(cell, (options.foreground, options.background));
+            let opaque = |[r, g, b]: [u8; 3]| Rgba([r, g, b, 255]);
+            let fg = opaque(fg.unwrap_or(options.foreground));
+            let bg = opaque(bg.unwrap_or(options.background));
+            let half = cell_height / 2;
+            let glyph = match cell.glyph {
+                '▀' | '▄' | '█' | ' ' => None,
+                glyph => font.glyph(glyph),
+            };
+            for dy in 0..cell_height {
+                for dx in 0..cell_width {
+                    let set = match cell.glyph {
+                        '▀' => dy < half,
+                        '▄' => dy >= half,
+                        '█' => true,
+                        _ => {
+                            let (fx, fy) =
+                                (dx * font.width / cell_width, dy * font.height / cell_height);
+                            glyph.is_some_and(|glyph| {
+                                font.pixel(glyph, fx, fy)
+                                    || (cell.attrs.bold && fx > 0 && font.pixel(glyph, fx - 1, fy))
+                            })
+                        }
+                    } || (cell.attrs.underline && dy == cell_height - 1);
+                    image.put_pixel(x + dx, y + dy, if set { fg } else { bg });
+                }
+            }
+        }
+        image
+    }
+
+    /// Rasterize the grid to a PNG image
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The cell size, the font and the default colors
+    ///
+    /// # Returns
+    ///
+    /// Returns the PNG data
+    pub fn to_png(&self, options: &RasterOptions) -> ConvertResult<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.to_image(options)
+            .write_to(&mut Cursor::new(&mut buffer), image::ImageFormat::Png)
+            .map_err(|e| {
+                ConvertError::ImageError(ConvertErrorContext::new(
+                    ConvertErrorContextSource::Function("to_png".to_string()),
+                    e.to_string(),
+                ))
+            })?;
+        Ok(buffer)
+    }
+}
+
+/// The printable ASCII characters of font8x8 (public domain)
+static FONT_8X8: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // !
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // "
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // #
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // $
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // %
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // &
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // '
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // (
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // )
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // *
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // +
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ,
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // .
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // /
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // 0
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // 1
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // 2
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // 3
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // 4
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // 5
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // 6
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // 7
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // 8
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // 9
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // :
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ;
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // <
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // =
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // >
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // ?
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // @
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // A
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // B
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // C
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // D
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // E
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // F
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // G
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // H
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // I
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // J
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // K
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // L
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // M
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // N
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // O
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // P
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // Q
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // R
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // S
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // T
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // U
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // V
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // W
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // X
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // Y
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // Z
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // [
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // \
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // ]
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // ^
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // _
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // `
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // a
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // b
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // c
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // d
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // e
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // f
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // g
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // h
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // i
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // j
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // k
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // l
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // m
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // n
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // o
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // p
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // q
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // r
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // s
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // t
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // u
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // v
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // w
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // x
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // y
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // z
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // {
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // |
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // }
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ~
+];
@@ -1,298 +1,255 @@
 use crate::{
     error::{ConvertError, ConvertErrorContext, ConvertErrorContextSource, ConvertResult},
-    indexed_image::IndexedImage,
+    indexed_image::{IndexedImage, SixelPalette},
 };
-use nohash_hasher::BuildNoHashHasher;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use std::collections::HashMap;
+use std::io::Write;
 
-const AIR_STYLE: &[u8; 6] = &[0u8; 6];
-
-// tool enum
-#[derive(Copy, Clone, Default)]
-pub enum ColorIndexState {
-    First(u8),
-    Same(u8),
-    #[default]
-    None,
+/// Write a decimal number without the formatting machinery
+fn write_number(data: &mut Vec<u8>, mut number: usize) {
+    let mut digits = [0u8; 20];
+    let mut start = digits.len();
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (number % 10) as u8;
+        number /= 10;
+        if number == 0 {
+            break;
+        }
+    }
+    data.extend_from_slice(&digits[start..]);
 }
 
-impl From<ColorIndexState> for Option<u8> {
-    fn from(state: ColorIndexState) -> Self {
-        match state {
-            ColorIndexState::First(index) => Some(index),
-            ColorIndexState::Same(_) => None,
-            ColorIndexState::None => None,
+/// Write `count` columns of the same sixel with run-length encoding
+fn write_run(data: &mut Vec<u8>, sixel: u8, count: usize) {
+    let char = sixel + 63;
+    match count {
+        0 => {}
+        1..=3 => data.extend(std::iter::repeat_n(char, count)),
+        _ => {
+            data.push(b'!');
+            write_number(data, count);
+            data.push(char);
         }
     }
 }
 
-impl PartialEq<u8> for ColorIndexState {
-    fn eq(&self, other: &u8) -> bool {
-        match self {
-            ColorIndexState::First(index) => index == other,
-            ColorIndexState::Same(index) => index == other,
-            ColorIndexState::None => false,
+/// Reusable buffers to encode a six-row band
+struct BandEncoder {
+    /// Sixel bits of every color, `palette_len` rows of `width` bytes
+    masks: Vec<u8>,
+    /// The first and the last column of every color in the current band, `None` if unused
+    spans: Vec<Option<(usize, usize)>>,
+}
+
+impl BandEncoder {
+    fn new(palette_len: usize, width: usize) -> Self {
+        Self {
+            masks: vec![0; palette_len * width],
+            spans: vec![None; palette_len],
         }
     }
-}
 
-impl ColorIndexState {
-    pub fn update_index(&self, index: u8) -> Self {
-        if *self == index {
-            Self::Same(index)
-        } else {
-            Self::First(index)
+    /// Encode the band starting at row `y`
+    ///
+    /// Every used color is written as one `#index` pass over the band, the passes are
+    /// separated by `$` (graphics carriage return) and the band ends with `-` (graphics new line).
+    ///
+    /// # Arguments
+    ///
+    /// * `img` - The indexed image
+    /// * `y` - The first row of the band
+    /// * `is_full` - Whether every pixel is written once (`false` writes it twice)
+    ///
+    /// # Returns
+    ///
+    /// Returns the encoded band
+    fn encode(&mut self, img: &IndexedImage, y: u32, is_full: bool) -> Vec<u8> {
+        let width = img.width as usize;
+        let rows = (img.height - y).min(6);
+        for dy in 0..rows {
+            let offset = (y + dy) as usize * width;
+            for (x, &index) in img.index_data[offset..offset + width].iter().enumerate() {
+                if img.transparent_index == Some(index) {
+                    continue;
+                }
+                let span = &mut self.spans[index as usize];
+                *span = match *span {
+                    Some((first, last)) => Some((first.min(x), last.max(x))),
+                    None => Some((x, x)),
+                };
+                self.masks[index as usize * width + x] |= 1 << dy;
+            }
+        }
+        let mut data = Vec::new();
+        let repeat = if is_full { 1 } else { 2 };
+        for index in 0..self.spans.len() {
+            let Some((first, last)) = self.spans[index].take() else {
+                continue;
+            };
+            if !data.is_empty() {
+                data.push(b'$');
+            }
+            data.push(b'#');
+            write_number(&mut data, index);
+            // Only the columns between the first and the last sixel of the color are scanned,
+            // the empty sixels at the end of the pass are not needed
+            write_run(&mut data, 0, first * repeat);
+            let masks = &mut self.masks[index * width + first..=index * width + last];
+            let mut x = 0;
+            while x < masks.len() {
+                let sixel = masks[x];
+                let mut end = x + 1;
+                while end < masks.len() && masks[end] == sixel {
+                    end += 1;
+                }
+                write_run(&mut data, sixel, (end - x) * repeat);
+                x = end;
+            }
+            masks.fill(0);
         }
+        data.push(b'-');
+        data
     }
 }
 
-// Some tool functions
-fn get_sixel(style: &[u8; 6]) -> String {
-    let mut v = 0u8;
-    for (i, item) in style.iter().enumerate() {
-        v |= item << i;
-    }
-    ((v + 63) as char).to_string()
+/// Returns the shared palette of the options if it has colors
+fn shared_palette(option: &super::ImageConverterOption) -> Option<&SixelPalette> {
+    option
+        .sixel_palette
+        .as_deref()
+        .filter(|palette| !palette.is_empty())
 }
 
-fn get_color(r: u8, g: u8, b: u8) -> String {
-    format!(
-        "{:.0};{:.0};{:.0}",
-        r as f32 / 255f32 * 100f32,
-        g as f32 / 255f32 * 100f32,
-        b as f32 / 255f32 * 100f32
+fn quantize_error(err: quantette::AboveMaxLen<u32>) -> ConvertError {
+    ConvertError::AboveMaxLength(
+        err.0,
+        ConvertErrorContext::new(ConvertErrorContextSource::SixelConvert, err.to_string())
+            .with_inner(Box::new(err)),
     )
 }
 
-fn render_same(
-    index: Option<u8>,
-    mut times: usize,
-    char: &str,
-    is_full: bool,
-    counter: &mut [usize],
-) -> (Option<u8>, String) {
-    if !is_full {
-        times *= 2;
-    }
-    match index {
-        Some(index) => {
-            counter[index as usize] += times;
-            if times == 0 {
-                (Some(index), String::new())
-            } else if times < 3 {
-                (Some(index), char.repeat(times))
-            } else {
-                (Some(index), String::from("!") + &times.to_string() + char)
-            }
-        }
-        None => {
-            if times == 0 {
-                (None, String::new())
-            } else if times < 3 {
-                (None, char.repeat(times))
-            } else {
-                (None, String::from("!") + &times.to_string() + char)
-            }
+/// Build the introducer and the raster attributes of the sixel image
+///
+/// # Arguments
+///
+/// * `img` - The indexed image
+/// * `is_full` - Whether to use the 1:1 pixel aspect ratio
+/// * `option` - The converter options
+///
+/// # Returns
+///
+/// Returns the beginning of the sixel sequence
+fn header(img: &IndexedImage, is_full: bool, option: &super::ImageConverterOption) -> String {
+    // P1 selects the pixel aspect ratio (9 is 1:1, 0 is 2:1),
+    // P2 = 1 keeps the pixels that are not painted unchanged
+    let mut result = String::from(match (is_full, img.transparent_index.is_some()) {
+        (true, _) => "\x1bP9;1q",
+        (false, true) => "\x1bP0;1q",
+        (false, false) => "\x1bPq",
+    });
+    if option.raster_attributes {
+        // Pan;Pad;Ph;Pv, the half mode doubles every pixel horizontally
+        if is_full {
+            result.push_str(&format!("\"1;1;{};{}", img.width, img.height));
+        } else {
+            result.push_str(&format!("\"2;1;{};{}", img.width * 2, img.height));
         }
     }
+    result
 }
 
+/// Quantize an RGB image and encode it with the sixel protocol
 pub fn convert(
     img: &image::RgbImage,
     is_full: bool,
     option: &super::ImageConverterOption,
 ) -> ConvertResult<Vec<String>> {
-    let img = IndexedImage::from_image(
-        img,
-        option.max_colors,
-        option.dither,
-        option.quantize_method,
-        option.color_space,
-    )
-    .map_err(|err| {
-        ConvertError::AboveMaxLength(
-            err.0,
-            ConvertErrorContext::new(ConvertErrorContextSource::SixelConvert, err.to_string())
-                .with_inner(Box::new(err)),
+    let img = match shared_palette(option) {
+        Some(palette) => IndexedImage::from_palette(img, &palette.colors(), option.dither),
+        None => IndexedImage::from_image(
+            img,
+            option.max_colors,
+            option.dither,
+            option.quantize_method,
+            option.color_space,
         )
-    })?;
-    let mut result = String::from(if is_full { "\x1bP9;1q" } else { "\x1bPq" });
-    let palette_count = img.palette.len();
-    let (width, height) = (img.width, img.height);
-    let index_counter = vec![0usize; palette_count];
-    let ptr = std::sync::Arc::new(std::sync::Mutex::new(index_counter));
-    let pixels: Vec<(Option<u8>, String)> = (0..=height / 6)
-        .into_par_iter()
-        .map(|y| {
-            if y * 6 >= height {
-                return vec![];
-            }
-            let mut line: Vec<(Option<u8>, String)> = vec![];
-            let mut col: HashMap<u32, (usize, usize), BuildNoHashHasher<u32>> =
-                (0..width).map(|i| (i, (0, 0))).collect();
-            let mut col_indexs: Vec<[i16; 6]> = vec![[-1; 6]; width as usize];
-            let mut col_index_counter = vec![0usize; palette_count];
-            let mut same_index = ColorIndexState::default();
-            while !col.is_empty() {
-                line.push((None, "$".to_string()));
-                let mut skip_count = 0;
-                let mut same_count = 0;
-                let mut same_style = [0u8; 6];
-                (0..width).for_each(|x| {
-                    if !col.contains_key(&x) {
-                        if same_count > 0 {
-                            line.push(render_same(
-                                same_index.into(),
-                                same_count,
-                                &get_sixel(&same_style),
-                                is_full,
-                                &mut col_index_counter,
-                            ));
-                            same_count = 0;
-                        }
-                        skip_count += 1;
-                        return;
-                    }
-                    if skip_count > 0 {
-                        line.push(render_same(
-                            None,
-                            skip_count,
-                            &get_sixel(AIR_STYLE),
-                            is_full,
-                            &mut col_index_counter,
-                        ));
-                        skip_count = 0;
-                    }
-                    let y = y * 6;
-                    // Get the information for this colum
-                    let (mut cur_sum, mut cur_head) = col[&x];
-                    let mut cur_indexs = col_indexs[x as usize];
-                    // Get current color index
-                    let cur_index = img.get_pixel(x, y + cur_head as u32);
-                    // Update the indexs for this colum
-                    cur_indexs[cur_head] = cur_index as i16;
-                    // Init some variable
-                    let mut style = [0u8; 6];
-                    let mut is_head = true;
-                    // Get the style and next head
-                    for dy in cur_head as u32..6 {
-                        if y + dy >= height {
-                            break;
-                        }
-                        let index = img.get_pixel(x, y + dy);
-                        if index == cur_index {
-                            cur_sum += 1;
-                            style[dy as usize] = 1;
-                        } else if is_head && !cur_indexs.contains(&(index as i16)) {
-                            // update the cur_head
-                            is_head = false;
-                            cur_head = dy as usize;
-                        }
-                    }
-                    // remove it if cur_sum >= 6, else update it
-                    if cur_sum >= 6 {
-                        col.remove(&x);
-                    } else {
-                        col.insert(x, (cur_sum, cur_head));
-                        col_indexs[x as usize] = cur_indexs;
-                    }
-                    // counter add 1 if is the same style and color index when the counter is not zero
-                    if same_count > 0 && same_index == cur_index && same_style == style {
-                        same_count += 1;
-                    } else {
-                        // This is not a simple style or color, we need write the last style and color into this line
-                        // And update this color and style to the same style and color
-                        if same_count > 0 {
-                            line.push(render_same(
-                                same_index.into(),
-                                same_count,
-                                &get_sixel(&same_style),
-                                is_full,
-                                &mut col_index_counter,
-                            ))
-                        }
-                        // Set the counter to 1
-                        same_count = 1;
-                        // update other information
-                        same_index = same_index.update_index(cur_index);
-                        same_style = style;
-                    }
-                });
-                // maybe some data in the same counter is not written
-                // so we should check the same_count here
-                // write into this line if the counter is not zero
-                if same_count > 0 {
-                    line.push(render_same(
-                        same_index.into(),
-                        same_count,
-                        &get_sixel(&same_style),
-                        is_full,
-                        &mut col_index_counter,
-                    ));
-                }
-                // And maybe some data in the skip_count is not written
-                // so we also should check the skip_count here
-                if skip_count > 0 {
-                    line.push(render_same(
-                        None,
-                        skip_count,
-                        &get_sixel(AIR_STYLE),
-                        is_full,
-                        &mut col_index_counter,
-                    ));
-                }
-            }
-            // This line is finished
-            // Goto the next line
-            line.push((None, "-".to_string()));
-            let mut r = ptr.lock().unwrap();
-            for (index, count) in col_index_counter.iter().enumerate() {
-                r[index] += *count;
-            }
-            // Return this line to collect
-            line
-        })
-        .flatten_iter()
-        .into_par_iter()
-        .collect::<Vec<(Option<u8>, String)>>();
-    let mut index_counter = ptr
-        .lock()
-        .map_err(|err| {
-            ConvertError::LockError(ConvertErrorContext::new(
-                ConvertErrorContextSource::SixelConvert,
-                err.to_string(),
-            ))
-        })?
-        .iter()
-        .enumerate()
-        .map(|(index, &count)| (index, count))
-        .collect::<Vec<(usize, usize)>>();
-    index_counter.sort_by_key(|item| std::cmp::Reverse(item.1));
-    let index_mapping: HashMap<usize, usize, BuildNoHashHasher<usize>> = index_counter
-        .iter()
-        .enumerate()
-        .map(|(index, &(i, _))| (i, index))
-        .collect();
-    let palette = index_counter
-        .iter()
-        .enumerate()
-        .map(|(index, &(i, _))| {
-            let rgb = img.palette[i];
-            format!("#{index};2;{}", get_color(rgb.red, rgb.green, rgb.blue))
-        })
-        .collect::<String>();
-    let pixels = pixels
-        .into_par_iter()
-        .map(|(index, char)| match index {
-            Some(index) => format!("#{}{char}", index_mapping[&(index as usize)]),
-            None => char,
-        })
-        .collect::<String>();
+        .map_err(quantize_error)?,
+    };
+    encode(&img, is_full, option)
+}
+
+/// Convert an RGBA image, the transparent pixels are not painted
+pub fn convert_rgba(
+    img: &image::RgbaImage,
+    is_full: bool,
+    option: &super::ImageConverterOption,
+) -> ConvertResult<Vec<String>> {
+    let img = match shared_palette(option) {
+        Some(palette) => IndexedImage::from_rgba_palette(img, &palette.colors(), option.dither),
+        None => IndexedImage::from_rgba_image(
+            img,
+            option.max_colors,
+            option.dither,
+            option.quantize_method,
+            option.color_space,
+        )
+        .map_err(quantize_error)?,
+    };
+    encode(&img, is_full, option)
+}
 
-    result.push_str(&palette);
-    result.push_str(&pixels);
-    result.push_str("\x1b\\");
+/// Encode an indexed image with the sixel protocol
+///
+/// The palette is written in the order of the indexed image, and every six-row band
+/// is encoded in parallel, so the output only depends on the input image.
+/// With a shared palette in the options, `img` must be mapped to its colors, and only
+/// the registers that are not defined in the terminal yet are written.
+///
+/// # Arguments
+///
+/// * `img` - The indexed image
+/// * `is_full` - Whether to use the 1:1 pixel aspect ratio
+/// * `option` - The converter options
+///
+/// # Returns
+///
+/// Returns a vector of strings representing the converted image
+pub fn encode(
+    img: &IndexedImage,
+    is_full: bool,
+    option: &super::ImageConverterOption,
+) -> ConvertResult<Vec<String>> {
+    let mut result = header(img, is_full, option).into_bytes();
+    let registers = match shared_palette(option) {
+        Some(palette) => palette.take_changed(),
+        None => img.palette.iter().copied().enumerate().collect(),
+    };
+    for (index, rgb) in registers {
+        let percent = |value: u8| (value as u32 * 100 + 127) / 255;
+        write!(
+            result,
+            "#{index};2;{};{};{}",
+            percent(rgb.red),
+            percent(rgb.green),
+            percent(rgb.blue)
+        )
+        .unwrap();
+    }
+    let bands = (0..img.height.div_ceil(6))
+        .into_par_iter()
+        .map_init(
+            || BandEncoder::new(img.palette.len(), img.width as usize),
+            |encoder, band| encoder.encode(img, band * 6, is_full),
+        )
+        .collect::<Vec<Vec<u8>>>();
+    result.reserve(bands.iter().map(Vec::len).sum::<usize>() + 2);
+    for band in bands {
+        result.extend_from_slice(&band);
+    }
+    result.extend_from_slice(b"\x1b\\");
+    // The sixel data only contains ASCII characters
+    let result = String::from_utf8(result).unwrap();
     let mut lines = vec![String::from(" "); 2];
     lines[0] = result;
     Ok(lines)
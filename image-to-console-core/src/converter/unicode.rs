@@ -58,7 +58,7 @@ const NO_COLOR_PIXELS: [NoColorPixel; 5] = [
     },
 ];
 
-pub fn luma_convert(luma_img: &image::GrayImage, x: u32, y: u32) -> String {
+pub fn luma_convert(luma_img: &image::GrayImage, x: u32, y: u32) -> &'static str {
     let pixel1 = luma_img.get_pixel(x, y * 2);
     let pixel2 = luma_img.get_pixel(x, y * 2 + 1);
     let p1 = pixel1.0[0] as usize;
@@ -67,29 +67,38 @@ pub fn luma_convert(luma_img: &image::GrayImage, x: u32, y: u32) -> String {
     for pixel in NO_COLOR_PIXELS.iter() {
         if pixel.sep {
             if pixel.from < p1 && p1 < pixel.to && pixel.from < p2 && p2 < pixel.to {
-                return pixel.full.to_string();
+                return pixel.full;
             } else if pixel.from < p1 && p1 < pixel.to {
-                return pixel.top.to_string();
+                return pixel.top;
             } else if pixel.from < p2 && p2 < pixel.to {
-                return pixel.bottom.to_string();
+                return pixel.bottom;
             }
         } else {
             if (pixel.from < p1 || pixel.from < p2) && (p1 < pixel.to && p2 < pixel.to) {
-                return pixel.full.to_string();
+                return pixel.full;
             }
         }
     }
     if p1 > 128 && p2 > 128 {
-        "█".to_string()
+        "█"
     } else if p1 > 128 {
-        "▀".to_string()
+        "▀"
     } else if p2 > 128 {
-        "▄".to_string()
+        "▄"
     } else {
-        " ".to_string()
+        " "
     }
 }
 
+/// The characters of the ascii mode, from the darkest to the brightest
+const ASCII_CHARS: [char; 12] = [' ', '.', ',', ':', ';', '+', '*', '?', '%', 'S', '#', '@'];
+
+/// Get the character of a luma value in the ascii mode
+pub fn ascii_char(luma: u8) -> char {
+    let unit = 256 / ASCII_CHARS.len();
+    ASCII_CHARS[(luma as usize / unit).min(ASCII_CHARS.len() - 1)]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
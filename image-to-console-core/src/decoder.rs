@@ -0,0 +1,59 @@
+pub mod kitty;
+pub mod sixel;
+
+use crate::error::{DecodeError, DecodeResult};
+use image::RgbaImage;
+
+/// Decode the first sixel or kitty image sequence in `data`
+///
+/// Anything before the sequence (e.g. the cursor movement written by `line_init`) is skipped.
+///
+/// # Arguments
+///
+/// * `data` - The terminal output containing the sequence
+///
+/// # Returns
+///
+/// Returns the decoded image
+pub fn decode(data: &[u8]) -> DecodeResult<RgbaImage> {
+    let sixel = sixel::find_start(data);
+    let kitty = kitty::find_start(data);
+    match (sixel, kitty) {
+        (Some(sixel), Some(kitty)) if kitty < sixel => kitty::decode(data),
+        (Some(_), _) => sixel::decode(data),
+        (None, Some(_)) => kitty::decode(data),
+        (None, None) => Err(DecodeError::UnknownProtocol),
+    }
+}
+
+/// Parse a decimal parameter starting at `pos`
+///
+/// # Returns
+///
+/// Returns the value (`None` if there are no digits) and the position after the digits
+fn parse_number(data: &[u8], mut pos: usize) -> (Option<u32>, usize) {
+    let mut value: Option<u32> = None;
+    while let Some(digit) = data.get(pos).filter(|c| c.is_ascii_digit()) {
+        let digit = (digit - b'0') as u32;
+        value = Some(value.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        pos += 1;
+    }
+    (value, pos)
+}
+
+/// Parse a list of decimal parameters separated by `;` starting at `pos`
+///
+/// # Returns
+///
+/// Returns the values (empty parameters are `None`) and the position after the list
+fn parse_params(data: &[u8], pos: usize) -> (Vec<Option<u32>>, usize) {
+    let mut params = Vec::new();
+    let (value, mut pos) = parse_number(data, pos);
+    params.push(value);
+    while data.get(pos) == Some(&b';') {
+        let (value, next) = parse_number(data, pos + 1);
+        params.push(value);
+        pos = next;
+    }
+    (params, pos)
+}
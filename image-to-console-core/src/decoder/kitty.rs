@@ -0,0 +1,169 @@
+use crate::error::{ConvertErrorContext, ConvertErrorContextSource, DecodeError, DecodeResult};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use image::{DynamicImage, RgbImage, RgbaImage};
+
+/// One graphics command (`ESC _ G <keys> ; <payload> ESC \`)
+struct Command<'a> {
+    /// The `key=value` pairs of the control data
+    keys: Vec<(&'a [u8], &'a [u8])>,
+    payload: &'a [u8],
+}
+
+impl Command<'_> {
+    fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.keys
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| *value)
+    }
+
+    fn get_number(&self, key: &[u8], offset: usize) -> DecodeResult<Option<u32>> {
+        self.get(key)
+            .map(|value| {
+                std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| DecodeError::InvalidSequence {
+                        offset,
+                        message: format!("invalid value of `{}`", String::from_utf8_lossy(key)),
+                    })
+            })
+            .transpose()
+    }
+}
+
+/// Find the beginning of a kitty graphics command
+///
+/// # Returns
+///
+/// Returns the position of the introducer, `None` if there is no graphics command
+pub(super) fn find_start(data: &[u8]) -> Option<usize> {
+    data.windows(3).position(|window| window == b"\x1b_G")
+}
+
+/// Parse the graphics command starting at `start`
+///
+/// # Returns
+///
+/// Returns the command and the position after its terminator
+fn parse_command(data: &[u8], start: usize) -> DecodeResult<(Command<'_>, usize)> {
+    let body = start + 3;
+    let end = data[body..]
+        .windows(2)
+        .position(|window| window == b"\x1b\\")
+        .map(|end| body + end)
+        .ok_or(DecodeError::InvalidSequence {
+            offset: start,
+            message: String::from("the command is not terminated"),
+        })?;
+    let (control, payload) = match data[body..end].iter().position(|&c| c == b';') {
+        Some(split) => (&data[body..body + split], &data[body + split + 1..end]),
+        None => (&data[body..end], &data[end..end]),
+    };
+    let keys = control
+        .split(|&c| c == b',')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let split = pair.iter().position(|&c| c == b'=').unwrap_or(pair.len());
+            (&pair[..split], pair.get(split + 1..).unwrap_or_default())
+        })
+        .collect();
+    Ok((Command { keys, payload }, end + 2))
+}
+
+fn image_error(message: &str, inner: image::ImageError) -> DecodeError {
+    DecodeError::ImageError(
+        ConvertErrorContext::new(
+            ConvertErrorContextSource::Function(String::from("kitty::decode")),
+            message.to_string(),
+        )
+        .with_inner(Box::new(inner)),
+    )
+}
+
+/// Decode the first image transmitted with the kitty graphics protocol in `data`
+///
+/// The chunks of the image (`m=1`) are joined, and the payload is loaded with its format
+/// (`f=100` PNG, `f=24` RGB or `f=32` RGBA). Only the direct transmission (`t=d`)
+/// without compression is supported, the shared memory written by the `KittyShm` mode
+/// is only valid in the process that wrote it.
+///
+/// # Arguments
+///
+/// * `data` - The data containing the graphics commands, anything before them is skipped
+///
+/// # Returns
+///
+/// Returns the decoded image
+pub fn decode(data: &[u8]) -> DecodeResult<RgbaImage> {
+    let first_start = find_start(data).ok_or(DecodeError::UnknownProtocol)?;
+    let (first, mut pos) = parse_command(data, first_start)?;
+    match first.get(b"t") {
+        None | Some(b"d") => {}
+        Some(medium) => {
+            return Err(DecodeError::Unsupported(format!(
+                "transmission medium `{}`",
+                String::from_utf8_lossy(medium)
+            )));
+        }
+    }
+    if first.get(b"o").is_some() {
+        return Err(DecodeError::Unsupported(String::from("compression")));
+    }
+    let format = first.get_number(b"f", first_start)?.unwrap_or(32);
+    let width = first.get_number(b"s", first_start)?;
+    let height = first.get_number(b"v", first_start)?;
+
+    // Every chunk is encoded on its own, so they are decoded one by one
+    let decode_chunk = |payload: &[u8], offset: usize| {
+        STANDARD
+            .decode(payload)
+            .map_err(|e| DecodeError::InvalidSequence {
+                offset,
+                message: e.to_string(),
+            })
+    };
+    let mut image_data = decode_chunk(first.payload, first_start)?;
+    let mut more = first.get(b"m") == Some(&b"1"[..]);
+    while more {
+        let start = pos;
+        if data.get(start..start + 3) != Some(&b"\x1b_G"[..]) {
+            return Err(DecodeError::InvalidSequence {
+                offset: start,
+                message: String::from("expect the next chunk"),
+            });
+        }
+        let (chunk, next) = parse_command(data, start)?;
+        image_data.extend(decode_chunk(chunk.payload, start)?);
+        more = chunk.get(b"m") == Some(&b"1"[..]);
+        pos = next;
+    }
+
+    let raw_size = || match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height)),
+        _ => Err(DecodeError::InvalidSequence {
+            offset: first_start,
+            message: String::from("the raw formats need `s` and `v`"),
+        }),
+    };
+    let wrong_size = || DecodeError::InvalidSequence {
+        offset: first_start,
+        message: String::from("the size of the data does not match `s` and `v`"),
+    };
+    match format {
+        100 => image::load_from_memory_with_format(&image_data, image::ImageFormat::Png)
+            .map(|img| img.to_rgba8())
+            .map_err(|e| image_error("Failed to load the PNG data", e)),
+        24 => {
+            let (width, height) = raw_size()?;
+            RgbImage::from_raw(width, height, image_data)
+                .map(|img| DynamicImage::ImageRgb8(img).to_rgba8())
+                .ok_or_else(wrong_size)
+        }
+        32 => {
+            let (width, height) = raw_size()?;
+            RgbaImage::from_raw(width, height, image_data).ok_or_else(wrong_size)
+        }
+        format => Err(DecodeError::Unsupported(format!("format f={format}"))),
+    }
+}
@@ -0,0 +1,289 @@
+use super::{parse_number, parse_params};
+use crate::error::{DecodeError, DecodeResult};
+use image::RgbaImage;
+
+/// The largest width or height accepted by the decoder
+const MAX_SIZE: usize = 16384;
+
+/// The largest image the raster attributes alone can declare, the size of a 4K screen
+///
+/// The declared size costs a few bytes, a larger image is cropped to the painted pixels.
+const MAX_DECLARED_PIXELS: usize = 3840 * 2160;
+
+/// The number of color registers
+const REGISTERS: usize = 1024;
+
+/// The default color registers of the VT340 in percent, the other registers start black
+const DEFAULT_PALETTE: [[u32; 3]; 16] = [
+    [0, 0, 0],
+    [20, 20, 80],
+    [80, 13, 13],
+    [20, 80, 20],
+    [80, 20, 80],
+    [20, 80, 80],
+    [80, 80, 20],
+    [53, 53, 53],
+    [26, 26, 26],
+    [33, 33, 60],
+    [60, 26, 26],
+    [33, 60, 33],
+    [60, 33, 60],
+    [33, 60, 60],
+    [60, 60, 33],
+    [80, 80, 80],
+];
+
+fn invalid(offset: usize, message: &str) -> DecodeError {
+    DecodeError::InvalidSequence {
+        offset,
+        message: message.to_string(),
+    }
+}
+
+fn percent(value: u32) -> u8 {
+    ((value.min(100) * 255 + 50) / 100) as u8
+}
+
+/// Convert a DEC HLS color to RGB
+///
+/// The hue of DEC HLS starts at blue (0°), red is at 120° and green at 240°.
+fn hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> [u8; 3] {
+    let hue = ((hue + 240) % 360) as f32 / 360.0;
+    let lightness = lightness.min(100) as f32 / 100.0;
+    let saturation = saturation.min(100) as f32 / 100.0;
+    if saturation == 0.0 {
+        let value = (lightness * 255.0).round() as u8;
+        return [value; 3];
+    }
+    let q = if lightness < 0.5 {
+        lightness * (1.0 + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2.0 * lightness - q;
+    let channel = |t: f32| {
+        let t = t.rem_euclid(1.0);
+        let value = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (value * 255.0).round() as u8
+    };
+    [
+        channel(hue + 1.0 / 3.0),
+        channel(hue),
+        channel(hue - 1.0 / 3.0),
+    ]
+}
+
+/// The pixels painted so far, grown on demand
+struct Canvas {
+    width: usize,
+    height: usize,
+    /// RGBA pixels, the alpha of unpainted pixels is 0
+    pixels: Vec<[u8; 4]>,
+    /// The size of the painted area
+    extent: (usize, usize),
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0; 4]; width * height],
+            extent: (0, 0),
+        }
+    }
+
+    /// Make sure the canvas is at least `width` x `height`
+    fn reserve(&mut self, width: usize, height: usize, offset: usize) -> DecodeResult<()> {
+        if width <= self.width && height <= self.height {
+            return Ok(());
+        }
+        if width > MAX_SIZE || height > MAX_SIZE {
+            return Err(invalid(offset, "the image is too large"));
+        }
+        // Grow geometrically, the final image is cropped to the painted area
+        let new_width = width.max(self.width.min(MAX_SIZE / 2) * 2);
+        let new_height = height.max(self.height.min(MAX_SIZE / 2) * 2);
+        let mut pixels = vec![[0; 4]; new_width * new_height];
+        for y in 0..self.height {
+            pixels[y * new_width..y * new_width + self.width]
+                .copy_from_slice(&self.pixels[y * self.width..(y + 1) * self.width]);
+        }
+        self.width = new_width;
+        self.height = new_height;
+        self.pixels = pixels;
+        Ok(())
+    }
+
+    /// Paint `count` columns of the same sixel starting at (`x`, `y`)
+    fn paint(
+        &mut self,
+        x: usize,
+        y: usize,
+        sixel: u8,
+        count: usize,
+        color: [u8; 3],
+        offset: usize,
+    ) -> DecodeResult<()> {
+        if sixel == 0 {
+            return Ok(());
+        }
+        // The number of rows up to the highest bit
+        let rows = 8 - sixel.leading_zeros() as usize;
+        self.reserve(x + count, y + rows, offset)?;
+        for row in 0..rows {
+            if sixel & (1 << row) == 0 {
+                continue;
+            }
+            let start = (y + row) * self.width + x;
+            self.pixels[start..start + count].fill([color[0], color[1], color[2], 255]);
+        }
+        self.extent = (self.extent.0.max(x + count), self.extent.1.max(y + rows));
+        Ok(())
+    }
+
+    fn into_image(self, width: usize, height: usize) -> RgbaImage {
+        RgbaImage::from_fn(width as u32, height as u32, |x, y| {
+            let (x, y) = (x as usize, y as usize);
+            if x < self.width && y < self.height {
+                image::Rgba(self.pixels[y * self.width + x])
+            } else {
+                image::Rgba([0; 4])
+            }
+        })
+    }
+}
+
+/// Find the beginning of a sixel sequence (`ESC P ... q` or the 8-bit `DCS ... q`)
+///
+/// # Returns
+///
+/// Returns the position of the introducer, `None` if there is no sixel sequence
+pub(super) fn find_start(data: &[u8]) -> Option<usize> {
+    (0..data.len()).find(|&pos| {
+        let params = match data[pos] {
+            0x1b if data.get(pos + 1) == Some(&b'P') => pos + 2,
+            0x90 => pos + 1,
+            _ => return false,
+        };
+        let (_, end) = parse_params(data, params);
+        data.get(end) == Some(&b'q')
+    })
+}
+
+/// Decode the first sixel sequence in `data`
+///
+/// The image has the resolution of the sixel grid, the pixel aspect ratio is not applied,
+/// so an image written in the half mode is twice as wide as the source image.
+/// The size is taken from the raster attributes if there are any, and extended to every
+/// painted pixel. A declared size above a 4K screen that is larger than the painted area is
+/// ignored, so a short sequence can not allocate a huge image. Pixels that are never painted
+/// are transparent, whatever the background selector (P2) is.
+///
+/// # Arguments
+///
+/// * `data` - The data containing the sixel sequence, anything before it is skipped
+///
+/// # Returns
+///
+/// Returns the decoded image
+pub fn decode(data: &[u8]) -> DecodeResult<RgbaImage> {
+    let start = find_start(data).ok_or(DecodeError::UnknownProtocol)?;
+    let params = if data[start] == 0x1b {
+        start + 2
+    } else {
+        start + 1
+    };
+    // The introducer was already checked by `find_start`
+    let (_, mut pos) = parse_params(data, params);
+    pos += 1;
+
+    let mut palette = vec![[0u8; 3]; REGISTERS];
+    for (register, color) in palette.iter_mut().zip(DEFAULT_PALETTE) {
+        *register = color.map(percent);
+    }
+    let mut color = palette[0];
+    let mut raster = (0, 0);
+    let mut canvas = Canvas::new(0, 0);
+    let (mut x, mut y) = (0, 0);
+
+    loop {
+        let Some(&byte) = data.get(pos) else {
+            return Err(invalid(pos, "the sequence is not terminated"));
+        };
+        match byte {
+            b'?'..=b'~' => {
+                canvas.paint(x, y, byte - b'?', 1, color, pos)?;
+                x += 1;
+                pos += 1;
+            }
+            b'!' => {
+                let (count, next) = parse_number(data, pos + 1);
+                let Some(&sixel @ b'?'..=b'~') = data.get(next) else {
+                    return Err(invalid(next, "expect a sixel after the repeat count"));
+                };
+                let count = count.unwrap_or(1).max(1) as usize;
+                if x + count > MAX_SIZE {
+                    return Err(invalid(pos, "the image is too large"));
+                }
+                canvas.paint(x, y, sixel - b'?', count, color, pos)?;
+                x += count;
+                pos = next + 1;
+            }
+            b'#' => {
+                let (params, next) = parse_params(data, pos + 1);
+                let index = params[0].unwrap_or(0) as usize;
+                if index >= REGISTERS {
+                    return Err(invalid(pos, "the color register is out of range"));
+                }
+                if params.len() >= 5 {
+                    let [px, py, pz] = [params[2], params[3], params[4]].map(|v| v.unwrap_or(0));
+                    palette[index] = match params[1] {
+                        Some(1) => hls_to_rgb(px, py, pz),
+                        Some(2) => [percent(px), percent(py), percent(pz)],
+                        _ => return Err(DecodeError::Unsupported(String::from("color space"))),
+                    };
+                }
+                color = palette[index];
+                pos = next;
+            }
+            b'"' => {
+                let (params, next) = parse_params(data, pos + 1);
+                let size = |index: usize| params.get(index).copied().flatten().unwrap_or(0);
+                raster = (size(2) as usize, size(3) as usize);
+                if raster.0 > MAX_SIZE || raster.1 > MAX_SIZE {
+                    return Err(invalid(pos, "the image is too large"));
+                }
+                pos = next;
+            }
+            b'$' => {
+                x = 0;
+                pos += 1;
+            }
+            b'-' => {
+                x = 0;
+                y += 6;
+                pos += 1;
+            }
+            0x1b if data.get(pos + 1) == Some(&b'\\') => break,
+            0x9c => break,
+            b' ' | b'\t' | b'\r' | b'\n' => pos += 1,
+            _ => return Err(invalid(pos, "unexpected byte")),
+        }
+    }
+
+    let (width, height) = canvas.extent;
+    let declared = (raster.0.max(width), raster.1.max(height));
+    if declared.0 * declared.1 <= MAX_DECLARED_PIXELS.max(width * height) {
+        return Ok(canvas.into_image(declared.0, declared.1));
+    }
+    Ok(canvas.into_image(width, height))
+}
@@ -85,6 +85,8 @@ pub enum ConvertError {
     /// An error related with image
     ImageError(ConvertErrorContext),
     OSError(ConvertErrorContext),
+    /// The display mode cannot be converted this way, e.g. a graphics protocol to cells
+    UnsupportedMode(String),
 }
 
 impl Display for ConvertError {
@@ -105,6 +107,7 @@ impl Display for ConvertError {
             ConvertError::LockError(context) => write!(f, "{}", context.message),
             ConvertError::ImageError(context) => write!(f, "{}", context.message),
             ConvertError::OSError(context) => write!(f, "{}", context.message),
+            ConvertError::UnsupportedMode(mode) => write!(f, "Unsupported display mode {mode}"),
         }
     }
 }
@@ -114,6 +117,7 @@ impl Error for ConvertError {
         match self {
             Self::EmptyData
             | Self::GetTerminalSizeError
+            | Self::UnsupportedMode(_)
             | Self::WrongImageType {
                 actual_type: _,
                 expect_type: _,
@@ -131,3 +135,49 @@ impl Error for ConvertError {
 
 /// A type alias for `Result<T, ConvertError>`
 pub type ConvertResult<T> = Result<T, ConvertError>;
+
+/// Represents all possible errors that can occur while decoding a terminal image sequence
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    /// The data does not contain a supported image sequence
+    UnknownProtocol,
+    /// The sequence is malformed
+    InvalidSequence {
+        /// The byte offset of the error in the input data
+        offset: usize,
+        /// A descriptive message explaining the error
+        message: String,
+    },
+    /// The sequence is valid, but uses a feature the decoder does not support
+    Unsupported(String),
+    /// The payload of the sequence could not be loaded as an image
+    ImageError(ConvertErrorContext),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnknownProtocol => write!(f, "No supported image sequence found"),
+            DecodeError::InvalidSequence { offset, message } => {
+                write!(f, "Invalid sequence at byte {offset}: {message}")
+            }
+            DecodeError::Unsupported(feature) => write!(f, "Unsupported {feature}"),
+            DecodeError::ImageError(context) => write!(f, "{}", context.message),
+        }
+    }
+}
+
+impl Error for DecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::UnknownProtocol | Self::InvalidSequence { .. } | Self::Unsupported(_) => None,
+            Self::ImageError(context) => context
+                .inner
+                .as_ref()
+                .map(|e| e.as_ref() as &(dyn Error + 'static)),
+        }
+    }
+}
+
+/// A type alias for `Result<T, DecodeError>`
+pub type DecodeResult<T> = Result<T, DecodeError>;
@@ -1,21 +1,42 @@
 use gif::DisposalMethod;
-use image::{DynamicImage, ImageBuffer, Rgba};
+use image::{
+    ImageError, ImageFormat, ImageResult, Rgba, RgbaImage,
+    error::{DecodingError, ImageFormatHint},
+    imageops,
+};
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+const TRANSPARENT: Rgba<u8> = Rgba([0, 0, 0, 0]);
 
 /// Processes GIF frames to create a coherent animation
 ///
 /// This handles GIF frame disposal methods and maintains the correct frame state
 /// throughout the animation sequence.
+///
+/// The canvas starts as the background. The background is the color of the background index in
+/// the global palette, or transparent below a frame with a transparent color (as browsers do).
 pub struct GifFrameProcessor {
     /// Global color palette for the GIF
     global_palette: Option<Vec<u8>>,
+    /// Background color index in the global palette
+    background_index: Option<usize>,
+    /// Whether a frame was processed
+    started: bool,
     /// Disposal method used for the last frame
     last_disposal: DisposalMethod,
-    /// Area (left, top, width, height) occupied by the last frame
+    /// Whether the last frame has a transparent color
+    last_transparent: bool,
+    /// Area (left, top, width, height) occupied by the last frame, inside the canvas
     last_frame_area: (u32, u32, u32, u32),
     /// Current canvas storing the accumulated image state
-    canvas: ImageBuffer<Rgba<u8>, Vec<u8>>,
-    /// Previous canvas state for DisposalMethod::Previous
-    previous_canvas: Option<ImageBuffer<Rgba<u8>, Vec<u8>>>,
+    canvas: RgbaImage,
+    /// The area of the canvas below the last frame before it was drawn, for
+    /// DisposalMethod::Previous
+    previous_area: Option<RgbaImage>,
 }
 
 impl GifFrameProcessor {
@@ -33,29 +54,89 @@ impl GifFrameProcessor {
     pub fn new(width: u32, height: u32, global_palette: Option<Vec<u8>>) -> Self {
         Self {
             global_palette,
-            previous_canvas: None,
-            last_frame_area: (0, 0, 0, 0),
-            last_disposal: DisposalMethod::Any,
-            canvas: ImageBuffer::new(width, height),
+            background_index: None,
+            started: false,
+            previous_area: None,
+            last_frame_area: (0, 0, width, height),
+            last_disposal: DisposalMethod::Background,
+            last_transparent: false,
+            canvas: RgbaImage::new(width, height),
+        }
+    }
+
+    /// Set the background color index of the logical screen
+    ///
+    /// # Arguments
+    ///
+    /// * `background_index` - The index in the global palette, `None` for a transparent
+    ///   background
+    ///
+    /// # Returns
+    ///
+    /// Returns the processor itself for chaining
+    pub fn background_index(&mut self, background_index: Option<usize>) -> &mut Self {
+        self.background_index = background_index;
+        self
+    }
+
+    /// Get the current canvas
+    pub fn canvas(&self) -> &RgbaImage {
+        &self.canvas
+    }
+
+    /// Take the canvas with the last frame drawn
+    ///
+    /// The canvas is only copied if the next frame is drawn over it. When the last frame covers
+    /// the canvas and is disposed to the background or to the previous canvas, the disposal
+    /// replaces every pixel, so the canvas is moved out and [`canvas`](Self::canvas) is blank
+    /// until the next frame.
+    ///
+    /// # Returns
+    ///
+    /// Returns the canvas
+    pub fn take_canvas(&mut self) -> RgbaImage {
+        let (width, height) = self.canvas.dimensions();
+        let covered = self.last_frame_area == (0, 0, width, height);
+        match self.last_disposal {
+            DisposalMethod::Background | DisposalMethod::Previous if covered => {
+                std::mem::replace(&mut self.canvas, RgbaImage::new(width, height))
+            }
+            _ => self.canvas.clone(),
         }
     }
 
+    /// Get the background color
+    ///
+    /// # Arguments
+    ///
+    /// * `transparent` - Whether the frame that is disposed has a transparent color
+    fn background(&self, transparent: bool) -> Rgba<u8> {
+        if transparent {
+            return TRANSPARENT;
+        }
+        self.background_index
+            .zip(self.global_palette.as_ref())
+            .and_then(|(index, palette)| palette.get(index * 3..index * 3 + 3))
+            .map_or(TRANSPARENT, |rgb| Rgba([rgb[0], rgb[1], rgb[2], 255]))
+    }
+
     /// Clean the canvas according to the last frame's disposal method
     fn clean_canvas(&mut self) {
+        let (left, top, width, height) = self.last_frame_area;
         match self.last_disposal {
             DisposalMethod::Background => {
-                // Clean up the area occupied by the previous frame to be transparent
-                let (left, top, w, h) = self.last_frame_area;
-                for y in top..(top + h) {
-                    for x in left..(left + w) {
-                        self.canvas.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+                // Clean up the area occupied by the previous frame to the background
+                let background = self.background(self.last_transparent);
+                for y in top..top + height {
+                    for x in left..left + width {
+                        self.canvas.put_pixel(x, y, background);
                     }
                 }
             }
             DisposalMethod::Previous => {
                 // Reverts to the state it was in before the last frame was drawn
-                if let Some(prev) = &self.previous_canvas {
-                    self.canvas = prev.clone();
+                if let Some(previous) = self.previous_area.take() {
+                    imageops::replace(&mut self.canvas, &previous, left as i64, top as i64);
                 }
             }
             _ => { /* Any or Keep，do nothing */ }
@@ -64,59 +145,200 @@ impl GifFrameProcessor {
 
     /// Process a GIF frame and return the resulting image
     ///
+    /// The buffer of the frame holds palette indices, as decoded by [`gif::Decoder`] with
+    /// [`gif::ColorOutput::Indexed`]. The rows are in interlaced order if `frame.interlaced` is set
+    /// (the decoder deinterlaces the rows and clears it). The parts of the frame outside the canvas
+    /// are not drawn.
+    ///
     /// # Arguments
     ///
     /// * `frame` - The GIF frame to process
     ///
     /// # Returns
     ///
-    /// Returns the processed frame as a DynamicImage
-    pub fn process_frame(&mut self, frame: &gif::Frame) -> DynamicImage {
+    /// Returns the canvas with the frame drawn, or an error if the frame has no palette, a color
+    /// index is outside the palette or the buffer is shorter than the frame
+    pub fn process_frame(&mut self, frame: &gif::Frame) -> ImageResult<&RgbaImage> {
+        if frame.palette.is_none() && self.global_palette.is_none() {
+            return Err(format_error("the frame has no color palette"));
+        }
+        let (frame_width, frame_height) = (frame.width as usize, frame.height as usize);
+        if frame.buffer.len() < frame_width * frame_height {
+            return Err(format_error("the frame data is shorter than the frame"));
+        }
+
+        if !self.started {
+            // The canvas starts as the background of the first frame
+            self.last_transparent = frame.transparent.is_some();
+            self.started = true;
+        }
         self.clean_canvas();
+
+        let left = (frame.left as u32).min(self.canvas.width());
+        let top = (frame.top as u32).min(self.canvas.height());
+        let width = (frame.width as u32).min(self.canvas.width() - left);
+        let height = (frame.height as u32).min(self.canvas.height() - top);
         if frame.dispose == DisposalMethod::Previous {
-            self.previous_canvas = Some(self.canvas.clone());
+            self.previous_area =
+                Some(imageops::crop_imm(&self.canvas, left, top, width, height).to_image());
         }
 
         let palette = frame
             .palette
-            .as_ref()
-            .or(self.global_palette.as_ref())
-            .unwrap();
-        let transparent_index = frame.transparent;
-        for y in 0..frame.height {
-            for x in 0..frame.width {
-                let index_in_frame = (y as usize) * (frame.width as usize) + (x as usize);
-                let color_index = frame.buffer[index_in_frame];
-
+            .as_deref()
+            .or(self.global_palette.as_deref())
+            .unwrap_or_default();
+        let rows = if frame.interlaced {
+            interlaced_rows(frame_height)
+        } else {
+            (0..frame_height).collect()
+        };
+        for y in 0..height {
+            let row = &frame.buffer[rows[y as usize] * frame_width..][..width as usize];
+            for (x, &color_index) in (0..width).zip(row) {
                 // Skip the transparent color
-                if Some(color_index) == transparent_index {
+                if Some(color_index) == frame.transparent {
                     continue;
                 }
+                let index = color_index as usize * 3;
+                let rgb = palette.get(index..index + 3).ok_or_else(|| {
+                    format_error(&format!(
+                        "the color index {color_index} is outside the palette"
+                    ))
+                })?;
+                self.canvas
+                    .put_pixel(left + x, top + y, Rgba([rgb[0], rgb[1], rgb[2], 255]));
+            }
+        }
+        self.last_disposal = frame.dispose;
+        self.last_transparent = frame.transparent.is_some();
+        self.last_frame_area = (left, top, width, height);
+        Ok(&self.canvas)
+    }
+}
 
-                // Get RGBA colors from the palette
-                let (r, g, b) = (
-                    palette[color_index as usize * 3],
-                    palette[color_index as usize * 3 + 1],
-                    palette[color_index as usize * 3 + 2],
-                );
+/// A GIF decoded by the `gif` crate, the frames are composed by a [`GifFrameProcessor`]
+///
+/// The frames are decoded as indexed colors, so the global palette of the file is kept.
+pub struct GifAnimation<R: Read> {
+    decoder: gif::Decoder<R>,
+    processor: GifFrameProcessor,
+}
 
-                // Coordinates are calculated on the main canvas
-                let canvas_x = frame.left as u32 + x as u32;
-                let canvas_y = frame.top as u32 + y as u32;
+impl GifAnimation<BufReader<File>> {
+    /// Open a GIF file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the GIF file
+    ///
+    /// # Returns
+    ///
+    /// Returns the animation before the first frame, or the error of reading the header
+    pub fn open(path: impl AsRef<Path>) -> ImageResult<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
 
-                if canvas_x < self.canvas.width() && canvas_y < self.canvas.height() {
-                    self.canvas
-                        .put_pixel(canvas_x, canvas_y, Rgba([r, g, b, 255]));
-                }
+impl<R: Read> GifAnimation<R> {
+    /// Read the header of a GIF
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader of the GIF
+    ///
+    /// # Returns
+    ///
+    /// Returns the animation before the first frame, or the error of reading the header
+    pub fn new(reader: R) -> ImageResult<Self> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let decoder = options.read_info(reader).map_err(decoding_error)?;
+        let mut processor = GifFrameProcessor::new(
+            decoder.width() as u32,
+            decoder.height() as u32,
+            decoder.global_palette().map(|p| p.to_vec()),
+        );
+        processor.background_index(decoder.bg_color());
+        Ok(Self { decoder, processor })
+    }
+
+    /// Get the global palette of the GIF as RGB triples
+    pub fn global_palette(&self) -> Option<&[u8]> {
+        self.decoder.global_palette()
+    }
+}
+
+#[cfg(feature = "animation")]
+impl<R: Read> crate::animation::AnimationSource for GifAnimation<R> {
+    fn next_frame(&mut self) -> Option<ImageResult<(image::RgbaImage, std::time::Duration)>> {
+        match self.decoder.read_next_frame() {
+            Ok(Some(frame)) => {
+                let delay = std::time::Duration::from_millis(frame.delay as u64 * 10);
+                let processed = self.processor.process_frame(frame).map(|_| ());
+                Some(processed.map(|()| (self.processor.take_canvas(), delay)))
             }
+            Ok(None) => None,
+            Err(err) => Some(Err(decoding_error(err))),
         }
-        self.last_disposal = frame.dispose;
-        self.last_frame_area = (
-            frame.left as u32,
-            frame.top as u32,
-            frame.width as u32,
-            frame.height as u32,
-        );
-        DynamicImage::from(self.canvas.clone())
+    }
+
+    /// The NETSCAPE loop count is the number of times the GIF is repeated after the first time,
+    /// 0 loops forever
+    fn loop_count(&self) -> image::metadata::LoopCount {
+        use image::metadata::LoopCount;
+        match self.decoder.repeat() {
+            gif::Repeat::Infinite => LoopCount::Infinite,
+            gif::Repeat::Finite(repeat) => {
+                LoopCount::Finite(std::num::NonZeroU32::MIN.saturating_add(repeat as u32))
+            }
+        }
+    }
+
+    fn is_gif(&self) -> bool {
+        true
+    }
+}
+
+/// Get where each row of an interlaced frame is stored
+///
+/// The rows are stored in four passes: every 8th row from row 0, every 8th row from row 4, every
+/// 4th row from row 2 and every 2nd row from row 1.
+///
+/// # Arguments
+///
+/// * `height` - The height of the frame
+///
+/// # Returns
+///
+/// Returns the index in the buffer of each row, from top to bottom
+fn interlaced_rows(height: usize) -> Vec<usize> {
+    let mut rows = vec![0; height];
+    let passes = [(0, 8), (4, 8), (2, 4), (1, 2)];
+    let order = passes
+        .into_iter()
+        .flat_map(|(start, step)| (start..height).step_by(step));
+    for (index, row) in order.enumerate() {
+        rows[row] = index;
+    }
+    rows
+}
+
+/// Create an error of a malformed GIF
+fn format_error(message: &str) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Exact(ImageFormat::Gif),
+        message,
+    ))
+}
+
+/// Convert an error of the `gif` crate
+fn decoding_error(err: gif::DecodingError) -> ImageError {
+    match err {
+        gif::DecodingError::Io(err) => ImageError::IoError(err),
+        err => ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Exact(ImageFormat::Gif),
+            err,
+        )),
     }
 }
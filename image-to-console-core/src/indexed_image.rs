@@ -1,4 +1,47 @@
-use quantette::{AboveMaxLen, palette::{encoding::Srgb, rgb::Rgb}, ColorSpace};
+use quantette::{
+    AboveMaxLen, ColorSpace,
+    palette::{cast::from_component_slice, encoding::Srgb, rgb::Rgb},
+};
+use std::{collections::HashMap, sync::Mutex};
+
+/// Whether the pixel is treated as transparent (alpha below 128)
+fn is_transparent(pixel: &image::Rgba<u8>) -> bool {
+    pixel.0[3] < 128
+}
+
+/// Fill the transparent pixels with the last opaque color in the row,
+/// so they don't take a color of the palette
+fn fill_transparent(img: &image::RgbaImage) -> image::RgbImage {
+    let mut rgb = image::RgbImage::new(img.width(), img.height());
+    for (row, target) in img.rows().zip(rgb.rows_mut()) {
+        let mut last = row
+            .clone()
+            .find(|pixel| !is_transparent(pixel))
+            .map(|pixel| [pixel.0[0], pixel.0[1], pixel.0[2]])
+            .unwrap_or_default();
+        for (pixel, target) in row.zip(target) {
+            if !is_transparent(pixel) {
+                last = [pixel.0[0], pixel.0[1], pixel.0[2]];
+            }
+            target.0 = last;
+        }
+    }
+    rgb
+}
+
+/// Find the index of the nearest color in the palette
+fn nearest_color(palette: &[Rgb<Srgb, u8>], color: &Rgb<Srgb, u8>) -> u8 {
+    let distance = |other: &Rgb<Srgb, u8>| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(color.red, other.red) + d(color.green, other.green) + d(color.blue, other.blue)
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, other)| distance(other))
+        .map(|(index, _)| index as u8)
+        .unwrap_or_default()
+}
 
 /// An image represented with a limited color palette (indexed image)
 /// 
@@ -14,6 +57,8 @@ pub struct IndexedImage {
     pub width: u32,
     /// Image height in pixels
     pub height: u32,
+    /// The reserved index of the transparent pixels, it is not a valid index of the palette
+    pub transparent_index: Option<u8>,
 }
 
 impl IndexedImage {
@@ -49,9 +94,132 @@ impl IndexedImage {
             index_data,
             width,
             height,
+            transparent_index: None,
         })
     }
 
+    /// Create an indexed image from an RGBA image
+    ///
+    /// The pixels with an alpha value below 128 are transparent. They are mapped to a
+    /// reserved index right after the palette, so one slot of `max_colors` is kept for them.
+    /// If there is no transparent pixel, it is the same as [`IndexedImage::from_image`].
+    ///
+    /// # Arguments
+    ///
+    /// * `img` - The source RGBA image
+    /// * `max_colors` - Maximum number of colors, including the reserved transparent slot
+    /// * `dither` - Whether to apply dithering during quantization
+    ///
+    /// # Returns
+    ///
+    /// Returns the indexed image or an error if the image is too large
+    pub fn from_rgba_image(
+        img: &image::RgbaImage,
+        max_colors: u16,
+        dither: bool,
+        dither_method: quantette::QuantizeMethod,
+        color_space: ColorSpace,
+    ) -> Result<Self, AboveMaxLen<u32>> {
+        if !img.pixels().any(is_transparent) {
+            let rgb = image::DynamicImage::from(img.clone()).to_rgb8();
+            return Self::from_image(&rgb, max_colors, dither, dither_method, color_space);
+        }
+        let rgb = fill_transparent(img);
+        let mut indexed = Self::from_image(
+            &rgb,
+            max_colors.saturating_sub(1).max(1),
+            dither,
+            dither_method,
+            color_space,
+        )?;
+        indexed.mark_transparent(img);
+        Ok(indexed)
+    }
+
+    /// Create an indexed image with the colors of an existing palette
+    ///
+    /// Every pixel is mapped to the nearest color of the palette, it is used to keep
+    /// the colors of the frames of an animation the same (see [`SixelPalette`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `img` - The source RGB image
+    /// * `palette` - The palette to use, it must not be empty
+    /// * `dither` - Whether to apply dithering
+    ///
+    /// # Returns
+    ///
+    /// Returns the indexed image
+    pub fn from_palette(img: &image::RgbImage, palette: &[Rgb<Srgb, u8>], dither: bool) -> Self {
+        let (width, height) = img.dimensions();
+        let colors: &[Rgb<Srgb, u8>] = from_component_slice(img.as_raw());
+        let mut cache = HashMap::new();
+        let mut index_data = colors
+            .iter()
+            .map(|color| {
+                *cache
+                    .entry([color.red, color.green, color.blue])
+                    .or_insert_with(|| nearest_color(palette, color))
+            })
+            .collect::<Vec<u8>>();
+        if dither {
+            quantette::FloydSteinberg::new().dither(
+                palette,
+                &mut index_data,
+                colors,
+                width,
+                height,
+            );
+        }
+        Self {
+            palette: palette.to_vec(),
+            index_data,
+            width,
+            height,
+            transparent_index: None,
+        }
+    }
+
+    /// Create an indexed image from an RGBA image with the colors of an existing palette
+    ///
+    /// The transparent pixels are handled like [`IndexedImage::from_rgba_image`], the last color
+    /// of a full palette (256 colors) is not used when the image has transparent pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * `img` - The source RGBA image
+    /// * `palette` - The palette to use, it must not be empty
+    /// * `dither` - Whether to apply dithering
+    ///
+    /// # Returns
+    ///
+    /// Returns the indexed image
+    pub fn from_rgba_palette(
+        img: &image::RgbaImage,
+        palette: &[Rgb<Srgb, u8>],
+        dither: bool,
+    ) -> Self {
+        if !img.pixels().any(is_transparent) {
+            let rgb = image::DynamicImage::from(img.clone()).to_rgb8();
+            return Self::from_palette(&rgb, palette, dither);
+        }
+        let palette = &palette[..palette.len().min(u8::MAX as usize)];
+        let mut indexed = Self::from_palette(&fill_transparent(img), palette, dither);
+        indexed.mark_transparent(img);
+        indexed
+    }
+
+    /// Map the transparent pixels of the source image to the reserved index
+    fn mark_transparent(&mut self, img: &image::RgbaImage) {
+        let transparent_index = self.palette.len() as u8;
+        for (index, pixel) in self.index_data.iter_mut().zip(img.pixels()) {
+            if is_transparent(pixel) {
+                *index = transparent_index;
+            }
+        }
+        self.transparent_index = Some(transparent_index);
+    }
+
     /// Get the palette index of a pixel at the specified coordinates
     /// 
     /// # Arguments
@@ -65,4 +233,177 @@ impl IndexedImage {
     pub fn get_pixel(&self, x: u32, y: u32) -> u8 {
         self.index_data[y as usize * self.width as usize + x as usize]
     }
+
+    /// Check if the pixel at the specified coordinates is transparent
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X coordinate
+    /// * `y` - Y coordinate
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the pixel is mapped to the reserved transparent index
+    pub fn is_transparent(&self, x: u32, y: u32) -> bool {
+        self.transparent_index == Some(self.get_pixel(x, y))
+    }
+}
+
+/// A sixel palette shared by the frames of an animation
+///
+/// Every frame is mapped to the same colors, so the colors don't flicker between frames
+/// and the color registers only need to be defined once. The palette remembers which
+/// registers were sent to the terminal, and the sixel encoder only redefines the
+/// registers that changed.
+///
+/// Many terminals give every sixel image its own color registers (private color registers,
+/// mode `?1070`), then the frames without the register definitions are drawn with the wrong
+/// colors. Print [`SixelPalette::DISABLE_PRIVATE_REGISTERS`] before the frames and
+/// [`SixelPalette::ENABLE_PRIVATE_REGISTERS`] after them.
+#[derive(Debug, Default)]
+pub struct SixelPalette {
+    state: Mutex<SixelPaletteState>,
+}
+
+#[derive(Debug, Default)]
+struct SixelPaletteState {
+    /// The colors of the palette
+    colors: Vec<Rgb<Srgb, u8>>,
+    /// The colors of the registers that were sent to the terminal
+    defined: Vec<Option<Rgb<Srgb, u8>>>,
+}
+
+impl SixelPalette {
+    /// Share the color registers between the sixel images
+    pub const DISABLE_PRIVATE_REGISTERS: &'static str = "\x1b[?1070l";
+    /// Give every sixel image its own color registers (the default of most terminals)
+    pub const ENABLE_PRIVATE_REGISTERS: &'static str = "\x1b[?1070h";
+
+    /// Create a shared palette with the given colors
+    ///
+    /// # Arguments
+    ///
+    /// * `colors` - The colors of the palette, at most 256 colors
+    ///
+    /// # Returns
+    ///
+    /// Returns a new shared palette
+    pub fn new(mut colors: Vec<Rgb<Srgb, u8>>) -> Self {
+        colors.truncate(quantette::MAX_COLORS as usize);
+        Self {
+            state: Mutex::new(SixelPaletteState {
+                colors,
+                defined: Vec::new(),
+            }),
+        }
+    }
+
+    /// Compute one palette from the pixels of several frames
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - The frames used to compute the palette (e.g. the first frames of an animation)
+    /// * `max_colors` - Maximum number of colors
+    /// * `quantize_method` - The quantize method to use
+    /// * `color_space` - The color space to use
+    ///
+    /// # Returns
+    ///
+    /// Returns the shared palette or an error if the frames are too large
+    pub fn from_frames<'a>(
+        frames: impl IntoIterator<Item = &'a image::RgbImage>,
+        max_colors: u16,
+        quantize_method: quantette::QuantizeMethod,
+        color_space: ColorSpace,
+    ) -> Result<Self, AboveMaxLen<u32>> {
+        let colors = frames
+            .into_iter()
+            .flat_map(|frame| from_component_slice::<Rgb<Srgb, u8>>(frame.as_raw()))
+            .copied()
+            .collect::<Vec<_>>();
+        let palette = quantette::PalettePipeline::try_from(colors.as_slice())?
+            .palette_size(quantette::PaletteSize::from_clamped(max_colors))
+            .colorspace(color_space)
+            .quantize_method(quantize_method)
+            .palette_par();
+        Ok(Self::new(palette))
+    }
+
+    /// Create a shared palette from the global color table of a GIF file
+    ///
+    /// The colors are quantized again if the table has more than `max_colors` colors.
+    ///
+    /// # Arguments
+    ///
+    /// * `palette` - The global color table (`r, g, b` triples)
+    /// * `max_colors` - Maximum number of colors
+    ///
+    /// # Returns
+    ///
+    /// Returns the shared palette or an error if the table is too large
+    pub fn from_gif_palette(palette: &[u8], max_colors: u16) -> Result<Self, AboveMaxLen<u32>> {
+        let colors: &[Rgb<Srgb, u8>] =
+            from_component_slice(&palette[..palette.len() / 3 * 3]);
+        if colors.len() <= max_colors as usize {
+            return Ok(Self::new(colors.to_vec()));
+        }
+        let palette = quantette::PalettePipeline::try_from(colors)?
+            .palette_size(quantette::PaletteSize::from_clamped(max_colors))
+            .palette();
+        Ok(Self::new(palette))
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, SixelPaletteState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the colors of the palette
+    pub fn colors(&self) -> Vec<Rgb<Srgb, u8>> {
+        self.state().colors.clone()
+    }
+
+    /// Returns `true` if the palette has no color
+    pub fn is_empty(&self) -> bool {
+        self.state().colors.is_empty()
+    }
+
+    /// Replace the colors of the palette
+    ///
+    /// Only the registers whose color changed are sent again with the next frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `colors` - The new colors of the palette, at most 256 colors
+    pub fn set_colors(&self, mut colors: Vec<Rgb<Srgb, u8>>) {
+        colors.truncate(quantette::MAX_COLORS as usize);
+        self.state().colors = colors;
+    }
+
+    /// Forget the registers sent to the terminal, e.g. after the terminal is reset
+    pub fn reset(&self) {
+        self.state().defined.clear();
+    }
+
+    /// Take the registers which are not defined in the terminal yet
+    ///
+    /// The registers are marked as defined, so they are only returned once.
+    ///
+    /// # Returns
+    ///
+    /// Returns the index and the color of every register to define
+    pub fn take_changed(&self) -> Vec<(usize, Rgb<Srgb, u8>)> {
+        let mut state = self.state();
+        let SixelPaletteState { colors, defined } = &mut *state;
+        defined.resize(colors.len().max(defined.len()), None);
+        colors
+            .iter()
+            .zip(defined.iter_mut())
+            .enumerate()
+            .filter(|(_, (color, defined))| **defined != Some(**color))
+            .map(|(index, (color, defined))| {
+                *defined = Some(*color);
+                (index, *color)
+            })
+            .collect()
+    }
 }
@@ -27,13 +27,18 @@
 */
 #![allow(clippy::collapsible_if)]
 #![allow(clippy::collapsible_else_if)]
+#[cfg(feature = "animation")]
+pub mod animation;
+pub mod capabilities;
 pub mod converter;
+pub mod decoder;
 pub mod error;
 #[cfg(feature = "gif")]
 pub mod gif_processor;
 #[cfg(feature = "sixel")]
 pub mod indexed_image;
 pub mod macro_rules;
+pub mod passthrough;
 #[cfg(feature = "processor")]
 pub mod processor;
 pub mod protocol;
@@ -271,7 +276,8 @@ impl DisplayMode {
     ///
     /// # Compatibility Rules
     /// * `FullColor` mode requires images with both color and grayscale data (`ProcessedImage::Both`)
-    /// * `SixelHalf` and `SixelFull` modes require RGB image data (`ProcessedImage::Color2`)
+    /// * `SixelHalf` and `SixelFull` modes require RGB image data (`ProcessedImage::Color2`),
+    ///   or RGBA image data (`ProcessedImage::Color`) to keep the transparent pixels
     /// * `HalfColor`, `Kitty`, `Iterm2`, and `WezTerm` modes require color image data (`ProcessedImage::Color`)
     /// * `Ascii`, `FullNoColor`, `KittyNoColor`, `Iterm2NoColor`, and `WezTermNoColor` modes require grayscale data (`ProcessedImage::NoColor`)
     ///
@@ -292,7 +298,7 @@ impl DisplayMode {
         match self {
             Self::FullColor => img_type.is_both(),
             #[cfg(feature = "sixel")]
-            Self::SixelHalf | Self::SixelFull => img_type.is_color2(),
+            Self::SixelHalf | Self::SixelFull => img_type.is_color2() || img_type.is_color(),
             #[cfg(target_os = "linux")]
             Self::KittyShm | Self::KittyShmNoColor => img_type.is_color2(),
             Self::HalfColor | Self::Kitty | Self::Iterm2 | Self::WezTerm => img_type.is_color(),
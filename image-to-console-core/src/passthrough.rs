@@ -0,0 +1,117 @@
+/// The largest DCS packet GNU screen passes through, including the introducer and the terminator
+pub const SCREEN_PACKET_SIZE: usize = 768;
+
+/// A terminal multiplexer that swallows the graphics sequences unless they are wrapped
+///
+/// tmux (3.3 or later) also needs `set -g allow-passthrough on`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    /// tmux, detected with the `TMUX` environment variable
+    Tmux,
+    /// GNU screen, detected with the `STY` environment variable
+    Screen,
+}
+
+impl Multiplexer {
+    /// Detect the multiplexer the process runs in
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the process does not run in tmux or GNU screen
+    pub fn detect() -> Option<Self> {
+        if std::env::var_os("TMUX").is_some_and(|v| !v.is_empty()) {
+            Some(Self::Tmux)
+        } else if std::env::var_os("STY").is_some_and(|v| !v.is_empty()) {
+            Some(Self::Screen)
+        } else {
+            None
+        }
+    }
+
+    /// Wrap one escape sequence, so the multiplexer passes it to the terminal
+    ///
+    /// tmux takes the whole sequence in one `DCS tmux; ... ST` with every `ESC` doubled.
+    /// GNU screen takes packets of at most [`SCREEN_PACKET_SIZE`] bytes, and a packet
+    /// is cut after the `ESC` of an inner `ESC \`, which would end the packet early.
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - The escape sequence for the terminal
+    ///
+    /// # Returns
+    ///
+    /// Returns the wrapped sequence
+    pub fn wrap_sequence(&self, sequence: &str) -> String {
+        match self {
+            Self::Tmux => format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b")),
+            Self::Screen => {
+                let payload_size = SCREEN_PACKET_SIZE - 4;
+                let packets =
+                    sequence.len().div_ceil(payload_size) + sequence.matches("\x1b\\").count();
+                let mut result = String::with_capacity(sequence.len() + packets * 4);
+                let mut rest = sequence;
+                while !rest.is_empty() {
+                    let mut end = rest.len().min(payload_size);
+                    while !rest.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    if let Some(pos) = rest[..end].find("\x1b\\") {
+                        end = pos + 1;
+                    }
+                    result.push_str("\x1bP");
+                    result.push_str(&rest[..end]);
+                    result.push_str("\x1b\\");
+                    rest = &rest[end..];
+                }
+                result
+            }
+        }
+    }
+
+    /// Wrap every graphics sequence (DCS, APC and OSC) in `data`
+    ///
+    /// The other text, e.g. the cursor movement before an image, is kept unwrapped,
+    /// so the multiplexer still knows where the cursor is.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The output of the converter
+    ///
+    /// # Returns
+    ///
+    /// Returns the output with the graphics sequences wrapped
+    pub fn wrap(&self, data: &str) -> String {
+        let mut result = String::with_capacity(data.len() + 16);
+        let mut rest = data;
+        while let Some(start) = find_string_sequence(rest) {
+            result.push_str(&rest[..start]);
+            let end = string_sequence_end(rest, start);
+            result.push_str(&self.wrap_sequence(&rest[start..end]));
+            rest = &rest[end..];
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+/// Find the start of the next DCS (`ESC P`), APC (`ESC _`) or OSC (`ESC ]`) sequence
+fn find_string_sequence(data: &str) -> Option<usize> {
+    data.as_bytes()
+        .windows(2)
+        .position(|window| matches!(window, [0x1b, b'P' | b'_' | b']']))
+}
+
+/// Find the end of the sequence starting at `start`, after its terminator
+///
+/// The OSC sequences may also be terminated by `BEL`.
+/// An unterminated sequence ends at the end of `data`.
+fn string_sequence_end(data: &str, start: usize) -> usize {
+    let body = &data.as_bytes()[start + 2..];
+    let is_osc = data.as_bytes()[start + 1] == b']';
+    let end = (0..body.len()).find_map(|i| match body[i] {
+        0x07 if is_osc => Some(i + 1),
+        0x1b if body.get(i + 1) == Some(&b'\\') => Some(i + 2),
+        _ => None,
+    });
+    end.map_or(data.len(), |end| start + 2 + end)
+}
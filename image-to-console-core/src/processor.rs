@@ -1,5 +1,9 @@
-use crate::converter::{ImageConverter, ImageConverterOption};
+use crate::converter::{
+    ImageConverter, ImageConverterOption,
+    cell::{CellGrid, ColorDepth},
+};
 use crate::error::{ConvertError, ConvertResult};
+use crate::passthrough::Multiplexer;
 use crate::ResizeMode::{Auto, Custom, None};
 use crate::{AutoResizeOption, DisplayMode, ProcessedImage, ResizeMode};
 use image::{imageops::FilterType, GenericImageView};
@@ -30,6 +34,15 @@ pub struct ImageProcessorOptions {
     pub max_colors: u16,
     #[cfg(feature = "sixel")]
     pub color_space: quantette::ColorSpace,
+    /// Whether to keep the transparent pixels unpainted (requires `sixel` feature)
+    #[cfg(feature = "sixel")]
+    pub transparent: bool,
+    /// The multiplexer to pass the graphics sequences through
+    pub passthrough: Option<Multiplexer>,
+    /// Whether to keep the cell grid in the result (only the character-cell modes)
+    pub cell_grid: bool,
+    /// The terminal size in columns and rows, used instead of querying the terminal
+    pub terminal_size: Option<(u16, u16)>,
 }
 
 impl Default for ImageProcessorOptions {
@@ -50,6 +63,11 @@ impl Default for ImageProcessorOptions {
             max_colors: 256,
             #[cfg(feature = "sixel")]
             color_space: quantette::ColorSpace::Srgb,
+            #[cfg(feature = "sixel")]
+            transparent: false,
+            passthrough: Option::None,
+            cell_grid: false,
+            terminal_size: Option::None,
         }
     }
 }
@@ -214,9 +232,145 @@ impl ImageProcessorOptions {
         self
     }
 
+    /// Set transparent option (requires `sixel` feature)
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to keep the transparent pixels unpainted
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    #[cfg(feature = "sixel")]
+    pub fn option_transparent(&mut self, enabled: bool) -> &mut Self {
+        self.transparent = enabled;
+        self
+    }
+
+    /// Set the multiplexer to pass the graphics sequences through
+    ///
+    /// # Arguments
+    ///
+    /// * `passthrough` - The multiplexer, usually `Multiplexer::detect()`
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn option_passthrough(&mut self, passthrough: Option<Multiplexer>) -> &mut Self {
+        self.passthrough = passthrough;
+        self
+    }
+
+    /// Keep the cell grid in the result, so a renderer can redraw only the changed cells
+    ///
+    /// It is ignored by the graphics protocols.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to keep the cell grid
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn option_cell_grid(&mut self, enabled: bool) -> &mut Self {
+        self.cell_grid = enabled;
+        self
+    }
+
+    /// Sets the terminal size, so the image can be processed without a terminal
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The width and the height in cells, `None` to query the terminal
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn option_terminal_size(&mut self, size: Option<(u16, u16)>) -> &mut Self {
+        self.terminal_size = size;
+        self
+    }
+
     pub fn get_options(&self) -> ImageProcessorOptions {
         *self
     }
+
+    /// Get the terminal size, the size in the options is used if it is set
+    fn get_terminal_size(&self) -> ConvertResult<(terminal_size::Width, terminal_size::Height)> {
+        match self.terminal_size {
+            Some((width, height)) => {
+                Ok((terminal_size::Width(width), terminal_size::Height(height)))
+            }
+            Option::None => {
+                terminal_size::terminal_size().ok_or(ConvertError::GetTerminalSizeError)
+            }
+        }
+    }
+
+    /// Get the size an image is resized to before it is converted
+    ///
+    /// A decoder can scale the image to this size itself, then the image is not resized again.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The width and the height of the image
+    ///
+    /// # Returns
+    ///
+    /// Returns the width and the height of the resized image, or an error if the terminal size
+    /// is needed and cannot be got
+    pub fn fit_size(&self, size: (u32, u32)) -> ConvertResult<(u32, u32)> {
+        let (mut w, mut h) = size;
+        match self.resize_mode {
+            Auto(option) => {
+                let (width, height) = self.get_terminal_size()?;
+                // The cells of a mode in pixels, and the width the image is resized to
+                let (max_width, max_height, resize_width) = match self.mode {
+                    mode if mode.is_normal() => (
+                        (width.0 / if self.full { 1 } else { 2 }) as u32,
+                        (height.0 * if self.full { 2 } else { 1 }) as u32,
+                        (width.0 as f32 / if self.full { 1f32 } else { 2f32 }).round() as u32,
+                    ),
+                    #[cfg(feature = "sixel")]
+                    DisplayMode::SixelHalf | DisplayMode::SixelFull => {
+                        let max_width = width.0 as u32 * if self.full { 12 } else { 6 };
+                        (
+                            max_width,
+                            height.0 as u32 * if self.full { 21 } else { 10 },
+                            max_width,
+                        )
+                    }
+                    // The other protocols scale the image in the terminal
+                    _ => return Ok(size),
+                };
+                if option.width && w > max_width {
+                    (w, h) = fit_dimensions((w, h), (resize_width, h));
+                }
+                if option.height && h > max_height {
+                    (w, h) = fit_dimensions((w, h), (w, max_height));
+                }
+            }
+            Custom(option) => {
+                if option.width.is_some() || option.height.is_some() {
+                    w = option.width.unwrap_or(w);
+                    h = option.height.unwrap_or(h);
+                }
+            }
+            None => {}
+        }
+        Ok((w, h))
+    }
+}
+
+/// Get the size of an image resized to fit in a size with its aspect ratio, like
+/// [`image::DynamicImage::resize`]
+fn fit_dimensions((width, height): (u32, u32), (max_width, max_height): (u32, u32)) -> (u32, u32) {
+    let ratio = f64::min(
+        max_width as f64 / width as f64,
+        max_height as f64 / height as f64,
+    );
+    let fit = |size: u32| ((size as f64 * ratio).round() as u64).clamp(1, u32::MAX as u64) as u32;
+    (fit(width), fit(height))
 }
 
 /// Image processing result
@@ -232,6 +386,8 @@ pub struct ImageProcessorResult {
     pub air_lines: usize,
     /// Processed line data
     pub lines: Vec<String>,
+    /// The cell grid, if `cell_grid` is enabled in a cell mode
+    pub cells: Option<CellGrid>,
     /// Processing time
     pub time: std::time::Instant,
     /// Processing options
@@ -305,6 +461,9 @@ pub struct ImageProcessor {
     pub image: image::DynamicImage,
     /// Processing options
     pub option: ImageProcessorOptions,
+    /// The palette shared by the frames of an animation (requires `sixel` feature)
+    #[cfg(feature = "sixel")]
+    pub sixel_palette: Option<std::sync::Arc<crate::indexed_image::SixelPalette>>,
 }
 
 impl ImageProcessor {
@@ -319,7 +478,35 @@ impl ImageProcessor {
     ///
     /// Returns a new image processor instance
     pub fn new(image: image::DynamicImage, option: ImageProcessorOptions) -> Self {
-        Self { image, option }
+        Self {
+            image,
+            option,
+            #[cfg(feature = "sixel")]
+            sixel_palette: Option::None,
+        }
+    }
+
+    /// Set the palette shared by the frames of an animation (requires `sixel` feature)
+    ///
+    /// # Arguments
+    ///
+    /// * `palette` - The shared palette, `None` quantizes every image on its own
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    #[cfg(feature = "sixel")]
+    pub fn sixel_palette(
+        &mut self,
+        palette: Option<std::sync::Arc<crate::indexed_image::SixelPalette>>,
+    ) -> &mut Self {
+        self.sixel_palette = palette;
+        self
+    }
+
+    /// Get the terminal size, the size in the options is used if it is set
+    fn terminal_size(&self) -> ConvertResult<(terminal_size::Width, terminal_size::Height)> {
+        self.option.get_terminal_size()
     }
 
     pub fn process_only(
@@ -327,71 +514,14 @@ impl ImageProcessor {
     ) -> ConvertResult<(&image::DynamicImage, (u32, u32), String, usize)> {
         let mut air_line: usize = 0;
         let (mut w, mut h) = self.image.dimensions();
-        let (width, height) =
-            terminal_size::terminal_size().ok_or(ConvertError::GetTerminalSizeError)?;
-        match self.option.resize_mode {
-            Auto(option) => {
-                if self.option.mode.is_normal() {
-                    if option.width {
-                        if w > (width.0 / if self.option.full { 1 } else { 2 }) as u32 {
-                            let new_img = self.image.resize(
-                                (width.0 as f32 / if self.option.full { 1f32 } else { 2f32 })
-                                    .round() as u32,
-                                h,
-                                FilterType::Lanczos3,
-                            );
-                            (w, h) = new_img.dimensions();
-                            self.image = new_img;
-                        }
-                    }
-                    if option.height {
-                        if h > (height.0 * if self.option.full { 2 } else { 1 }) as u32 {
-                            let new_img = self.image.resize(
-                                w,
-                                (height.0 * if self.option.full { 2 } else { 1 }) as u32,
-                                FilterType::Lanczos3,
-                            );
-                            (w, h) = new_img.dimensions();
-                            self.image = new_img;
-                        }
-                    }
-                }
-                #[cfg(feature = "sixel")]
-                if self.option.mode.is_sixel() {
-                    if option.width {
-                        if w > width.0 as u32 * if self.option.full { 12 } else { 6 } {
-                            let new_img = self.image.resize(
-                                width.0 as u32 * if self.option.full { 12 } else { 6 },
-                                h,
-                                FilterType::Lanczos3,
-                            );
-                            (w, h) = new_img.dimensions();
-                            self.image = new_img;
-                        }
-                    }
-                    if option.height {
-                        if h > height.0 as u32 * if self.option.full { 21 } else { 10 } {
-                            let new_img = self.image.resize(
-                                w,
-                                height.0 as u32 * if self.option.full { 21 } else { 10 },
-                                FilterType::Lanczos3,
-                            );
-                            (w, h) = new_img.dimensions();
-                            self.image = new_img;
-                        }
-                    }
-                }
-            }
-            Custom(option) => {
-                if option.width.is_some() || option.height.is_some() {
-                    let width = option.width.unwrap_or(w);
-                    let height = option.height.unwrap_or(h);
-                    let new_img = self.image.resize_exact(width, height, FilterType::Lanczos3);
-                    (w, h) = new_img.dimensions();
-                    self.image = new_img;
-                }
-            }
-            None => {}
+        let (width, height) = self.terminal_size()?;
+        // The image is resized once, to the size of the last resize of the mode
+        let size = self.option.fit_size((w, h))?;
+        if size != (w, h) {
+            self.image = self
+                .image
+                .resize_exact(size.0, size.1, FilterType::Lanczos3);
+            (w, h) = size;
         }
         let mut line_init = String::new();
         if self.option.center {
@@ -457,12 +587,23 @@ impl ImageProcessor {
     /// Returns the processed result
     pub fn process(&mut self) -> ConvertResult<ImageProcessorResult> {
         let mode = self.option.mode;
+        let option = self.option;
+        #[cfg(feature = "sixel")]
+        let sixel_palette = self.sixel_palette.clone();
         let time = std::time::Instant::now();
+        let (width, height) = self.terminal_size()?;
         let (img, (w, h), line_init, air_line) = self.process_only()?;
-        let (width, height) =
-            terminal_size::terminal_size().ok_or(ConvertError::GetTerminalSizeError)?;
+        // The sixel converter needs the alpha channel to keep the transparent pixels
+        #[cfg(feature = "sixel")]
+        let processed = if option.transparent && mode.is_sixel() {
+            ProcessedImage::Color(img.to_rgba8())
+        } else {
+            ProcessedImage::new(mode, img)
+        };
+        #[cfg(not(feature = "sixel"))]
+        let processed = ProcessedImage::new(mode, img);
         let converter = ImageConverter::new(
-            ProcessedImage::new(mode, img),
+            processed,
             ImageConverterOption {
                 center: self.option.center,
                 width: w,
@@ -475,16 +616,30 @@ impl ImageProcessor {
                 enable_compression: self.option.enable_compression,
                 #[cfg(feature = "sixel")]
                 max_colors: self.option.max_colors,
+                #[cfg(feature = "sixel")]
+                transparent: option.transparent,
+                #[cfg(feature = "sixel")]
+                sixel_palette,
                 terminal_size: (width.0 as u32, height.0 as u32),
+                passthrough: option.passthrough,
                 ..ImageConverterOption::default()
             },
         );
+        // The lines are rendered from the cells, so the image is converted only once
+        let (lines, cells) = if option.cell_grid && mode.is_normal() {
+            let mut cells = converter.convert_cells()?;
+            cells.top = air_line as u32;
+            (cells.to_ansi_lines(ColorDepth::TrueColor), Some(cells))
+        } else {
+            (converter.convert()?, Option::None)
+        };
         Ok(ImageProcessorResult {
             time,
             width: w,
             height: h,
             air_lines: air_line,
-            lines: converter.convert()?,
+            lines,
+            cells,
             option: self.option,
         })
     }
@@ -1,18 +1,14 @@
 use crate::DisplayMode;
+#[cfg(feature = "auto_select")]
+use crate::capabilities;
 
 #[cfg(feature = "auto_select")]
-/// Automatically detect the terminal protocol based on environment variables and terminal capabilities
-///
-/// This function attempts to detect which terminal protocol is supported by examining:
-/// 1. Environment variables (`TERM_PROGRAM`, `TERM`, `ITERM_SESSION`)
-/// 2. Terminal capability queries (for Sixel support when crossterm feature is enabled)
+/// Automatically detect the terminal protocol based on the terminal capabilities
 ///
-/// Detection priority:
-/// 1. WezTerm - detected by "wezterm" in TERM_PROGRAM or TERM environment variables
-/// 2. Kitty - detected by "kitty" in TERM_PROGRAM or TERM environment variables
-/// 3. ITerm2 - detected by "iterm" in TERM_PROGRAM or TERM environment variables, or presence of ITERM_SESSION environment variable
-/// 4. Sixel - detected by querying terminal capabilities via escape sequences (requires crossterm feature)
-/// 5. Normal - fallback if no specific terminal protocol is detected
+/// The terminal is probed once per process with [`capabilities::capabilities`],
+/// see [`Capabilities::protocol`] for the detection priority.
+/// If the terminal does not answer the probe, the environment variables
+/// (`TERM_PROGRAM`, `TERM`, `KITTY_WINDOW_ID`, `ITERM_SESSION`) are used instead.
 ///
 /// # Returns
 ///
@@ -20,91 +16,11 @@ use crate::DisplayMode;
 ///
 /// # Note
 ///
-/// This function is only available when the `auto_check` feature is enabled.
+/// This function is only available when the `auto_select` feature is enabled.
+///
+/// [`Capabilities::protocol`]: crate::capabilities::Capabilities::protocol
 pub fn get_terminal_protocol() -> Protocol {
-    let term_program = std::env::var("TERM_PROGRAM")
-        .unwrap_or_default()
-        .to_lowercase();
-    let term = std::env::var("TERM").unwrap_or_default().to_lowercase();
-    if term_program.contains("wezterm") || term.contains("wezterm") {
-        Protocol::WezTerm
-    } else if term_program.contains("kitty")
-        || term.contains("kitty")
-        || std::env::var("KITTY_WINDOW_ID").is_ok()
-    {
-        Protocol::Kitty
-    } else if term_program.contains("iterm")
-        || term.contains("iterm")
-        || std::env::var("ITERM_SESSION").is_ok()
-    {
-        Protocol::ITerm2
-    } else {
-        #[cfg(feature = "crossterm")]
-        {
-            fn check_sixel() -> std::io::Result<Protocol> {
-                use std::io::{BufRead, Write};
-                crossterm::terminal::enable_raw_mode()?;
-                // Send the escape sequence
-                std::io::stdout().write_all(b"\x1b[>c")?;
-                // Flush the output
-                std::io::stdout().flush()?;
-                // Wait some time then try to get the result
-                std::thread::sleep(std::time::Duration::from_millis(100));
-                let (st, rt) = std::sync::mpsc::channel::<String>();
-                // Spawn a thread to read the input
-                std::thread::spawn(move || {
-                    let mut buffer = Vec::new();
-                    match std::io::stdin().lock().read_until(b'c', &mut buffer) {
-                        Ok(_) => st.send(String::from_utf8(buffer).unwrap_or_default()),
-                        Err(_) => st.send(String::default()),
-                    }
-                });
-                // try to get the result
-                let p = match rt.recv_timeout(std::time::Duration::from_millis(100)) {
-                    Ok(s) => {
-                        // The result should be "ESC [ > Ps ; Pv ; Pc c"
-                        // So we skip to '>' then skip one again
-                        // and take all characters before 'c'
-                        let s = s
-                            .chars()
-                            .skip_while(|&c| c == '>')
-                            .skip(1)
-                            .take_while(|&c| c != 'c')
-                            .collect::<String>();
-                        // return the normal if we don't get anything
-                        if s.is_empty() {
-                            return Ok(Protocol::Normal);
-                        }
-                        // Parse the args
-                        let args = s.split(";").collect::<Vec<&str>>();
-                        // The Pc was ignored if the args length is 2
-                        // We need Pc argument to determine whether we should use sixel or not
-                        if args.len() <= 2 {
-                            Protocol::Normal
-                        } else if args
-                            .last()
-                            .map(|s| s.trim().parse::<u8>().unwrap_or_default() & 1)
-                            .unwrap_or_default()
-                            == 1
-                        {
-                            Protocol::Sixel
-                        } else {
-                            Protocol::Normal
-                        }
-                    }
-                    Err(_) => {
-                        // return normal if we cannot get the result
-                        Protocol::Normal
-                    }
-                };
-                crossterm::terminal::disable_raw_mode()?;
-                Ok(p)
-            }
-            check_sixel().unwrap_or(Protocol::Normal)
-        }
-        #[cfg(not(feature = "crossterm"))]
-        Protocol::Normal
-    }
+    capabilities::capabilities().protocol()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
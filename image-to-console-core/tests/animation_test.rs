@@ -0,0 +1,102 @@
+#![cfg(feature = "animation")]
+
+use image::{ImageFormat, Rgba, RgbaImage, metadata::LoopCount};
+use image_to_console_core::animation::open_animation;
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const COLORS: [[u8; 3]; 3] = [[255, 0, 0], [0, 255, 0], [0, 0, 255]];
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("image_to_console_{}_{name}", std::process::id()))
+}
+
+fn write_gif(name: &str, frames: usize, repeat: gif::Repeat) -> PathBuf {
+    let path = temp_path(name);
+    let palette: Vec<u8> = COLORS.concat();
+    let mut encoder = gif::Encoder::new(File::create(&path).unwrap(), 2, 2, &palette).unwrap();
+    encoder.set_repeat(repeat).unwrap();
+    for index in 0..frames {
+        let mut frame = gif::Frame::from_indexed_pixels(2, 2, vec![index as u8; 4], None);
+        frame.delay = 5;
+        encoder.write_frame(&frame).unwrap();
+    }
+    path
+}
+
+fn write_apng(name: &str, plays: u32) -> PathBuf {
+    let path = temp_path(name);
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(&path).unwrap()), 2, 2);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_animated(COLORS.len() as u32, plays).unwrap();
+    encoder.set_frame_delay(1, 20).unwrap();
+    let mut writer = encoder.write_header().unwrap();
+    for color in COLORS {
+        writer.write_image_data(&color.repeat(4)).unwrap();
+    }
+    writer.finish().unwrap();
+    path
+}
+
+fn read_frames(path: &Path) -> (Vec<(Rgba<u8>, Duration)>, LoopCount) {
+    let mut animation = open_animation(path).unwrap().unwrap();
+    let mut frames = Vec::new();
+    while let Some(frame) = animation.next_frame() {
+        let (image, delay) = frame.unwrap();
+        assert_eq!(image.dimensions(), (2, 2));
+        frames.push((*image.get_pixel(1, 1), delay));
+    }
+    (frames, animation.loop_count())
+}
+
+#[test]
+fn test_gif_animation() {
+    let path = write_gif("anim.gif", 3, gif::Repeat::Finite(1));
+    let (frames, loop_count) = read_frames(&path);
+    let expected: Vec<_> = COLORS
+        .iter()
+        .map(|&[r, g, b]| (Rgba([r, g, b, 255]), Duration::from_millis(50)))
+        .collect();
+    assert_eq!(frames, expected);
+    // The NETSCAPE repeat count does not count the first play
+    assert!(matches!(loop_count, LoopCount::Finite(n) if n.get() == 2));
+    // Only the GIF delays are in hundredths of a second
+    assert!(open_animation(&path).unwrap().unwrap().is_gif());
+
+    let path = write_gif("infinite.gif", 2, gif::Repeat::Infinite);
+    assert!(matches!(read_frames(&path).1, LoopCount::Infinite));
+}
+
+#[test]
+fn test_apng_animation() {
+    let path = write_apng("anim.png", 3);
+    let (frames, loop_count) = read_frames(&path);
+    assert_eq!(frames.len(), 3);
+    assert_eq!(
+        frames[2],
+        (Rgba([0, 0, 255, 255]), Duration::from_millis(50))
+    );
+    assert!(matches!(loop_count, LoopCount::Finite(n) if n.get() == 3));
+    assert!(!open_animation(&path).unwrap().unwrap().is_gif());
+
+    let path = write_apng("infinite.png", 0);
+    assert!(matches!(read_frames(&path).1, LoopCount::Infinite));
+}
+
+#[test]
+fn test_still_images() {
+    let path = write_gif("still.gif", 1, gif::Repeat::Infinite);
+    assert!(open_animation(&path).unwrap().is_none());
+
+    let path = temp_path("still.png");
+    RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255]))
+        .save_with_format(&path, ImageFormat::Png)
+        .unwrap();
+    assert!(open_animation(&path).unwrap().is_none());
+
+    assert!(open_animation(temp_path("missing.png")).is_err());
+}
@@ -0,0 +1,112 @@
+use image_to_console_core::{
+    capabilities::{Capabilities, Reply, ReplyParser},
+    protocol::Protocol,
+};
+
+/// Replies in the format of kitty 0.35, written from its documentation
+const KITTY: &[u8] = b"\x1b_Gi=31;OK\x1b\\\x1bP>|kitty(0.35.2)\x1b\\\x1b[6;20;10t\x1b[?2026;2$y\x1bP1+r524742=382f382f38\x1b\\\x1b[?62;c";
+
+/// Replies in the format of xterm 390 started with `-ti vt340`, written from its documentation
+const XTERM: &[u8] = b"\x1bP>|XTerm(390)\x1b\\\x1b[?1;0;256S\x1b[?2;0;1000;1000S\x1b[6;17;9t\x1b[?2026;0$y\x1bP0+r\x1b\\\x1b[?63;1;2;4;6;9;15;16;22;28;29c";
+
+/// Replies in the format of WezTerm, written from its documentation
+const WEZTERM: &[u8] = b"\x1b_Gi=31;OK\x1b\\\x1bP>|WezTerm 20240203-110809-5046fc22\x1b\\\x1b[?1;0;256S\x1b[6;19;9t\x1b[?2026;2$y\x1b[?65;4;6;18;22c";
+
+#[test]
+fn test_reply_parser() {
+    let mut parser = ReplyParser::new();
+    // Split in the middle of the sequences, with keys pressed before the replies
+    let mut replies = parser.feed(b"ab\x1b_Gi=31;O");
+    assert!(replies.is_empty());
+    replies.extend(parser.feed(b"K\x1b\\\x1b[?62;4"));
+    replies.extend(parser.feed(b";22c"));
+    assert_eq!(
+        replies,
+        vec![
+            Reply::KittyGraphics {
+                id: Some(31),
+                message: String::from("OK")
+            },
+            Reply::DeviceAttributes(vec![62, 4, 22]),
+        ]
+    );
+
+    // A cancelled sequence does not hide the next one
+    let replies = parser.feed(b"\x1bP>|abc\x18\x1b[?1;0;256S");
+    assert_eq!(
+        replies,
+        vec![Reply::Graphics {
+            item: 1,
+            status: 0,
+            values: vec![256]
+        }]
+    );
+
+    let replies = parser.feed(b"\x1bP1+r524742=382f382f38\x1b\\");
+    assert_eq!(
+        replies,
+        vec![Reply::TermCap {
+            valid: true,
+            capabilities: vec![(String::from("RGB"), Some(String::from("8/8/8")))]
+        }]
+    );
+}
+
+#[test]
+fn test_terminal_capabilities() {
+    let kitty = Capabilities::parse(KITTY);
+    assert!(kitty.responded);
+    assert!(kitty.kitty_graphics);
+    assert!(!kitty.sixel);
+    assert!(kitty.truecolor);
+    assert!(kitty.sync_output);
+    assert_eq!(kitty.cell_size, Some((10, 20)));
+    assert_eq!(kitty.name.as_deref(), Some("kitty(0.35.2)"));
+    assert_eq!(kitty.protocol(), Protocol::Kitty);
+
+    let xterm = Capabilities::parse(XTERM);
+    assert!(xterm.responded);
+    assert!(!xterm.kitty_graphics);
+    assert!(xterm.sixel);
+    assert!(!xterm.truecolor);
+    assert!(!xterm.sync_output);
+    assert_eq!(xterm.sixel_colors, Some(256));
+    assert_eq!(xterm.sixel_max_size, Some((1000, 1000)));
+    assert_eq!(xterm.cell_size, Some((9, 17)));
+    #[cfg(feature = "sixel")]
+    assert_eq!(xterm.protocol(), Protocol::Sixel);
+
+    let wezterm = Capabilities::parse(WEZTERM);
+    assert!(wezterm.kitty_graphics);
+    assert!(wezterm.iterm2);
+    assert!(wezterm.sixel);
+    assert_eq!(wezterm.protocol(), Protocol::WezTerm);
+
+    // A terminal without graphics only answers the device attributes
+    let plain = Capabilities::parse(b"\x1b[?1;2c");
+    assert!(plain.responded);
+    assert_eq!(plain.protocol(), Protocol::Normal);
+
+    // Nothing was received
+    assert!(!Capabilities::parse(b"").responded);
+}
+
+#[test]
+fn test_capabilities_with_env() {
+    let env = Capabilities {
+        name: Some("iTerm.app".to_string()),
+        iterm2: true,
+        truecolor: true,
+        ..Default::default()
+    };
+    // An older iTerm2 only answers the device attributes, the environment names it
+    let iterm2 = Capabilities::parse(b"\x1b[?62;4c").with_env(&env);
+    assert!(iterm2.responded);
+    assert!(iterm2.iterm2 && iterm2.sixel && iterm2.truecolor);
+    assert_eq!(iterm2.protocol(), Protocol::ITerm2);
+
+    // The replies naming the terminal are trusted over the environment
+    let xterm = Capabilities::parse(XTERM).with_env(&env);
+    assert!(!xterm.iterm2);
+    assert!(xterm.truecolor);
+}
@@ -0,0 +1,172 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use image_to_console_core::{
+    DisplayMode, ProcessedImage,
+    converter::{
+        ImageConverter, ImageConverterOption,
+        cell::{Cell, CellAttributes, CellGrid, ColorDepth, ansi256},
+    },
+    error::ConvertError,
+};
+
+const RED: [u8; 3] = [255, 0, 0];
+const WHITE: [u8; 3] = [255, 255, 255];
+
+fn convert_cells(mode: DisplayMode, img: RgbaImage) -> Result<CellGrid, ConvertError> {
+    let options = ImageConverterOption::default()
+        .mode(mode)
+        .width(img.width())
+        .height(img.height())
+        .line_init(String::from("  "))
+        .black_background(true)
+        .get_options();
+    ImageConverter::new(ProcessedImage::new(mode, &DynamicImage::from(img)), options)
+        .convert_cells()
+}
+
+#[test]
+fn test_full_color_cells() {
+    // A red pixel over a white one, then a transparent pixel over a red one, and the odd last row
+    let mut img = RgbaImage::new(2, 3);
+    img.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+    img.put_pixel(0, 1, Rgba([255, 255, 255, 255]));
+    img.put_pixel(1, 1, Rgba([255, 0, 0, 255]));
+    img.put_pixel(0, 2, Rgba([255, 255, 255, 255]));
+    let grid = convert_cells(DisplayMode::FullColor, img).unwrap();
+    assert_eq!((grid.width, grid.height, grid.left), (2, 2, 2));
+    assert_eq!(
+        grid.row(0),
+        &[
+            Cell::new('▄', Some(WHITE), Some(RED)),
+            Cell::new('▄', Some(RED), None)
+        ]
+    );
+    assert_eq!(
+        grid.row(1),
+        &[Cell::new('▀', Some(WHITE), None), Cell::default()]
+    );
+    assert_eq!(
+        grid.to_ansi_lines(ColorDepth::TrueColor),
+        vec![
+            "  \x1b[38;2;255;255;255m\x1b[48;2;255;0;0m▄\x1b[0m\x1b[38;2;255;0;0m▄\x1b[0m",
+            "  \x1b[38;2;255;255;255m▀\x1b[0m "
+        ]
+    );
+}
+
+#[test]
+fn test_half_color_cells() {
+    let img = RgbaImage::from_pixel(2, 1, Rgba([255, 0, 0, 255]));
+    let grid = convert_cells(DisplayMode::HalfColor, img).unwrap();
+    // A pixel is two columns wide
+    assert_eq!((grid.width, grid.height), (4, 1));
+    assert!(
+        grid.cells
+            .iter()
+            .all(|cell| *cell == Cell::new(' ', None, Some(RED)))
+    );
+    assert_eq!(
+        grid.to_ansi_lines(ColorDepth::TrueColor),
+        vec!["  \x1b[48;2;255;0;0m    \x1b[0m"]
+    );
+
+    // The graphics protocols are not made of cells
+    assert_eq!(
+        convert_cells(DisplayMode::Kitty, RgbaImage::new(1, 1)),
+        Err(ConvertError::UnsupportedMode(String::from("Kitty")))
+    );
+}
+
+#[test]
+fn test_no_color_cells() {
+    let mut img = RgbaImage::from_pixel(1, 3, Rgba([255, 255, 255, 255]));
+    img.put_pixel(0, 1, Rgba([0, 0, 0, 255]));
+    let grid = convert_cells(DisplayMode::FullNoColor, img.clone()).unwrap();
+    let black = Some([0, 0, 0]);
+    assert_eq!(
+        grid.cells,
+        vec![Cell::new('▀', None, black), Cell::new('▀', None, black)]
+    );
+    assert_eq!(grid.to_plain_lines(), vec!["  ▀", "  ▀"]);
+
+    let grid = convert_cells(DisplayMode::Ascii, img).unwrap();
+    assert_eq!((grid.width, grid.height), (2, 3));
+    assert_eq!(grid.to_plain_lines(), vec!["  @@", "    ", "  @@"]);
+}
+
+#[test]
+fn test_cell_serializers() {
+    let mut grid = CellGrid::new(3, 1);
+    let bold = CellAttributes {
+        bold: true,
+        ..Default::default()
+    };
+    grid.cells = vec![
+        Cell::new('<', Some(RED), None).with_attributes(bold),
+        Cell::new('&', Some(RED), None).with_attributes(bold),
+        Cell::new('a', None, Some(WHITE)),
+    ];
+    assert_eq!(
+        grid.to_ansi_lines(ColorDepth::Ansi256),
+        vec!["\x1b[1m\x1b[38;5;196m<&\x1b[0m\x1b[48;5;231ma\x1b[0m"]
+    );
+    assert_eq!(
+        grid.to_html(),
+        "<pre style=\"line-height:1;font-family:monospace\">\
+         <span style=\"color:#ff0000;font-weight:bold\">&lt;&amp;</span>\
+         <span style=\"background-color:#ffffff\">a</span></pre>"
+    );
+    assert_eq!(grid.to_plain_lines(), vec!["<&a"]);
+
+    assert_eq!(ansi256([0, 0, 0]), 16);
+    assert_eq!(ansi256([128, 128, 128]), 244);
+    assert_eq!(ansi256([0, 135, 255]), 33);
+}
+
+#[test]
+fn test_html_and_svg_export() {
+    let mut grid = CellGrid::new(3, 1);
+    grid.cells = vec![
+        Cell::new('▀', Some(RED), Some(WHITE)),
+        Cell::new('▀', Some(RED), None),
+        Cell::new('&', None, None),
+    ];
+    let page = grid.to_html_page();
+    assert!(page.starts_with("<!DOCTYPE html>"));
+    assert!(page.contains(&grid.to_html()));
+
+    assert_eq!(
+        grid.to_svg(),
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"24\" height=\"16\" \
+         viewBox=\"0 0 24 16\" shape-rendering=\"crispEdges\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"#000000\"/>\n\
+         <rect x=\"0\" y=\"0\" width=\"8\" height=\"16\" fill=\"#ffffff\"/>\n\
+         <rect x=\"0\" y=\"0\" width=\"16\" height=\"8\" fill=\"#ff0000\"/>\n\
+         <text x=\"16\" y=\"12\" font-family=\"monospace\" font-size=\"14\" fill=\"#ffffff\">&amp;</text>\n\
+         </svg>\n"
+    );
+}
+
+#[test]
+fn test_reversed_default_colors() {
+    let reverse = CellAttributes {
+        reverse: true,
+        ..Default::default()
+    };
+    let mut grid = CellGrid::new(2, 1);
+    grid.cells = vec![
+        Cell::new('a', None, None).with_attributes(reverse),
+        Cell::new('b', Some(RED), None).with_attributes(reverse),
+    ];
+    // The default colors are swapped too, white on black becomes black on white
+    assert_eq!(
+        grid.to_html(),
+        "<pre style=\"line-height:1;font-family:monospace\">\
+         <span style=\"color:#000000;background-color:#ffffff\">a</span>\
+         <span style=\"color:#000000;background-color:#ff0000\">b</span></pre>"
+    );
+    let svg = grid.to_svg();
+    assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"8\" height=\"16\" fill=\"#ffffff\"/>"));
+    assert!(svg.contains("<rect x=\"8\" y=\"0\" width=\"8\" height=\"16\" fill=\"#ff0000\"/>"));
+    assert!(svg.contains("fill=\"#000000\">a</text>"));
+    assert!(svg.contains("fill=\"#000000\">b</text>"));
+}
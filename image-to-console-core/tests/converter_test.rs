@@ -1,6 +1,6 @@
 use image_to_console_core::{
     DisplayMode, ProcessedImage,
-    converter::{ImageConverter, ImageConverterOption},
+    converter::{ImageConverter, ImageConverterOption, Iterm2Options, Iterm2Size},
     error::ConvertError,
 };
 
@@ -84,3 +84,185 @@ fn test_all_protocol_convert() {
         assert!(result.is_ok());
     }
 }
+
+#[test]
+fn test_iterm2_options_convert() {
+    let img = image::RgbaImage::new(10, 10);
+    let iterm2 = Iterm2Options::default()
+        .width(Iterm2Size::Percent(50))
+        .height(Iterm2Size::Pixels(120))
+        .preserve_aspect_ratio(false)
+        .name(String::from("image.png"))
+        .do_not_move_cursor(true)
+        .get_options();
+    let options = ImageConverterOption::default()
+        .mode(DisplayMode::Iterm2)
+        .width(10)
+        .height(10)
+        .center(false)
+        .iterm2(iterm2)
+        .get_options();
+    let converter = ImageConverter::new(ProcessedImage::Color(img), options);
+    let result = converter.convert().unwrap();
+    assert_eq!(result.len(), 1);
+    let line = &result[0];
+    assert!(line.starts_with("\x1b]1337;File=name=aW1hZ2UucG5n;size="));
+    assert!(
+        line.contains(";width=50%;height=120px;preserveAspectRatio=0;inline=1;doNotMoveCursor=1:")
+    );
+    assert!(line.ends_with('\x07'));
+}
+
+#[test]
+fn test_iterm2_center_fit() {
+    let img = image::RgbaImage::new(10, 20);
+    let options = ImageConverterOption::default()
+        .mode(DisplayMode::WezTerm)
+        .width(10)
+        .height(20)
+        .line_init(String::from("\x1b[1;5H"))
+        .get_options();
+    let options = ImageConverterOption {
+        terminal_size: (80, 24),
+        ..options
+    };
+    let converter = ImageConverter::new(ProcessedImage::Color(img), options);
+    let result = converter.convert().unwrap();
+    assert!(result[0].starts_with("\x1b[1;5H\x1b]1337;File=size="));
+    assert!(result[0].contains(";height=24;inline=1:"));
+    assert!(result[0].ends_with("\x1b\\"));
+}
+
+#[test]
+fn test_iterm2_multipart_convert() {
+    let img = image::RgbaImage::new(10, 10);
+    let options = ImageConverterOption::default()
+        .mode(DisplayMode::Iterm2)
+        .width(10)
+        .height(10)
+        .center(false)
+        .iterm2(
+            Iterm2Options::default()
+                .multipart_chunk_size(Some(30))
+                .get_options(),
+        )
+        .get_options();
+    let converter = ImageConverter::new(ProcessedImage::Color(img), options);
+    let line = converter.convert().unwrap().remove(0);
+    assert!(line.starts_with("\x1b]1337;MultipartFile=size="));
+    assert!(line.ends_with("\x1b]1337;FileEnd\x07"));
+    let parts = line.matches("\x1b]1337;FilePart=").count();
+    assert!(parts > 1);
+    // Every part except the last one should be decoded without padding
+    let payloads = line
+        .split("\x1b]1337;FilePart=")
+        .skip(1)
+        .map(|part| part.split('\x07').next().unwrap())
+        .collect::<Vec<_>>();
+    for payload in &payloads[..payloads.len() - 1] {
+        assert_eq!(payload.len(), 40);
+        assert!(!payload.contains('='));
+    }
+}
+
+#[cfg(feature = "sixel")]
+#[test]
+fn test_sixel_raster_attributes() {
+    let img = image::RgbImage::from_pixel(4, 6, image::Rgb([255, 0, 0]));
+    let options = ImageConverterOption::default()
+        .mode(DisplayMode::SixelFull)
+        .width(4)
+        .height(6)
+        .get_options();
+    let result = ImageConverter::new(ProcessedImage::Color2(img.clone()), options.clone())
+        .convert()
+        .unwrap();
+    assert!(result[0].starts_with("\x1bP9;1q\"1;1;4;6#0;2;100;0;0"));
+
+    let options = options.clone().mode(DisplayMode::SixelHalf).get_options();
+    let result = ImageConverter::new(ProcessedImage::Color2(img.clone()), options.clone())
+        .convert()
+        .unwrap();
+    assert!(result[0].starts_with("\x1bPq\"2;1;8;6#0"));
+
+    let options = options.clone().raster_attributes(false).get_options();
+    let result = ImageConverter::new(ProcessedImage::Color2(img), options)
+        .convert()
+        .unwrap();
+    assert!(result[0].starts_with("\x1bPq#0"));
+}
+
+#[cfg(feature = "sixel")]
+#[test]
+fn test_sixel_transparent_convert() {
+    // The left half is transparent, the right half is red
+    let img = image::RgbaImage::from_fn(4, 6, |x, _| {
+        if x < 2 {
+            image::Rgba([0, 0, 0, 0])
+        } else {
+            image::Rgba([255, 0, 0, 255])
+        }
+    });
+    let options = ImageConverterOption::default()
+        .mode(DisplayMode::SixelFull)
+        .width(4)
+        .height(6)
+        .transparent(true)
+        .get_options();
+    let result = ImageConverter::new(ProcessedImage::Color(img.clone()), options.clone())
+        .convert()
+        .unwrap();
+    assert_eq!(result[0], "\x1bP9;1q\"1;1;4;6#0;2;100;0;0#0??~~-\x1b\\");
+
+    let options = options.clone().mode(DisplayMode::SixelHalf).get_options();
+    let result = ImageConverter::new(ProcessedImage::Color(img), options)
+        .convert()
+        .unwrap();
+    assert!(result[0].starts_with("\x1bP0;1q\"2;1;8;6"));
+}
+
+#[cfg(feature = "sixel")]
+#[test]
+fn test_sixel_shared_palette_convert() {
+    use image_to_console_core::indexed_image::SixelPalette;
+    use std::sync::Arc;
+
+    let frames = [
+        image::RgbImage::from_pixel(4, 6, image::Rgb([255, 0, 0])),
+        image::RgbImage::from_pixel(4, 6, image::Rgb([0, 0, 255])),
+    ];
+    let palette = Arc::new(
+        SixelPalette::from_frames(
+            &frames,
+            256,
+            quantette::QuantizeMethod::wu(),
+            quantette::ColorSpace::Srgb,
+        )
+        .unwrap(),
+    );
+    let options = ImageConverterOption::default()
+        .mode(DisplayMode::SixelFull)
+        .width(4)
+        .height(6)
+        .raster_attributes(false)
+        .sixel_palette(Some(palette.clone()))
+        .get_options();
+    let convert = |img: &image::RgbImage| {
+        ImageConverter::new(ProcessedImage::Color2(img.clone()), options.clone())
+            .convert()
+            .unwrap()
+            .remove(0)
+    };
+    // The first frame defines every register
+    let first = convert(&frames[0]);
+    assert_eq!(first.matches(";2;").count(), palette.colors().len());
+    // The next frames reuse the registers
+    let second = convert(&frames[1]);
+    assert!(!second.contains(";2;"));
+    let index = palette
+        .colors()
+        .iter()
+        .position(|color| color.blue == 255)
+        .unwrap();
+    assert_eq!(second, format!("\x1bP9;1q#{index}!4~-\x1b\\"));
+}
@@ -0,0 +1,196 @@
+use image::{Rgba, RgbaImage};
+use image_to_console_core::{
+    DisplayMode, ProcessedImage,
+    converter::{ImageConverter, ImageConverterOption},
+    decoder,
+    error::DecodeError,
+};
+use rand::{RngExt, SeedableRng, rngs::StdRng};
+
+/// The sixel encoder before the rewrite, shared with the benchmark
+#[cfg(feature = "sixel")]
+#[path = "../benches/sixel_bench/legacy.rs"]
+mod legacy;
+
+/// Build a random image with a few colors, so the quantizer keeps every color
+fn random_image(rng: &mut StdRng, width: u32, height: u32, transparent: bool) -> RgbaImage {
+    let colors = (0..8).map(|_| rng.random::<[u8; 3]>()).collect::<Vec<_>>();
+    RgbaImage::from_fn(width, height, |_, _| {
+        let [r, g, b] = colors[rng.random_range(0..colors.len())];
+        let alpha = if transparent && rng.random_bool(0.3) {
+            0
+        } else {
+            255
+        };
+        Rgba([r, g, b, alpha])
+    })
+}
+
+fn options(mode: DisplayMode, (width, height): (u32, u32)) -> ImageConverterOption {
+    ImageConverterOption::default()
+        .mode(mode)
+        .width(width)
+        .height(height)
+        .center(false)
+        .get_options()
+}
+
+fn convert(img: ProcessedImage, options: ImageConverterOption) -> String {
+    ImageConverter::new(img, options)
+        .convert()
+        .unwrap()
+        .concat()
+}
+
+/// The sixel colors are written in percent, so every channel may be off by one step
+fn assert_close(actual: &Rgba<u8>, expected: &Rgba<u8>) {
+    for (a, e) in actual.0.iter().zip(expected.0) {
+        assert!(a.abs_diff(e) <= 2, "{actual:?} != {expected:?}");
+    }
+}
+
+#[cfg(feature = "sixel")]
+#[test]
+fn test_sixel_round_trip() {
+    let mut rng = StdRng::seed_from_u64(30);
+    for (width, height) in [(1, 1), (7, 6), (13, 11), (32, 25)] {
+        let img = random_image(&mut rng, width, height, false);
+        let rgb = image::DynamicImage::ImageRgba8(img.clone()).to_rgb8();
+        let data = convert(
+            ProcessedImage::Color2(rgb.clone()),
+            options(DisplayMode::SixelFull, (width, height)),
+        );
+        let decoded = decoder::sixel::decode(data.as_bytes()).unwrap();
+        assert_eq!(decoded.dimensions(), (width, height));
+        for (x, y, pixel) in img.enumerate_pixels() {
+            assert_close(decoded.get_pixel(x, y), pixel);
+        }
+
+        // The half mode writes every pixel twice
+        let data = convert(
+            ProcessedImage::Color2(rgb),
+            options(DisplayMode::SixelHalf, (width, height)),
+        );
+        let decoded = decoder::decode(data.as_bytes()).unwrap();
+        assert_eq!(decoded.dimensions(), (width * 2, height));
+        for (x, y, pixel) in img.enumerate_pixels() {
+            assert_close(decoded.get_pixel(x * 2, y), pixel);
+            assert_close(decoded.get_pixel(x * 2 + 1, y), pixel);
+        }
+    }
+}
+
+#[cfg(feature = "sixel")]
+#[test]
+fn test_sixel_transparent_round_trip() {
+    let mut rng = StdRng::seed_from_u64(31);
+    let img = random_image(&mut rng, 17, 13, true);
+    let options = options(DisplayMode::SixelFull, img.dimensions())
+        .transparent(true)
+        .get_options();
+    let data = convert(ProcessedImage::Color(img.clone()), options);
+    let decoded = decoder::sixel::decode(data.as_bytes()).unwrap();
+    assert_eq!(decoded.dimensions(), img.dimensions());
+    for (x, y, pixel) in img.enumerate_pixels() {
+        let actual = decoded.get_pixel(x, y);
+        if pixel[3] == 0 {
+            assert_eq!(actual[3], 0);
+        } else {
+            assert_close(actual, pixel);
+        }
+    }
+}
+
+#[cfg(feature = "sixel")]
+#[test]
+fn test_sixel_matches_legacy_encoder() {
+    use image_to_console_core::{converter::sixel, indexed_image::IndexedImage};
+
+    // The palette order and the color runs differ, so the decoded pixels are compared
+    let mut rng = StdRng::seed_from_u64(28);
+    for (width, height, transparent) in [(1, 1, false), (13, 11, false), (40, 25, true)] {
+        let img = IndexedImage::from_rgba_image(
+            &random_image(&mut rng, width, height, transparent),
+            256,
+            false,
+            quantette::QuantizeMethod::wu(),
+            quantette::ColorSpace::Srgb,
+        )
+        .unwrap();
+        let option = ImageConverterOption::default();
+        for is_full in [true, false] {
+            let expected = decoder::sixel::decode(legacy::encode(&img, is_full).as_bytes());
+            let actual = sixel::encode(&img, is_full, &option).unwrap().concat();
+            assert_eq!(
+                decoder::sixel::decode(actual.as_bytes()).unwrap(),
+                expected.unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_sixel_decode() {
+    // Raster attributes larger than the painted area, a repeat and two color definitions
+    let data = b"\x1bP9;1q\"1;1;5;7#1;2;100;0;0!3~$#2;2;0;0;100??@-#1@\x1b\\";
+    let decoded = decoder::sixel::decode(data).unwrap();
+    assert_eq!(decoded.dimensions(), (5, 7));
+    assert_eq!(*decoded.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+    assert_eq!(*decoded.get_pixel(2, 5), Rgba([255, 0, 0, 255]));
+    assert_eq!(*decoded.get_pixel(2, 0), Rgba([0, 0, 255, 255]));
+    assert_eq!(*decoded.get_pixel(2, 1), Rgba([255, 0, 0, 255]));
+    assert_eq!(*decoded.get_pixel(0, 6), Rgba([255, 0, 0, 255]));
+    assert_eq!(decoded.get_pixel(4, 0)[3], 0);
+
+    // A huge declared size without the pixels is not allocated
+    let decoded = decoder::sixel::decode(b"\x1bPq\"1;1;16384;16384#1@\x1b\\").unwrap();
+    assert_eq!(decoded.dimensions(), (1, 1));
+
+    // HLS colors start at blue
+    let decoded = decoder::sixel::decode(b"\x1bPq#0;1;0;50;100@\x1b\\").unwrap();
+    assert_eq!(*decoded.get_pixel(0, 0), Rgba([0, 0, 255, 255]));
+
+    assert_eq!(
+        decoder::sixel::decode(b"\x1bPq#0~~"),
+        Err(DecodeError::InvalidSequence {
+            offset: 7,
+            message: String::from("the sequence is not terminated"),
+        })
+    );
+    assert_eq!(
+        decoder::decode(b"plain text"),
+        Err(DecodeError::UnknownProtocol)
+    );
+}
+
+#[test]
+fn test_kitty_round_trip() {
+    let mut rng = StdRng::seed_from_u64(32);
+    // Noise does not compress, so the PNG is sent in several chunks
+    let img = RgbaImage::from_fn(48, 40, |_, _| Rgba(rng.random::<[u8; 4]>()));
+    let data = convert(
+        ProcessedImage::Color(img.clone()),
+        options(DisplayMode::Kitty, img.dimensions()),
+    );
+    assert!(data.matches("\x1b_G").count() > 1);
+    let decoded = decoder::kitty::decode(data.as_bytes()).unwrap();
+    assert_eq!(decoded, img);
+
+    // The prefix of the sequence is skipped
+    let data = format!("\x1b[2;3H{data}");
+    assert_eq!(decoder::decode(data.as_bytes()).unwrap(), img);
+}
+
+#[test]
+fn test_kitty_decode_raw() {
+    let data = b"\x1b_Ga=T,f=24,s=2,v=1;/wAAAP8A\x1b\\";
+    let decoded = decoder::kitty::decode(data).unwrap();
+    assert_eq!(*decoded.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+    assert_eq!(*decoded.get_pixel(1, 0), Rgba([0, 255, 0, 255]));
+
+    let data = b"\x1b_Ga=T,t=s,f=24,s=2,v=1;bmFtZQ==\x1b\\";
+    assert!(matches!(
+        decoder::kitty::decode(data),
+        Err(DecodeError::Unsupported(_))
+    ));
+}
@@ -0,0 +1,242 @@
+#![cfg(feature = "gif")]
+
+use gif::{DisposalMethod, Frame};
+use image::Rgba;
+use image_to_console_core::gif_processor::GifFrameProcessor;
+use std::borrow::Cow;
+
+/// Red, green, blue and white
+const PALETTE: [u8; 12] = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const GREEN: Rgba<u8> = Rgba([0, 255, 0, 255]);
+const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const TRANSPARENT: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+fn frame(area: (u16, u16, u16, u16), buffer: Vec<u8>, dispose: DisposalMethod) -> Frame<'static> {
+    let (left, top, width, height) = area;
+    Frame {
+        left,
+        top,
+        width,
+        height,
+        dispose,
+        buffer: Cow::Owned(buffer),
+        ..Default::default()
+    }
+}
+
+/// A 2x2 canvas with a white background
+fn white_background() -> GifFrameProcessor {
+    let mut processor = GifFrameProcessor::new(2, 2, Some(PALETTE.to_vec()));
+    processor.background_index(Some(3));
+    processor
+}
+
+fn pixels(processor: &GifFrameProcessor) -> Vec<Rgba<u8>> {
+    processor.canvas().pixels().copied().collect()
+}
+
+#[test]
+fn test_first_frame_background() {
+    // The part of the canvas outside the first frame is the background color
+    let mut processor = white_background();
+    let image = processor
+        .process_frame(&frame((0, 0, 1, 1), vec![0], DisposalMethod::Keep))
+        .unwrap();
+    assert_eq!(image.as_raw().len(), 16);
+    assert_eq!(pixels(&processor), [RED, WHITE, WHITE, WHITE]);
+
+    // Below a first frame with a transparent color, the canvas is transparent
+    let mut processor = white_background();
+    let mut first = frame((0, 0, 2, 1), vec![0, 1], DisposalMethod::Keep);
+    first.transparent = Some(1);
+    processor.process_frame(&first).unwrap();
+    assert_eq!(
+        pixels(&processor),
+        [RED, TRANSPARENT, TRANSPARENT, TRANSPARENT]
+    );
+
+    // Without a background index the background is transparent
+    let mut processor = GifFrameProcessor::new(2, 1, Some(PALETTE.to_vec()));
+    processor
+        .process_frame(&frame((1, 0, 1, 1), vec![2], DisposalMethod::Keep))
+        .unwrap();
+    assert_eq!(pixels(&processor), [TRANSPARENT, BLUE]);
+}
+
+#[test]
+fn test_transparent_color() {
+    let mut processor = white_background();
+    processor
+        .process_frame(&frame((0, 0, 2, 2), vec![0; 4], DisposalMethod::Keep))
+        .unwrap();
+    let mut second = frame((0, 0, 2, 2), vec![1, 3, 3, 2], DisposalMethod::Keep);
+    second.transparent = Some(3);
+    processor.process_frame(&second).unwrap();
+    assert_eq!(pixels(&processor), [GREEN, RED, RED, BLUE]);
+}
+
+#[test]
+fn test_dispose_background() {
+    let mut processor = white_background();
+    processor
+        .process_frame(&frame((0, 0, 2, 2), vec![0; 4], DisposalMethod::Keep))
+        .unwrap();
+    processor
+        .process_frame(&frame((0, 0, 1, 2), vec![1; 2], DisposalMethod::Background))
+        .unwrap();
+    processor
+        .process_frame(&frame((1, 1, 1, 1), vec![2], DisposalMethod::Background))
+        .unwrap();
+    // The first column is cleared to the background color
+    assert_eq!(pixels(&processor), [WHITE, RED, WHITE, BLUE]);
+
+    // The area of a frame with a transparent color is cleared to transparent
+    let mut transparent = frame((0, 0, 1, 1), vec![0], DisposalMethod::Background);
+    transparent.transparent = Some(3);
+    processor.process_frame(&transparent).unwrap();
+    processor
+        .process_frame(&frame((1, 0, 1, 1), vec![1], DisposalMethod::Keep))
+        .unwrap();
+    assert_eq!(pixels(&processor), [TRANSPARENT, GREEN, WHITE, WHITE]);
+}
+
+#[test]
+fn test_dispose_previous() {
+    // The first frame is restored to the initial background
+    let mut processor = white_background();
+    processor
+        .process_frame(&frame((0, 0, 2, 2), vec![0; 4], DisposalMethod::Previous))
+        .unwrap();
+    processor
+        .process_frame(&frame((1, 1, 1, 1), vec![2], DisposalMethod::Keep))
+        .unwrap();
+    assert_eq!(pixels(&processor), [WHITE, WHITE, WHITE, BLUE]);
+
+    // Only the area of the frame is restored
+    processor
+        .process_frame(&frame((0, 0, 1, 2), vec![1; 2], DisposalMethod::Previous))
+        .unwrap();
+    assert_eq!(pixels(&processor), [GREEN, WHITE, GREEN, BLUE]);
+    processor
+        .process_frame(&frame((1, 0, 1, 1), vec![0], DisposalMethod::Keep))
+        .unwrap();
+    assert_eq!(pixels(&processor), [WHITE, RED, WHITE, BLUE]);
+}
+
+#[test]
+fn test_take_canvas() {
+    // A kept frame is drawn over, the canvas is copied
+    let mut processor = white_background();
+    processor
+        .process_frame(&frame((0, 0, 1, 1), vec![0], DisposalMethod::Keep))
+        .unwrap();
+    let image = processor.take_canvas();
+    assert_eq!(
+        image.pixels().copied().collect::<Vec<_>>(),
+        pixels(&processor)
+    );
+
+    // The disposal of a frame covering the canvas replaces it, the canvas is moved out
+    processor
+        .process_frame(&frame((0, 0, 2, 2), vec![1; 4], DisposalMethod::Background))
+        .unwrap();
+    let image = processor.take_canvas();
+    assert_eq!(image.pixels().copied().collect::<Vec<_>>(), [GREEN; 4]);
+    processor
+        .process_frame(&frame((1, 1, 1, 1), vec![2], DisposalMethod::Keep))
+        .unwrap();
+    assert_eq!(pixels(&processor), [WHITE, WHITE, WHITE, BLUE]);
+
+    let mut processor = white_background();
+    processor
+        .process_frame(&frame((0, 0, 2, 2), vec![0; 4], DisposalMethod::Keep))
+        .unwrap();
+    processor
+        .process_frame(&frame((0, 0, 2, 2), vec![2; 4], DisposalMethod::Previous))
+        .unwrap();
+    assert_eq!(processor.take_canvas().get_pixel(0, 0), &BLUE);
+    processor
+        .process_frame(&frame((0, 0, 1, 1), vec![1], DisposalMethod::Keep))
+        .unwrap();
+    assert_eq!(pixels(&processor), [GREEN, RED, RED, RED]);
+}
+
+#[test]
+fn test_frame_outside_canvas() {
+    let mut processor = white_background();
+    processor
+        .process_frame(&frame(
+            (1, 1, 2, 2),
+            vec![0, 1, 2, 0],
+            DisposalMethod::Background,
+        ))
+        .unwrap();
+    assert_eq!(pixels(&processor), [WHITE, WHITE, WHITE, RED]);
+    processor
+        .process_frame(&frame((5, 5, 1, 1), vec![1], DisposalMethod::Keep))
+        .unwrap();
+    assert_eq!(pixels(&processor), [WHITE; 4]);
+}
+
+#[test]
+fn test_malformed_frames() {
+    // No local or global palette
+    let mut processor = GifFrameProcessor::new(1, 1, None);
+    assert!(
+        processor
+            .process_frame(&frame((0, 0, 1, 1), vec![0], DisposalMethod::Keep))
+            .is_err()
+    );
+
+    // A color index outside the palette
+    let mut processor = white_background();
+    assert!(
+        processor
+            .process_frame(&frame((0, 0, 1, 1), vec![4], DisposalMethod::Keep))
+            .is_err()
+    );
+
+    // The buffer is shorter than the frame
+    assert!(
+        processor
+            .process_frame(&frame((0, 0, 2, 2), vec![0; 3], DisposalMethod::Keep))
+            .is_err()
+    );
+
+    // The local palette is used before the global palette
+    let mut local = frame((0, 0, 1, 1), vec![0], DisposalMethod::Keep);
+    local.palette = Some(vec![0, 0, 255]);
+    processor.process_frame(&local).unwrap();
+    assert_eq!(processor.canvas().get_pixel(0, 0), &BLUE);
+}
+
+#[test]
+fn test_interlaced_frame() {
+    // The 10 rows of an interlaced frame are stored in the order 0, 8, 4, 2, 6, 1, 3, 5, 7, 9
+    let order = [0, 8, 4, 2, 6, 1, 3, 5, 7, 9];
+    let buffer: Vec<u8> = order.iter().map(|row| row % 4).collect();
+    let mut interlaced = frame((0, 0, 1, 10), buffer.clone(), DisposalMethod::Keep);
+    interlaced.interlaced = true;
+    let expected: Vec<_> = (0..10)
+        .map(|row| [RED, GREEN, BLUE, WHITE][row % 4])
+        .collect();
+    let mut processor = GifFrameProcessor::new(1, 10, Some(PALETTE.to_vec()));
+    let image = processor.process_frame(&interlaced).unwrap();
+    assert_eq!(image.pixels().copied().collect::<Vec<_>>(), expected);
+
+    // The decoder deinterlaces the frames of a file
+    let mut data = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut data, 1, 10, &PALETTE).unwrap();
+        encoder.write_frame(&interlaced).unwrap();
+    }
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(data.as_slice()).unwrap();
+    let frame = decoder.read_next_frame().unwrap().unwrap();
+    let mut processor = GifFrameProcessor::new(1, 10, Some(PALETTE.to_vec()));
+    let image = processor.process_frame(frame).unwrap();
+    assert_eq!(image.pixels().copied().collect::<Vec<_>>(), expected);
+}
@@ -1,7 +1,8 @@
 #[cfg(feature = "sixel")]
 mod tests {
     use image::RgbImage;
-    use image_to_console_core::indexed_image::IndexedImage;
+    use image_to_console_core::indexed_image::{IndexedImage, SixelPalette};
+    use quantette::palette::Srgb;
 
     #[test]
     fn test_indexed_image_creation() {
@@ -42,4 +43,101 @@ mod tests {
         assert!(pixel01 < indexed_img.palette.len() as u8);
         assert!(pixel11 < indexed_img.palette.len() as u8);
     }
+
+    #[test]
+    fn test_indexed_image_transparent() {
+        let mut img = image::RgbaImage::new(2, 2);
+        img.put_pixel(0, 0, image::Rgba([255, 0, 0, 255])); // Red
+        img.put_pixel(1, 0, image::Rgba([0, 255, 0, 0])); // Transparent
+        img.put_pixel(0, 1, image::Rgba([0, 0, 255, 255])); // Blue
+        img.put_pixel(1, 1, image::Rgba([255, 255, 255, 100])); // Transparent
+
+        let indexed_img = IndexedImage::from_rgba_image(
+            &img,
+            256,
+            false,
+            quantette::QuantizeMethod::wu(),
+            quantette::ColorSpace::Srgb,
+        )
+        .unwrap();
+        let transparent_index = indexed_img.transparent_index.unwrap();
+        assert_eq!(transparent_index as usize, indexed_img.palette.len());
+        assert!(!indexed_img.is_transparent(0, 0));
+        assert!(indexed_img.is_transparent(1, 0));
+        assert!(!indexed_img.is_transparent(0, 1));
+        assert!(indexed_img.is_transparent(1, 1));
+
+        // The reserved index takes one of the max colors
+        let indexed_img = IndexedImage::from_rgba_image(
+            &img,
+            2,
+            false,
+            quantette::QuantizeMethod::wu(),
+            quantette::ColorSpace::Srgb,
+        )
+        .unwrap();
+        assert_eq!(indexed_img.palette.len(), 1);
+        assert_eq!(indexed_img.transparent_index, Some(1));
+
+        // An opaque image has no transparent index
+        img.put_pixel(1, 0, image::Rgba([0, 255, 0, 255]));
+        img.put_pixel(1, 1, image::Rgba([255, 255, 255, 255]));
+        let indexed_img = IndexedImage::from_rgba_image(
+            &img,
+            256,
+            false,
+            quantette::QuantizeMethod::wu(),
+            quantette::ColorSpace::Srgb,
+        )
+        .unwrap();
+        assert_eq!(indexed_img.transparent_index, None);
+    }
+
+    #[test]
+    fn test_indexed_image_from_palette() {
+        let palette = vec![
+            Srgb::new(0u8, 0, 0),
+            Srgb::new(255, 0, 0),
+            Srgb::new(255, 255, 255),
+        ];
+        let mut img = RgbImage::new(2, 1);
+        img.put_pixel(0, 0, image::Rgb([250, 10, 5])); // Near red
+        img.put_pixel(1, 0, image::Rgb([20, 20, 20])); // Near black
+
+        let indexed_img = IndexedImage::from_palette(&img, &palette, false);
+        assert_eq!(indexed_img.palette, palette);
+        assert_eq!(indexed_img.index_data, vec![1, 0]);
+        assert_eq!(indexed_img.transparent_index, None);
+    }
+
+    #[test]
+    fn test_sixel_palette_registers() {
+        let palette = SixelPalette::new(vec![Srgb::new(0u8, 0, 0), Srgb::new(255, 0, 0)]);
+        assert_eq!(palette.take_changed().len(), 2);
+        // The registers are only defined once
+        assert!(palette.take_changed().is_empty());
+
+        // Only the changed register is defined again
+        palette.set_colors(vec![Srgb::new(0u8, 0, 0), Srgb::new(0, 0, 255)]);
+        assert_eq!(palette.take_changed(), vec![(1, Srgb::new(0, 0, 255))]);
+
+        palette.reset();
+        assert_eq!(palette.take_changed().len(), 2);
+    }
+
+    #[test]
+    fn test_sixel_palette_from_gif_palette() {
+        let palette =
+            SixelPalette::from_gif_palette(&[0, 0, 0, 255, 0, 0, 0, 0, 255], 256).unwrap();
+        assert_eq!(
+            palette.colors(),
+            vec![
+                Srgb::new(0u8, 0, 0),
+                Srgb::new(255, 0, 0),
+                Srgb::new(0, 0, 255)
+            ]
+        );
+        let palette = SixelPalette::from_gif_palette(&[0, 0, 0, 255, 0, 0, 0, 0, 255], 2).unwrap();
+        assert_eq!(palette.colors().len(), 2);
+    }
 }
@@ -0,0 +1,68 @@
+use image_to_console_core::{
+    DisplayMode, ProcessedImage,
+    converter::{ImageConverter, ImageConverterOption},
+    passthrough::{Multiplexer, SCREEN_PACKET_SIZE},
+};
+
+#[test]
+fn test_tmux_wrap() {
+    let data = "\x1b[2;3H\x1b_Ga=T;AAAA\x1b\\\x1b]1337;File=:AAAA\x07 ";
+    assert_eq!(
+        Multiplexer::Tmux.wrap(data),
+        "\x1b[2;3H\x1bPtmux;\x1b\x1b_Ga=T;AAAA\x1b\x1b\\\x1b\\\x1bPtmux;\x1b\x1b]1337;File=:AAAA\x07\x1b\\ "
+    );
+    // The text without graphics sequences is not changed
+    assert_eq!(Multiplexer::Tmux.wrap("\x1b[0mabc"), "\x1b[0mabc");
+}
+
+#[test]
+fn test_screen_wrap() {
+    let sequence = format!("\x1bPq{}\x1b\\", "~".repeat(2000));
+    let wrapped = Multiplexer::Screen.wrap_sequence(&sequence);
+    let packets = wrapped
+        .strip_prefix("\x1bP")
+        .unwrap()
+        .strip_suffix("\x1b\\")
+        .unwrap()
+        .split("\x1b\\\x1bP")
+        .collect::<Vec<_>>();
+    for packet in &packets {
+        assert!(packet.len() + 4 <= SCREEN_PACKET_SIZE);
+        assert!(!packet.contains("\x1b\\"));
+    }
+    // The inner string terminator is split between two packets
+    assert_eq!(packets.last(), Some(&"\\"));
+    assert_eq!(packets.concat(), sequence);
+}
+
+#[cfg(feature = "sixel")]
+#[test]
+fn test_converter_passthrough() {
+    let img = image::RgbImage::from_pixel(4, 6, image::Rgb([255, 0, 0]));
+    let options = ImageConverterOption::default()
+        .mode(DisplayMode::SixelFull)
+        .width(4)
+        .height(6)
+        .passthrough(Some(Multiplexer::Tmux))
+        .get_options();
+    let result = ImageConverter::new(ProcessedImage::Color2(img), options)
+        .convert()
+        .unwrap();
+    assert!(result[0].starts_with("\x1bPtmux;\x1b\x1bP9;1q"));
+    assert!(result[0].ends_with("\x1b\x1b\\\x1b\\"));
+
+    // The normal modes don't write graphics sequences
+    let options = ImageConverterOption::default()
+        .mode(DisplayMode::FullColor)
+        .width(2)
+        .height(2)
+        .center(false)
+        .passthrough(Some(Multiplexer::Tmux))
+        .get_options();
+    let img = ProcessedImage::new(
+        DisplayMode::FullColor,
+        &image::DynamicImage::new_rgba8(2, 2),
+    );
+    let result = ImageConverter::new(img, options).convert().unwrap();
+    assert!(!result.concat().contains("tmux"));
+}
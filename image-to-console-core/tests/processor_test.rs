@@ -53,3 +53,38 @@ fn test_all_protocol_convert() {
         assert!(result.is_ok())
     }
 }
+
+#[test]
+fn test_fit_size() {
+    use image_to_console_core::{AutoResizeOption, CustomResizeOption};
+
+    let mut option = ImageProcessorOptions::default();
+    // A terminal of 80x24 cells is 80x48 pixels in the full color mode
+    option.option_terminal_size(Some((80, 24)));
+    assert_eq!(option.fit_size((1920, 1080)).unwrap(), (80, 45));
+    assert_eq!(option.fit_size((1080, 1920)).unwrap(), (27, 48));
+    // A small image is not enlarged
+    assert_eq!(option.fit_size((40, 20)).unwrap(), (40, 20));
+
+    // The processor resizes the image to the same size
+    let image = image::DynamicImage::new_rgb8(1920, 1080);
+    let mut processor = ImageProcessor::new(image, option);
+    let (image, size, _, _) = processor.process_only().unwrap();
+    assert_eq!((image.width(), image.height()), (80, 45));
+    assert_eq!(size, (80, 45));
+
+    option.option_resize(ResizeMode::Auto(AutoResizeOption::only_width()));
+    assert_eq!(option.fit_size((1080, 1920)).unwrap(), (80, 142));
+
+    option.option_resize(ResizeMode::Custom(CustomResizeOption {
+        width: Some(100),
+        height: None,
+    }));
+    assert_eq!(option.fit_size((1920, 1080)).unwrap(), (100, 1080));
+
+    // The graphics protocols are scaled by the terminal
+    option
+        .option_resize(ResizeMode::default())
+        .option_display_mode(DisplayMode::Kitty);
+    assert_eq!(option.fit_size((1920, 1080)).unwrap(), (1920, 1080));
+}
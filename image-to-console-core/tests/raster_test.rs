@@ -0,0 +1,73 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use image_to_console_core::{
+    DisplayMode, ProcessedImage,
+    converter::{
+        ImageConverter, ImageConverterOption,
+        cell::{Cell, CellAttributes, CellGrid},
+        raster::{BitmapFont, RasterOptions},
+    },
+};
+
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+#[test]
+fn test_rasterize_cells() {
+    let mut grid = CellGrid::new(3, 1);
+    grid.cells = vec![
+        Cell::new('▀', Some([255, 0, 0]), Some([255, 255, 255])),
+        Cell::new('A', None, None),
+        Cell::new(' ', None, None).with_attributes(CellAttributes {
+            underline: true,
+            ..Default::default()
+        }),
+    ];
+    let image = grid.to_image(&RasterOptions::default());
+    assert_eq!(image.dimensions(), (24, 16));
+    // The upper half of the block is the foreground
+    assert_eq!(*image.get_pixel(0, 7), RED);
+    assert_eq!(*image.get_pixel(0, 8), WHITE);
+    // The first row of `A` is `..##....`, every font row is two pixels high
+    assert_eq!(*image.get_pixel(8 + 1, 1), BLACK);
+    assert_eq!(*image.get_pixel(8 + 2, 1), WHITE);
+    assert_eq!(*image.get_pixel(8 + 4, 1), BLACK);
+    // The underline is on the last row
+    assert_eq!(*image.get_pixel(16, 14), BLACK);
+    assert_eq!(*image.get_pixel(16, 15), WHITE);
+
+    // A 1x1 font with a single glyph, the other characters fall back to `?`
+    let font = BitmapFont::new(1, 1, '?', vec![1]).unwrap();
+    let options = RasterOptions::default()
+        .cell_size(2, 2)
+        .font(font)
+        .default_colors([0, 0, 255], [0, 0, 0])
+        .get_options();
+    let image = grid.to_image(&options);
+    assert_eq!(image.dimensions(), (6, 2));
+    assert_eq!(*image.get_pixel(3, 1), Rgba([0, 0, 255, 255]));
+    assert!(BitmapFont::new(8, 8, ' ', vec![0; 7]).is_none());
+
+    let png = grid.to_png(&RasterOptions::default()).unwrap();
+    let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+    assert_eq!(decoded, grid.to_image(&RasterOptions::default()));
+}
+
+#[test]
+fn test_rasterize_converter_output() {
+    let img = RgbaImage::from_pixel(2, 2, RED);
+    let options = ImageConverterOption::default()
+        .mode(DisplayMode::FullColor)
+        .width(2)
+        .height(2)
+        .get_options();
+    let grid = ImageConverter::new(
+        ProcessedImage::new(DisplayMode::FullColor, &DynamicImage::from(img)),
+        options,
+    )
+    .convert_cells()
+    .unwrap();
+    let image = grid.to_image(&RasterOptions::default());
+    assert_eq!(image.dimensions(), (16, 16));
+    assert!(image.pixels().all(|pixel| *pixel == RED));
+}
@@ -0,0 +1,10 @@
+//! [ratatui](https://ratatui.rs) widgets drawing images with `image_to_console_core`
+//!
+//! [`CellGridWidget`] draws a converted character-cell image, and [`Image`] draws an image
+//! with [`ImageState`] in any display mode, the graphics protocols included.
+
+pub mod state;
+pub mod widget;
+
+pub use state::ImageState;
+pub use widget::{CellGridWidget, Image};
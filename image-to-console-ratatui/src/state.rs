@@ -0,0 +1,354 @@
+use crate::widget::draw_grid;
+use image::{DynamicImage, imageops::FilterType};
+use image_to_console_core::{
+    DisplayMode, ProcessedImage,
+    converter::{ImageConverter, ImageConverterOption, Iterm2Options, Iterm2Size, cell::CellGrid},
+    error::ConvertError,
+    passthrough::Multiplexer,
+};
+use ratatui_core::{
+    buffer::{Buffer, CellDiffOption},
+    layout::Rect,
+};
+use std::num::NonZeroU16;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// The size of a cell in pixels used until [`ImageState::cell_size`] is set
+pub const DEFAULT_CELL_SIZE: (u32, u32) = (10, 20);
+
+/// The kitty image ids, one per state
+static NEXT_KITTY_ID: AtomicU32 = AtomicU32::new(1);
+
+/// An image converted for an area size
+enum Conversion {
+    /// The cells of a character-cell mode
+    Cells(CellGrid),
+    /// The sequence of a graphics protocol and its size in cells
+    Graphics {
+        sequence: String,
+        width: u16,
+        height: u16,
+    },
+}
+
+/// A graphics image on the screen
+struct Placement {
+    /// The cells covered by the image
+    area: Rect,
+    /// The symbol of the first cell
+    symbol: String,
+}
+
+/// The state of the [`Image`](crate::Image) widget
+///
+/// The state keeps the conversion of the image, so it has to live as long as the image is shown.
+/// When the image is not drawn anymore, write [`ImageState::cleanup`] to the terminal
+/// to remove a kitty image.
+pub struct ImageState {
+    /// The image to draw
+    image: DynamicImage,
+    /// The display mode
+    mode: DisplayMode,
+    /// The size of a cell in pixels
+    cell_size: (u32, u32),
+    /// The multiplexer to pass the graphics sequences through
+    passthrough: Option<Multiplexer>,
+    /// The id of the kitty image
+    kitty_id: u32,
+    /// The last conversion and the area size it was made for
+    conversion: Option<((u16, u16), Conversion)>,
+    /// The graphics image on the screen
+    placement: Option<Placement>,
+    /// The error of the last conversion
+    error: Option<ConvertError>,
+}
+
+impl ImageState {
+    /// Create the state of an image
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The image to draw
+    /// * `mode` - The display mode, the shared memory kitty modes are drawn as `Kitty`
+    ///   and `KittyNoColor`, and `SixelHalf` as `SixelFull`
+    ///
+    /// # Returns
+    ///
+    /// Returns a new state
+    pub fn new(image: DynamicImage, mode: DisplayMode) -> Self {
+        Self {
+            image,
+            mode: widget_mode(mode),
+            cell_size: DEFAULT_CELL_SIZE,
+            passthrough: None,
+            kitty_id: NEXT_KITTY_ID.fetch_add(1, Ordering::Relaxed),
+            conversion: None,
+            placement: None,
+            error: None,
+        }
+    }
+
+    /// Sets the image, it is converted again on the next draw
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The image to draw
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn set_image(&mut self, image: DynamicImage) -> &mut Self {
+        self.image = image;
+        self.conversion = None;
+        self
+    }
+
+    /// Sets the display mode
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The display mode
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn mode(&mut self, mode: DisplayMode) -> &mut Self {
+        self.mode = widget_mode(mode);
+        self.conversion = None;
+        self
+    }
+
+    /// Sets the size of a cell in pixels, used to fit the graphics protocols in the area
+    ///
+    /// Probe it with `image_to_console_core::capabilities::capabilities().cell_size`
+    /// before the terminal enters the raw mode of the application.
+    ///
+    /// # Arguments
+    ///
+    /// * `cell_size` - The width and the height of a cell in pixels
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn cell_size(&mut self, cell_size: (u32, u32)) -> &mut Self {
+        self.cell_size = (cell_size.0.max(1), cell_size.1.max(1));
+        self.conversion = None;
+        self
+    }
+
+    /// Sets the multiplexer to pass the graphics sequences through
+    ///
+    /// # Arguments
+    ///
+    /// * `passthrough` - The multiplexer, usually `Multiplexer::detect()`
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn passthrough(&mut self, passthrough: Option<Multiplexer>) -> &mut Self {
+        self.passthrough = passthrough;
+        self.conversion = None;
+        self
+    }
+
+    /// Get the error of the last conversion, nothing is drawn when the conversion fails
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the image was converted
+    pub fn error(&self) -> Option<&ConvertError> {
+        self.error.as_ref()
+    }
+
+    /// Forget the graphics image on the screen
+    ///
+    /// The cells of sixel and iTerm2 images are redrawn by ratatui,
+    /// but a kitty image stays above the text until it is deleted.
+    ///
+    /// # Returns
+    ///
+    /// Returns the sequence deleting the kitty image, to write to the terminal,
+    /// or an empty string if there is nothing to delete
+    pub fn cleanup(&mut self) -> String {
+        match self.placement.take() {
+            Some(_) => self.delete_sequence(),
+            None => String::new(),
+        }
+    }
+
+    /// Draw the image in the area
+    pub(crate) fn render(&mut self, area: Rect, buf: &mut Buffer) {
+        let area = area.intersection(buf.area);
+        if area.is_empty() {
+            return;
+        }
+        let size = (area.width, area.height);
+        if self.conversion.as_ref().is_none_or(|(s, _)| *s != size) {
+            self.conversion = match self.convert(size) {
+                Ok(conversion) => {
+                    self.error = None;
+                    Some((size, conversion))
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    None
+                }
+            };
+        }
+        let Some((_, conversion)) = &self.conversion else {
+            return;
+        };
+        match conversion {
+            Conversion::Cells(grid) => {
+                let (width, height) = (grid.width as u16, grid.height as u16);
+                draw_grid(grid, center(area, width, height), buf);
+            }
+            Conversion::Graphics {
+                sequence,
+                width,
+                height,
+            } => {
+                let target = center(area, *width, *height);
+                let symbol = match &self.placement {
+                    Some(placement) if placement.area == target => {
+                        // The same symbol is not written again by ratatui
+                        if placement.symbol.ends_with(sequence.as_str()) {
+                            placement.symbol.clone()
+                        } else {
+                            sequence.clone()
+                        }
+                    }
+                    // The image moved, the kitty image at the old place is deleted first
+                    Some(_) => self.delete_sequence() + sequence,
+                    None => sequence.clone(),
+                };
+                place(target, &symbol, buf);
+                self.placement = Some(Placement {
+                    area: target,
+                    symbol,
+                });
+            }
+        }
+    }
+
+    /// Convert the image for an area size
+    fn convert(&self, (columns, rows): (u16, u16)) -> Result<Conversion, ConvertError> {
+        let (width, height) = (columns as u32, rows as u32);
+        if self.mode.is_normal() {
+            // A cell is two pixels high in the full modes, and a pixel is two cells wide otherwise
+            let (max_width, max_height) = if self.mode.is_full() {
+                (width, height * 2)
+            } else {
+                (width / 2, height)
+            };
+            if max_width == 0 || max_height == 0 {
+                return Ok(Conversion::Cells(CellGrid::new(0, 0)));
+            }
+            let image = self
+                .image
+                .resize(max_width, max_height, FilterType::Triangle);
+            let options = ImageConverterOption::default()
+                .mode(self.mode)
+                .width(image.width())
+                .height(image.height())
+                .center(false)
+                .get_options();
+            let mut grid = ImageConverter::new(ProcessedImage::new(self.mode, &image), options)
+                .convert_cells()?;
+            (grid.left, grid.top) = (0, 0);
+            return Ok(Conversion::Cells(grid));
+        }
+
+        let (cell_width, cell_height) = self.cell_size;
+        let image = self.image.resize(
+            width * cell_width,
+            height * cell_height,
+            FilterType::Triangle,
+        );
+        let (columns, rows) = (
+            image.width().div_ceil(cell_width).clamp(1, width),
+            image.height().div_ceil(cell_height).clamp(1, height),
+        );
+        let options = ImageConverterOption::default()
+            .mode(self.mode)
+            .width(image.width())
+            .height(image.height())
+            .center(false)
+            .iterm2(Iterm2Options {
+                width: Some(Iterm2Size::Cells(columns)),
+                height: Some(Iterm2Size::Cells(rows)),
+                do_not_move_cursor: true,
+                ..Default::default()
+            })
+            .get_options();
+        let mut sequence = ImageConverter::new(ProcessedImage::new(self.mode, &image), options)
+            .convert()?
+            .concat();
+        if matches!(self.mode, DisplayMode::Kitty | DisplayMode::KittyNoColor) {
+            // Identify the image to delete it later, scale it to the cells,
+            // keep the cursor in place and ignore the replies of the terminal
+            sequence = sequence.replacen(
+                "\x1b_G",
+                &format!("\x1b_Gi={},c={columns},r={rows},C=1,q=2,", self.kitty_id),
+                1,
+            );
+        }
+        if let Some(multiplexer) = self.passthrough {
+            sequence = multiplexer.wrap(&sequence);
+        }
+        Ok(Conversion::Graphics {
+            sequence,
+            width: columns as u16,
+            height: rows as u16,
+        })
+    }
+
+    /// Get the sequence deleting the kitty image, empty in the other modes
+    fn delete_sequence(&self) -> String {
+        if !matches!(self.mode, DisplayMode::Kitty | DisplayMode::KittyNoColor) {
+            return String::new();
+        }
+        let sequence = format!("\x1b_Ga=d,d=I,i={},q=2\x1b\\", self.kitty_id);
+        match self.passthrough {
+            Some(multiplexer) => multiplexer.wrap_sequence(&sequence),
+            None => sequence,
+        }
+    }
+}
+
+/// Map the display modes the widget cannot draw to the closest one
+fn widget_mode(mode: DisplayMode) -> DisplayMode {
+    match mode {
+        #[cfg(target_os = "linux")]
+        DisplayMode::KittyShm => DisplayMode::Kitty,
+        #[cfg(target_os = "linux")]
+        DisplayMode::KittyShmNoColor => DisplayMode::KittyNoColor,
+        DisplayMode::SixelHalf => DisplayMode::SixelFull,
+        mode => mode,
+    }
+}
+
+/// Center a size in an area
+fn center(area: Rect, width: u16, height: u16) -> Rect {
+    let (width, height) = (width.min(area.width), height.min(area.height));
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Put a graphics sequence in the first cell of the area, ratatui skips the other cells
+fn place(area: Rect, symbol: &str, buf: &mut Buffer) {
+    for position in area.positions() {
+        let cell = &mut buf[position];
+        cell.reset();
+        cell.set_diff_option(CellDiffOption::Skip);
+    }
+    if let Some(width) = NonZeroU16::new(area.width) {
+        buf[area.as_position()]
+            .set_symbol(symbol)
+            .set_diff_option(CellDiffOption::ForcedWidth(width));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::state::ImageState;
+use image_to_console_core::converter::cell::{Cell, CellGrid};
+use ratatui_core::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{StatefulWidget, Widget},
+};
+
+/// Draws the cells of a character-cell image, e.g. `ImageProcessorResult::cells`
+///
+/// The grid is drawn at the top-left corner of the area and cut at its edges,
+/// `left` and `top` of the grid are ignored.
+pub struct CellGridWidget<'a> {
+    grid: &'a CellGrid,
+}
+
+impl<'a> CellGridWidget<'a> {
+    /// Create a widget for a grid
+    ///
+    /// # Arguments
+    ///
+    /// * `grid` - The cells of the image
+    ///
+    /// # Returns
+    ///
+    /// Returns a new widget
+    pub fn new(grid: &'a CellGrid) -> Self {
+        Self { grid }
+    }
+}
+
+impl Widget for CellGridWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        draw_grid(self.grid, area, buf);
+    }
+}
+
+/// Draws an image in the display mode of its [`ImageState`]
+///
+/// The image is scaled to fit the area and centered in it. The conversion is kept in the
+/// state, so the image is only converted again when the area size or the image changes.
+pub struct Image;
+
+impl StatefulWidget for Image {
+    type State = ImageState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        state.render(area, buf);
+    }
+}
+
+/// Copy the cells of a grid to the buffer, from the top-left corner of `area`
+pub(crate) fn draw_grid(grid: &CellGrid, area: Rect, buf: &mut Buffer) {
+    let area = area.intersection(buf.area);
+    for y in 0..grid.height.min(area.height as u32) {
+        for (x, cell) in grid.row(y).iter().take(area.width as usize).enumerate() {
+            let target = &mut buf[(area.x + x as u16, area.y + y as u16)];
+            target.reset();
+            target.set_char(cell.glyph).set_style(style(cell));
+        }
+    }
+}
+
+/// Get the ratatui style of a cell, `None` colors are the default colors of the terminal
+fn style(cell: &Cell) -> Style {
+    let color = |color: Option<[u8; 3]>| match color {
+        Some([r, g, b]) => Color::Rgb(r, g, b),
+        None => Color::Reset,
+    };
+    let mut modifier = Modifier::empty();
+    for (set, flag) in [
+        (cell.attrs.bold, Modifier::BOLD),
+        (cell.attrs.italic, Modifier::ITALIC),
+        (cell.attrs.underline, Modifier::UNDERLINED),
+        (cell.attrs.reverse, Modifier::REVERSED),
+    ] {
+        if set {
+            modifier |= flag;
+        }
+    }
+    Style::new()
+        .fg(color(cell.fg))
+        .bg(color(cell.bg))
+        .add_modifier(modifier)
+}
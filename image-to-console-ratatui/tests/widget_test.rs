@@ -0,0 +1,100 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use image_to_console_core::{
+    DisplayMode,
+    converter::cell::{Cell, CellAttributes, CellGrid},
+};
+use image_to_console_ratatui::{CellGridWidget, Image, ImageState};
+use ratatui_core::{
+    backend::TestBackend,
+    buffer::{Buffer, CellDiffOption},
+    layout::Rect,
+    style::{Color, Modifier},
+    terminal::Terminal,
+    widgets::{StatefulWidget, Widget},
+};
+use std::num::NonZeroU16;
+
+const RED: Color = Color::Rgb(255, 0, 0);
+
+fn red_image(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::from(RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255])))
+}
+
+#[test]
+fn test_cell_modes() {
+    let mut terminal = Terminal::new(TestBackend::new(6, 3)).unwrap();
+    let mut state = ImageState::new(red_image(4, 2), DisplayMode::FullColor);
+    terminal
+        .draw(|frame| frame.render_stateful_widget(Image, frame.area(), &mut state))
+        .unwrap();
+    assert!(state.error().is_none());
+    // 6x3 pixels, the odd last row only has the top pixel
+    let buffer = terminal.backend().buffer();
+    for x in 0..6 {
+        assert_eq!(buffer[(x, 0)].symbol(), "█");
+        assert_eq!(buffer[(x, 0)].fg, RED);
+        assert_eq!(buffer[(x, 1)].symbol(), "▀");
+        assert_eq!(buffer[(x, 1)].bg, Color::Reset);
+        assert_eq!(buffer[(x, 2)].symbol(), " ");
+    }
+
+    // A pixel is two columns wide
+    let mut state = ImageState::new(red_image(1, 1), DisplayMode::HalfColor);
+    let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 3));
+    Image.render(buffer.area, &mut buffer, &mut state);
+    assert!(
+        buffer
+            .content
+            .iter()
+            .all(|cell| cell.symbol() == " " && cell.bg == RED)
+    );
+
+    let mut grid = CellGrid::new(2, 1);
+    grid.left = 3;
+    grid.cells[0] = Cell::new('a', None, Some([255, 0, 0])).with_attributes(CellAttributes {
+        bold: true,
+        ..Default::default()
+    });
+    let mut buffer = Buffer::empty(Rect::new(0, 0, 3, 1));
+    CellGridWidget::new(&grid).render(Rect::new(1, 0, 1, 1), &mut buffer);
+    // `left` is ignored and the grid is cut at the edge of the area
+    assert_eq!(buffer[(1, 0)].symbol(), "a");
+    assert_eq!(buffer[(1, 0)].bg, RED);
+    assert_eq!(buffer[(1, 0)].modifier, Modifier::BOLD);
+    assert_eq!(buffer[(2, 0)], ratatui_core::buffer::Cell::EMPTY);
+}
+
+#[test]
+fn test_kitty_placement() {
+    let mut state = ImageState::new(red_image(40, 20), DisplayMode::Kitty);
+    state.cell_size((10, 20));
+    let mut buffer = Buffer::empty(Rect::new(0, 0, 10, 5));
+    Image.render(Rect::new(1, 1, 4, 2), &mut buffer, &mut state);
+    // 40x20 pixels are 4x1 cells, centered in the area
+    let sequence = buffer[(1, 1)].symbol().to_string();
+    assert!(sequence.starts_with("\x1b_Gi="));
+    assert!(sequence.contains(",c=4,r=1,C=1,q=2,m=0,a=T,f=100,s=40,v=20"));
+    assert_eq!(
+        buffer[(1, 1)].diff_option,
+        CellDiffOption::ForcedWidth(NonZeroU16::new(4).unwrap())
+    );
+    assert_eq!(buffer[(4, 1)].diff_option, CellDiffOption::Skip);
+    assert_eq!(buffer[(1, 2)].diff_option, CellDiffOption::None);
+
+    // The same place keeps the same symbol, so ratatui does not write it again
+    let mut buffer = Buffer::empty(Rect::new(0, 0, 10, 5));
+    Image.render(Rect::new(1, 1, 4, 2), &mut buffer, &mut state);
+    assert_eq!(buffer[(1, 1)].symbol(), sequence);
+
+    // The image moved, the old one is deleted
+    let mut buffer = Buffer::empty(Rect::new(0, 0, 10, 5));
+    Image.render(Rect::new(3, 2, 4, 2), &mut buffer, &mut state);
+    let moved = buffer[(3, 2)].symbol();
+    assert!(moved.starts_with("\x1b_Ga=d,d=I,i="));
+    assert!(moved.ends_with(&sequence));
+    assert_eq!(buffer[(1, 1)].diff_option, CellDiffOption::None);
+
+    let cleanup = state.cleanup();
+    assert!(cleanup.starts_with("\x1b_Ga=d,d=I,i=") && cleanup.ends_with("\x1b\\"));
+    assert_eq!(state.cleanup(), "");
+}
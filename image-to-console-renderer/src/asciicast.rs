@@ -0,0 +1,202 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Result, Write},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The terminal size of a recording made without a terminal, in columns and rows
+pub const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+/// Records the output of the players to an [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) file
+///
+/// The times of the events are given by the player (the delays of the frames), not measured,
+/// so a recording made without sleeping between the frames has the same timing as the playback.
+/// The header is written with the first event, and its size is measured from the first
+/// output unless it is set with [`AsciicastRecorder::size`]. The line feeds are recorded as
+/// CR LF, like the output of a terminal which is not in raw mode.
+pub struct AsciicastRecorder {
+    /// The cast file
+    writer: Box<dyn Write + Send>,
+    /// The terminal size in the header
+    size: Option<(u32, u32)>,
+    /// The output written before the first event
+    preamble: String,
+    /// Whether the header is written
+    started: bool,
+}
+
+impl AsciicastRecorder {
+    /// Create a recorder writing to a file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the `.cast` file
+    ///
+    /// # Returns
+    ///
+    /// Returns a new recorder, or the error of creating the file
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Create a recorder writing to any writer
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The writer of the cast
+    ///
+    /// # Returns
+    ///
+    /// Returns a new recorder
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Box::new(writer),
+            size: None,
+            preamble: String::new(),
+            started: false,
+        }
+    }
+
+    /// Sets the terminal size in the header, instead of measuring the first output
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width in columns
+    /// * `height` - Height in rows
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn size(&mut self, width: u32, height: u32) -> &mut Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Sets the output written before the first event, e.g. hiding the cursor
+    ///
+    /// # Arguments
+    ///
+    /// * `preamble` - The output
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn preamble(&mut self, preamble: &str) -> &mut Self {
+        self.preamble.push_str(preamble);
+        self
+    }
+
+    /// Record an output event
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The time of the event since the start of the recording
+    /// * `data` - The output written to the terminal
+    pub fn record(&mut self, time: Duration, data: &str) -> Result<()> {
+        let data = if self.started {
+            data.to_string()
+        } else {
+            std::mem::take(&mut self.preamble) + data
+        };
+        if !self.started {
+            let (width, height) = self.size.unwrap_or_else(|| measure(&data));
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs());
+            writeln!(
+                self.writer,
+                "{{\"version\": 2, \"width\": {width}, \"height\": {height}, \
+                 \"timestamp\": {timestamp}, \"env\": {{\"TERM\": \"xterm-256color\"}}}}"
+            )?;
+            self.started = true;
+        }
+        writeln!(
+            self.writer,
+            "[{:.6}, \"o\", \"{}\"]",
+            time.as_secs_f64(),
+            escape_json(&data.replace('\n', "\r\n"))
+        )
+    }
+
+    /// Record the last event and flush the file
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The end of the recording, the last frame is shown until then
+    /// * `data` - The output restoring the terminal, e.g. showing the cursor
+    pub fn finish(mut self, time: Duration, data: &str) -> Result<()> {
+        if !self.started && self.size.is_none() {
+            self.size = Some((DEFAULT_SIZE.0 as u32, DEFAULT_SIZE.1 as u32));
+        }
+        self.record(time, data)?;
+        self.writer.flush()
+    }
+}
+
+/// Escape a string for a JSON string literal
+///
+/// # Arguments
+///
+/// * `data` - The string
+///
+/// # Returns
+///
+/// Returns the escaped string, without the quotes
+pub fn escape_json(data: &str) -> String {
+    let mut escaped = String::with_capacity(data.len() + data.len() / 4);
+    for c in data.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Measure the size of an output in columns and rows, the escape sequences are not counted
+fn measure(data: &str) -> (u32, u32) {
+    let (mut width, mut height, mut column) = (1, 1, 0);
+    let mut chars = data.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI ends with a byte from `@` to `~`
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC, DCS and APC end with BEL or ST
+                Some(']' | 'P' | '_') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\n' => {
+                height += 1;
+                column = 0;
+            }
+            '\r' => column = 0,
+            c if !c.is_control() => {
+                column += 1;
+                width = width.max(column);
+            }
+            _ => {}
+        }
+    }
+    (width, height)
+}
@@ -0,0 +1,187 @@
+use rodio::{ChannelCount, SampleRate, Source, source::SeekError};
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+/// How long the played audio is kept, so the player can seek back in it
+const HISTORY: Duration = Duration::from_secs(60);
+
+/// The longest gap between two frames filled with silence, the longer gaps are jumps in the
+/// timestamps rather than missing audio
+const MAX_GAP: Duration = Duration::from_secs(10);
+
+/// The audio played with a video
+#[derive(Debug, Default)]
+pub enum VideoAudio {
+    /// An audio file
+    File(std::path::PathBuf),
+    /// The audio stream of the video, decoded from its container
+    Decoded(DecodedAudio),
+    #[default]
+    None,
+}
+
+/// The audio of a video decoded in memory, played as a rodio source
+///
+/// The samples are played at their timestamps, and the time of the next sample is stored in the
+/// clock of the playback, so the video follows the audio without another timer.
+pub struct DecodedAudio {
+    /// The frames of the audio, with the time of their first sample
+    frames: Box<dyn Iterator<Item = (Duration, Vec<f32>)> + Send>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    /// The samples to play
+    pending: VecDeque<f32>,
+    /// The samples played last, to seek back
+    played: VecDeque<f32>,
+    /// The index of the next sample since the start of the video, known after the first frame
+    position: Option<u64>,
+    /// The time of the next sample in milliseconds
+    clock: Arc<AtomicU64>,
+}
+
+impl DecodedAudio {
+    /// Create the audio of a video
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - The decoded frames, with the time of their first sample and their
+    ///   interleaved samples
+    /// * `channels` - The number of the channels
+    /// * `sample_rate` - The sample rate
+    /// * `clock` - The clock of the playback, set to the time of the next sample
+    ///
+    /// # Returns
+    ///
+    /// Returns a new source
+    pub fn new(
+        frames: impl Iterator<Item = (Duration, Vec<f32>)> + Send + 'static,
+        channels: ChannelCount,
+        sample_rate: SampleRate,
+        clock: Arc<AtomicU64>,
+    ) -> Self {
+        Self {
+            frames: Box::new(frames),
+            channels: channels.max(1),
+            sample_rate: sample_rate.max(1),
+            pending: VecDeque::new(),
+            played: VecDeque::new(),
+            position: None,
+            clock,
+        }
+    }
+
+    /// Get the index of the first sample at a time
+    fn samples_at(&self, time: Duration) -> u64 {
+        (time.as_secs_f64() * self.sample_rate as f64).round() as u64 * self.channels as u64
+    }
+
+    fn sync_clock(&self) {
+        let position = self.position.unwrap_or_default() / self.channels as u64;
+        let millis = position * 1000 / self.sample_rate as u64;
+        self.clock.store(millis, Ordering::SeqCst);
+    }
+
+    /// Queue the samples of the next frame, after the silence of a gap before it
+    ///
+    /// # Returns
+    ///
+    /// Returns `false` at the end of the audio
+    fn fill(&mut self) -> bool {
+        let Some((pts, samples)) = self.frames.next() else {
+            return false;
+        };
+        let start = self.samples_at(pts);
+        let queued = *self.position.get_or_insert(start) + self.pending.len() as u64;
+        if start > queued && start - queued <= self.samples_at(MAX_GAP) {
+            self.pending
+                .extend(std::iter::repeat_n(0.0, (start - queued) as usize));
+        }
+        self.pending.extend(samples);
+        self.sync_clock();
+        true
+    }
+}
+
+impl std::fmt::Debug for DecodedAudio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DecodedAudio")
+            .field("channels", &self.channels)
+            .field("sample_rate", &self.sample_rate)
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Iterator for DecodedAudio {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        while self.pending.is_empty() {
+            if !self.fill() {
+                return None;
+            }
+        }
+        let sample = self.pending.pop_front()?;
+        self.played.push_back(sample);
+        if self.played.len() as u64 > self.samples_at(HISTORY) {
+            self.played.pop_front();
+        }
+        self.position = self.position.map(|position| position + 1);
+        Some(sample)
+    }
+}
+
+impl Source for DecodedAudio {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Seek back in the played audio or forward by skipping the samples
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        // The position is known after the first frame
+        if self.position.is_none() && !self.fill() {
+            return Ok(());
+        }
+        let target = self.samples_at(pos);
+        let position = self.position.unwrap_or_default();
+        if target < position {
+            let back = (position - target) as usize;
+            if back > self.played.len() {
+                return Err(SeekError::NotSupported {
+                    underlying_source: std::any::type_name::<Self>(),
+                });
+            }
+            let samples = self.played.split_off(self.played.len() - back);
+            for sample in samples.into_iter().rev() {
+                self.pending.push_front(sample);
+            }
+            self.position = Some(target);
+        } else {
+            for _ in position..target {
+                if self.next().is_none() {
+                    break;
+                }
+            }
+        }
+        self.sync_clock();
+        Ok(())
+    }
+}
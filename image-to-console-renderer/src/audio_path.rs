@@ -1,29 +1,15 @@
 #[derive(Debug, Clone, Default)]
 pub enum AudioPath {
-    #[cfg(feature = "rodio")]
-    Temp(std::path::PathBuf),
     #[cfg(feature = "rodio")]
     Custom(std::path::PathBuf),
     #[default]
     None,
 }
 
-#[cfg(feature = "rodio")]
-impl Drop for AudioPath {
-    fn drop(&mut self) {
-        if let AudioPath::Temp(path) = self {
-            if std::fs::exists(&path).unwrap_or_default() {
-                let _ = std::fs::remove_file(path);
-            }
-        }
-    }
-}
-
 impl AudioPath {
     #[cfg(feature = "rodio")]
     pub fn get_path(&self) -> Option<std::path::PathBuf> {
         match self {
-            AudioPath::Temp(path) => Some(path.clone()),
             AudioPath::Custom(path) => Some(path.clone()),
             AudioPath::None => None,
         }
@@ -1,5 +1,9 @@
+#[derive(Default)]
 pub struct Config {
     pub clear: bool,
+    pub alternate_screen: bool,
+    /// Read the keyboard controls while playing, in raw mode
+    pub interactive: bool,
     pub pause: bool,
     pub center: bool,
     pub show_time: bool,
@@ -8,9 +12,161 @@ pub struct Config {
     pub disable_print: bool,
     pub show_file_name: bool,
     pub output: Option<String>,
+    pub output_format: OutputFormat,
+    /// Only record the playback, without printing the frames or waiting between them
+    pub record_only: bool,
+    /// The options of the png and gif output
+    pub raster_options: image_to_console_core::converter::raster::RasterOptions,
+    /// How many times a GIF is played
+    pub loop_count: LoopCount,
     pub file_name: Option<String>,
     #[cfg(feature = "rodio")]
     pub audio: crate::audio_path::AudioPath,
     #[cfg(feature = "sixel_support")]
     pub mode: image_to_console_core::DisplayMode,
-}
\ No newline at end of file
+}
+
+/// How many times an animation is played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopCount {
+    /// Play the animation a number of times, at least once
+    Times(u32),
+    /// Play the animation until it is stopped
+    Forever,
+}
+
+impl Default for LoopCount {
+    /// Play the animation once
+    fn default() -> Self {
+        Self::Times(1)
+    }
+}
+
+impl LoopCount {
+    /// Check whether the animation is played more than once
+    pub fn is_looping(&self) -> bool {
+        *self != Self::Times(1)
+    }
+
+    /// Check whether another pass is played
+    ///
+    /// # Arguments
+    ///
+    /// * `passes` - The number of passes played
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the animation is played again after the passes
+    pub fn has_next(&self, passes: u32) -> bool {
+        match self {
+            Self::Times(times) => passes < *times,
+            Self::Forever => true,
+        }
+    }
+}
+
+impl From<image_to_console_core::image::metadata::LoopCount> for LoopCount {
+    fn from(value: image_to_console_core::image::metadata::LoopCount) -> Self {
+        use image_to_console_core::image::metadata::LoopCount;
+        match value {
+            LoopCount::Infinite => Self::Forever,
+            LoopCount::Finite(times) => Self::Times(times.get()),
+        }
+    }
+}
+
+impl std::str::FromStr for LoopCount {
+    type Err = String;
+
+    /// Parse a loop count, `forever` (or `infinite`) loops forever and a number `N` plays
+    /// the animation `N` times
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("forever") || s.eq_ignore_ascii_case("infinite") {
+            return Ok(Self::Forever);
+        }
+        match s.parse::<u32>() {
+            Ok(times) if times > 0 => Ok(Self::Times(times)),
+            _ => Err(format!(
+                "invalid loop count `{s}`, expected a positive number or `forever`"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for LoopCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Times(times) => write!(f, "{times}"),
+            Self::Forever => write!(f, "forever"),
+        }
+    }
+}
+
+/// The format of the file written by `output`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The ANSI text written to the terminal
+    #[default]
+    Text,
+    /// A standalone HTML page (only for the character-cell modes)
+    Html,
+    /// An SVG image (only for the character-cell modes)
+    Svg,
+    /// A PNG image of the cells, drawn with the bundled font (only for the character-cell modes)
+    Png,
+    /// A GIF of the cells of an animation, drawn with the bundled font (only for the
+    /// character-cell modes)
+    Gif,
+    /// A directory of the ANSI frames of an animation, with a timing manifest
+    Frames,
+}
+
+impl OutputFormat {
+    /// Get the format of a file from its extension
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file
+    ///
+    /// # Returns
+    ///
+    /// Returns `Html` for `.html` and `.htm`, `Svg` for `.svg`, `Png` for `.png`, `Gif` for `.gif`,
+    /// `Frames` for a path ending with a separator and `Text` otherwise
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(std::path::is_separator) {
+            return Self::Frames;
+        }
+        let extension = std::path::Path::new(path)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("html" | "htm") => Self::Html,
+            Some("svg") => Self::Svg,
+            Some("png") => Self::Png,
+            Some("gif") => Self::Gif,
+            _ => Self::Text,
+        }
+    }
+
+    /// Get the extension of the format, without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Html => "html",
+            Self::Svg => "svg",
+            Self::Png => "png",
+            Self::Gif => "gif",
+            Self::Frames => "ans",
+        }
+    }
+
+    /// Check whether the format is only written by the animations
+    pub fn is_animation(&self) -> bool {
+        matches!(self, Self::Gif | Self::Frames)
+    }
+
+    /// Check whether the format is made from the cells of the image
+    pub fn needs_cells(&self) -> bool {
+        !matches!(self, Self::Text | Self::Frames)
+    }
+}
@@ -0,0 +1,212 @@
+use std::time::Duration;
+
+/// The playback speeds selected with `+` and `-`
+pub const SPEEDS: [f32; 9] = [0.25, 0.5, 0.75, 1.0, 1.25, 1.5, 2.0, 3.0, 4.0];
+/// How far left and right seek while playing
+pub const SEEK_STEP: Duration = Duration::from_secs(5);
+/// How long the input thread waits for a key before it checks whether to stop
+#[cfg(feature = "crossterm")]
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A command of the keyboard controls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// `space`: pause or resume
+    TogglePause,
+    /// `left`: seek backward, or step one frame back when paused
+    Backward,
+    /// `right`: seek forward, or step one frame forward when paused
+    Forward,
+    /// `+`: play faster
+    SpeedUp,
+    /// `-`: play slower
+    SpeedDown,
+    /// `q`, `esc` or `ctrl-c`: stop the playback
+    Quit,
+    /// `i`: show or hide the info
+    ToggleInfo,
+    /// `l`: turn looping on or off
+    ToggleLoop,
+}
+
+impl Command {
+    /// Get the command of a key
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key event
+    ///
+    /// # Returns
+    ///
+    /// Returns the command, or `None` if the key has no command or is released
+    #[cfg(feature = "crossterm")]
+    pub fn from_key(key: crossterm::event::KeyEvent) -> Option<Self> {
+        use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
+        if key.kind == KeyEventKind::Release {
+            return None;
+        }
+        // `ctrl-c` does not raise SIGINT in raw mode
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return matches!(key.code, KeyCode::Char('c')).then_some(Self::Quit);
+        }
+        match key.code {
+            KeyCode::Char(' ') => Some(Self::TogglePause),
+            KeyCode::Left => Some(Self::Backward),
+            KeyCode::Right => Some(Self::Forward),
+            KeyCode::Char('+' | '=') => Some(Self::SpeedUp),
+            KeyCode::Char('-' | '_') => Some(Self::SpeedDown),
+            KeyCode::Char('q' | 'Q') | KeyCode::Esc => Some(Self::Quit),
+            KeyCode::Char('i' | 'I') => Some(Self::ToggleInfo),
+            KeyCode::Char('l' | 'L') => Some(Self::ToggleLoop),
+            _ => None,
+        }
+    }
+}
+
+/// The state of the playback changed by the commands
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaybackState {
+    /// Whether the playback is paused
+    pub paused: bool,
+    /// Whether the info is shown
+    pub show_info: bool,
+    /// Whether the playback starts again at the end
+    pub looping: bool,
+    /// The index of the speed in [`SPEEDS`]
+    speed: usize,
+}
+
+impl PlaybackState {
+    /// Create the state of a playback at the normal speed
+    ///
+    /// # Arguments
+    ///
+    /// * `show_info` - Whether the info is shown
+    /// * `looping` - Whether the playback starts again at the end
+    ///
+    /// # Returns
+    ///
+    /// Returns a new state
+    pub fn new(show_info: bool, looping: bool) -> Self {
+        Self {
+            paused: false,
+            show_info,
+            looping,
+            speed: SPEEDS.iter().position(|&speed| speed == 1.0).unwrap(),
+        }
+    }
+
+    /// Get the playback speed
+    pub fn speed(&self) -> f32 {
+        SPEEDS[self.speed]
+    }
+
+    /// Apply a command to the state, seeking and quitting are left to the player
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the state changed
+    pub fn apply(&mut self, command: Command) -> bool {
+        let old = *self;
+        match command {
+            Command::TogglePause => self.paused = !self.paused,
+            Command::SpeedUp => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+            Command::SpeedDown => self.speed = self.speed.saturating_sub(1),
+            Command::ToggleInfo => self.show_info = !self.show_info,
+            Command::ToggleLoop => self.looping = !self.looping,
+            Command::Backward | Command::Forward | Command::Quit => {}
+        }
+        *self != old
+    }
+
+    /// Get the status shown after the frame index, e.g. ` | paused | 1.5x | loop on`
+    pub fn status(&self) -> String {
+        format!(
+            " | {} | {}x | loop {}",
+            if self.paused { "paused" } else { "playing" },
+            self.speed(),
+            if self.looping { "on" } else { "off" }
+        )
+    }
+}
+
+/// Reads the keys on another thread while the terminal is in raw mode
+///
+/// The raw mode is left when the controls are dropped. The line feeds are not followed by
+/// a carriage return in raw mode, so the output has to use `\r\n`.
+pub struct Controls {
+    commands: std::sync::mpsc::Receiver<Command>,
+    #[cfg(feature = "crossterm")]
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    #[cfg(feature = "crossterm")]
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Controls {
+    /// Enter raw mode and start reading the keys
+    ///
+    /// # Returns
+    ///
+    /// Returns the controls, or `None` if the `crossterm` feature is disabled,
+    /// stdin or stdout is not a terminal, or the raw mode cannot be entered
+    pub fn start() -> Option<Self> {
+        #[cfg(feature = "crossterm")]
+        {
+            use std::io::IsTerminal;
+            use std::sync::{
+                Arc,
+                atomic::{AtomicBool, Ordering},
+            };
+            if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+                return None;
+            }
+            crossterm::terminal::enable_raw_mode().ok()?;
+            let (sender, commands) = std::sync::mpsc::channel();
+            let stop = Arc::new(AtomicBool::new(false));
+            let stopped = stop.clone();
+            let thread = std::thread::spawn(move || {
+                use crossterm::event::{Event, poll, read};
+                while !stopped.load(Ordering::Relaxed) {
+                    match poll(POLL_INTERVAL) {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(_) => return,
+                    }
+                    if let Ok(Event::Key(key)) = read()
+                        && let Some(command) = Command::from_key(key)
+                        && sender.send(command).is_err()
+                    {
+                        return;
+                    }
+                }
+            });
+            Some(Self {
+                commands,
+                stop,
+                thread: Some(thread),
+            })
+        }
+        #[cfg(not(feature = "crossterm"))]
+        None
+    }
+
+    /// Get the commands received since the last call, without waiting
+    pub fn commands(&self) -> impl Iterator<Item = Command> + '_ {
+        self.commands.try_iter()
+    }
+}
+
+#[cfg(feature = "crossterm")]
+impl Drop for Controls {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
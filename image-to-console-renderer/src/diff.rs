@@ -0,0 +1,126 @@
+use image_to_console_core::converter::cell::{CellGrid, ColorDepth};
+
+/// The share of changed cells above which the whole frame is redrawn
+pub const DEFAULT_REDRAW_THRESHOLD: f32 = 0.5;
+
+/// Two changed runs closer than this are written as one run,
+/// rewriting a few unchanged cells is shorter than moving the cursor
+const MERGE_GAP: usize = 4;
+
+/// Renders the frames of an animation, only the cells changed since the last frame are written
+///
+/// Every frame starts at the top-left corner of the image (the cursor is moved there before),
+/// and the cursor is left after the last cell of the image, like a full frame.
+pub struct DiffRenderer {
+    /// The cells on the screen
+    previous: Option<CellGrid>,
+    /// The share of changed cells above which the whole frame is redrawn
+    threshold: f32,
+}
+
+impl Default for DiffRenderer {
+    fn default() -> Self {
+        Self::new(DEFAULT_REDRAW_THRESHOLD)
+    }
+}
+
+impl DiffRenderer {
+    /// Create a renderer with nothing on the screen
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - The share of changed cells (from 0 to 1) above which the whole frame is redrawn
+    ///
+    /// # Returns
+    ///
+    /// Returns a new renderer
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            previous: None,
+            threshold,
+        }
+    }
+
+    /// Forget the cells on the screen, the next frame is redrawn completely
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    /// Render a frame
+    ///
+    /// # Arguments
+    ///
+    /// * `grid` - The cells of the frame
+    ///
+    /// # Returns
+    ///
+    /// Returns the output for the terminal
+    pub fn render(&mut self, grid: CellGrid) -> String {
+        let output = match &self.previous {
+            Some(previous) if previous.same_layout(&grid) => {
+                let changed = grid
+                    .cells
+                    .iter()
+                    .zip(&previous.cells)
+                    .filter(|(cell, previous)| cell != previous)
+                    .count();
+                if changed as f32 > grid.cells.len() as f32 * self.threshold {
+                    full_redraw(&grid)
+                } else {
+                    diff_redraw(previous, &grid)
+                }
+            }
+            _ => full_redraw(&grid),
+        };
+        self.previous = Some(grid);
+        output
+    }
+}
+
+/// Write every row of the grid
+fn full_redraw(grid: &CellGrid) -> String {
+    let mut output = "\n".repeat(grid.top as usize);
+    output.push_str(&grid.to_ansi_lines(ColorDepth::TrueColor).join("\n"));
+    output
+}
+
+/// Write the changed runs of every row, with the cursor moved between them
+fn diff_redraw(previous: &CellGrid, grid: &CellGrid) -> String {
+    let mut output = String::new();
+    let mut cursor_row = 0;
+    let mut move_down = |output: &mut String, row: u32| {
+        // `CSI 0 B` moves one row, so nothing is written if the cursor is on the row
+        if row > cursor_row {
+            output.push_str(&format!("\x1b[{}B", row - cursor_row));
+            cursor_row = row;
+        }
+    };
+    for y in 0..grid.height {
+        let (old, new) = (previous.row(y), grid.row(y));
+        let mut x = 0;
+        while x < new.len() {
+            if old[x] == new[x] {
+                x += 1;
+                continue;
+            }
+            // Extend the run until a gap of unchanged cells
+            let start = x;
+            let mut end = x + 1;
+            while end < new.len() {
+                match (end..new.len().min(end + MERGE_GAP)).find(|&i| old[i] != new[i]) {
+                    Some(next) => end = next + 1,
+                    None => break,
+                }
+            }
+            move_down(&mut output, grid.top + y);
+            output.push_str(&format!("\x1b[{}G", grid.left as usize + start + 1));
+            CellGrid::write_run(&mut output, &new[start..end], ColorDepth::TrueColor);
+            x = end;
+        }
+    }
+    // Leave the cursor after the last cell of the last row, like a full frame, so erasing the
+    // screen below the frame does not erase its cells
+    move_down(&mut output, grid.top + grid.height.saturating_sub(1));
+    output.push_str(&format!("\x1b[{}G", (grid.left + grid.width) as usize + 1));
+    output
+}
@@ -0,0 +1,280 @@
+use crate::config::{LoopCount, OutputFormat};
+use image_to_console_core::converter::{cell::CellGrid, raster::RasterOptions};
+use std::{
+    fs::File,
+    io::{BufWriter, Error, Result, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// The name of the timing manifest in a directory of frames
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// The shortest delay of a GIF frame in hundredths of a second, the viewers show the frames with
+/// a shorter delay for 10 hundredths
+const MIN_DELAY: u128 = 2;
+
+/// Where the frames are written
+enum Target {
+    /// A directory of `.ans` files, and the manifest entries of the written frames
+    Frames {
+        directory: PathBuf,
+        entries: Vec<String>,
+    },
+    /// A GIF of the rasterized cells, the encoder is created with the first frame
+    Gif {
+        path: PathBuf,
+        options: RasterOptions,
+        encoder: Option<gif::Encoder<BufWriter<File>>>,
+        /// The size of the frames, set with the encoder
+        size: (u16, u16),
+        /// The last frame and its time in hundredths of a second, written once its delay is known
+        pending: Option<(gif::Frame<'static>, u128)>,
+    },
+}
+
+/// Exports the frames of an animation instead of playing them
+///
+/// The frames are written either as a directory of ANSI files (the output of each frame as it is
+/// printed to the terminal) with a JSON timing manifest, or as a GIF of the cells rasterized
+/// with the bundled font (only for the character-cell modes).
+///
+/// The manifest is `{"version": 1, "loop_count": N, "frames": [{"file": "frame_000000.ans",
+/// "time": 0.0, "duration": 0.1}, ...]}`, the times are in seconds and a `loop_count` of 0 loops
+/// forever.
+pub struct AnimationExporter {
+    target: Target,
+    loop_count: LoopCount,
+    /// The time of the next frame
+    time: Duration,
+    /// The number of frames added
+    frames: usize,
+}
+
+impl AnimationExporter {
+    /// Create an exporter, the directory of the frames is created
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the directory or the GIF file
+    /// * `format` - [`OutputFormat::Frames`] or [`OutputFormat::Gif`]
+    /// * `loop_count` - How many times the animation is played, written to the manifest or the GIF
+    ///
+    /// # Returns
+    ///
+    /// Returns a new exporter, or an error if the format is not an animation format or the
+    /// directory cannot be created
+    pub fn create(
+        path: impl AsRef<Path>,
+        format: OutputFormat,
+        loop_count: LoopCount,
+    ) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let target = match format {
+            OutputFormat::Frames => {
+                std::fs::create_dir_all(&path)?;
+                Target::Frames {
+                    directory: path,
+                    entries: Vec::new(),
+                }
+            }
+            OutputFormat::Gif => Target::Gif {
+                path,
+                options: RasterOptions::default(),
+                encoder: None,
+                size: (0, 0),
+                pending: None,
+            },
+            format => {
+                return Err(Error::other(format!(
+                    "The {} output is not an animation, use a .gif file or a directory of frames",
+                    format.extension()
+                )));
+            }
+        };
+        Ok(Self {
+            target,
+            loop_count,
+            time: Duration::ZERO,
+            frames: 0,
+        })
+    }
+
+    /// Sets the options of rasterizing the cells of a GIF
+    ///
+    /// # Arguments
+    ///
+    /// * `raster_options` - The cell size, font and default colors
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn raster_options(&mut self, raster_options: RasterOptions) -> &mut Self {
+        if let Target::Gif { options, .. } = &mut self.target {
+            *options = raster_options;
+        }
+        self
+    }
+
+    /// Get the number of frames added
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Write a frame
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output of the frame as it is printed
+    /// * `cells` - The cells of the frame, needed for a GIF
+    /// * `duration` - How long the frame is shown
+    pub fn add_frame(
+        &mut self,
+        output: &str,
+        cells: Option<&CellGrid>,
+        duration: Duration,
+    ) -> Result<()> {
+        let (time, end) = (self.time, self.time + duration);
+        match &mut self.target {
+            Target::Frames { directory, entries } => {
+                let name = format!("frame_{:06}.ans", self.frames);
+                let mut file = BufWriter::new(File::create(directory.join(&name))?);
+                writeln!(file, "{output}")?;
+                file.flush()?;
+                entries.push(format!(
+                    "{{\"file\": \"{name}\", \"time\": {:.6}, \"duration\": {:.6}}}",
+                    time.as_secs_f64(),
+                    duration.as_secs_f64()
+                ));
+            }
+            Target::Gif {
+                path,
+                options,
+                encoder,
+                size,
+                pending,
+            } => {
+                let cells = cells.ok_or_else(|| {
+                    Error::other(
+                        "The gif output only supports the half-color, full-color, no-color and ascii modes",
+                    )
+                })?;
+                let mut image = cells.to_image(options);
+                let (Ok(width), Ok(height)) =
+                    (u16::try_from(image.width()), u16::try_from(image.height()))
+                else {
+                    return Err(Error::other("The frames are too large for the gif output"));
+                };
+                let encoder = match encoder {
+                    Some(encoder) => encoder,
+                    None => {
+                        *size = (width, height);
+                        encoder.insert(create_gif(path, width, height, self.loop_count)?)
+                    }
+                };
+                if (width, height) != *size {
+                    return Err(Error::other(
+                        "The size of the frames changed, the gif output needs the same size",
+                    ));
+                }
+                let frame = gif::Frame::from_rgba_speed(width, height, &mut image, 10);
+                // The delays are rounded from the times, so the rounding errors do not add up. A
+                // frame shown for less than the shortest delay is dropped, the next one is shown
+                // from its time instead
+                let start = match pending.take() {
+                    Some((_, start)) if centiseconds(time) - start < MIN_DELAY => start,
+                    Some((last, start)) => {
+                        write_gif_frame(encoder, last, centiseconds(time) - start)?;
+                        centiseconds(time)
+                    }
+                    None => centiseconds(time),
+                };
+                *pending = Some((frame, start));
+            }
+        }
+        self.time = end;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Write the manifest or the end of the GIF
+    pub fn finish(self) -> Result<()> {
+        match self.target {
+            Target::Frames { directory, entries } => {
+                let loop_count = match self.loop_count {
+                    LoopCount::Times(times) => times,
+                    LoopCount::Forever => 0,
+                };
+                let mut file = BufWriter::new(File::create(directory.join(MANIFEST_NAME))?);
+                writeln!(
+                    file,
+                    "{{\"version\": 1, \"loop_count\": {loop_count}, \"frames\": [\n  {}\n]}}",
+                    entries.join(",\n  ")
+                )?;
+                file.flush()
+            }
+            Target::Gif {
+                encoder, pending, ..
+            } => match (encoder, pending) {
+                (Some(mut encoder), Some((last, start))) => {
+                    let delay = centiseconds(self.time).saturating_sub(start).max(MIN_DELAY);
+                    write_gif_frame(&mut encoder, last, delay)?;
+                    encoder.into_inner().map_err(Error::other)?.flush()
+                }
+                _ => Err(Error::other("No frame was exported")),
+            },
+        }
+    }
+}
+
+/// Create the encoder of a GIF file
+///
+/// # Arguments
+///
+/// * `path` - The path of the GIF file
+/// * `width` - Width of the GIF in pixels
+/// * `height` - Height of the GIF in pixels
+/// * `loop_count` - How many times the GIF is played
+fn create_gif(
+    path: &Path,
+    width: u16,
+    height: u16,
+    loop_count: LoopCount,
+) -> Result<gif::Encoder<BufWriter<File>>> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = gif::Encoder::new(file, width, height, &[]).map_err(Error::other)?;
+    // The NETSCAPE loop count is the number of repeats after the first time, a GIF without it
+    // is played once
+    let repeat = match loop_count {
+        LoopCount::Times(1) => None,
+        LoopCount::Times(times) => Some(gif::Repeat::Finite(
+            times.saturating_sub(1).min(u16::MAX as u32) as u16,
+        )),
+        LoopCount::Forever => Some(gif::Repeat::Infinite),
+    };
+    if let Some(repeat) = repeat {
+        encoder.set_repeat(repeat).map_err(Error::other)?;
+    }
+    Ok(encoder)
+}
+
+/// Write a frame of a GIF
+///
+/// # Arguments
+///
+/// * `encoder` - The encoder of the GIF
+/// * `frame` - The frame
+/// * `delay` - How long the frame is shown in hundredths of a second
+fn write_gif_frame(
+    encoder: &mut gif::Encoder<BufWriter<File>>,
+    mut frame: gif::Frame<'static>,
+    delay: u128,
+) -> Result<()> {
+    frame.delay = delay.min(u16::MAX as u128) as u16;
+    encoder.write_frame(&frame).map_err(Error::other)
+}
+
+/// Get a time in hundredths of a second, rounded to the nearest
+fn centiseconds(time: Duration) -> u128 {
+    (time.as_millis() + 5) / 10
+}
@@ -1,13 +1,41 @@
+#[cfg(feature = "gif_player")]
+use image_to_console_core::converter::cell::CellGrid;
+#[cfg(feature = "gif_player")]
+use std::time::Duration;
+
+/// How long a frame of an animation is shown, in the unit of its file
+#[cfg(feature = "gif_player")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameDelay {
+    /// The delay of a GIF frame, in hundredths of a second
+    Hundredths(u16),
+    /// The delay of the frames of the other formats, e.g. APNG and animated WebP
+    Exact(Duration),
+}
+
+#[cfg(feature = "gif_player")]
+impl FrameDelay {
+    /// Get the delay as a duration
+    pub fn duration(self) -> Duration {
+        match self {
+            Self::Hundredths(delay) => Duration::from_millis(delay as u64 * 10),
+            Self::Exact(delay) => delay,
+        }
+    }
+}
+
 #[cfg(feature = "gif_player")]
 #[derive(Clone)]
 pub struct Frame {
     pub index: usize,
     pub frame: String,
-    pub delay: u64,
+    pub delay: FrameDelay,
+    /// The cells of the frame, only the changed cells are redrawn if it is set
+    pub cells: Option<CellGrid>,
 }
 #[cfg(feature = "gif_player")]
 impl Frame {
-    pub fn unpacking(&self) -> (&str, usize, u64) {
+    pub fn unpacking(&self) -> (&str, usize, FrameDelay) {
         (&self.frame, self.index, self.delay)
     }
 }
\ No newline at end of file
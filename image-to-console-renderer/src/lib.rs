@@ -1,4 +1,16 @@
 pub mod renderer;
 pub mod config;
 pub mod audio_path;
-pub mod frame;
\ No newline at end of file
+#[cfg(feature = "rodio")]
+pub mod audio;
+pub mod diff;
+pub mod frame;
+pub mod asciicast;
+pub mod controls;
+pub mod player;
+#[cfg(any(feature = "gif_player", feature = "video_player"))]
+pub mod export;
+#[cfg(any(feature = "gif_player", feature = "video_player"))]
+pub mod scheduler;
+#[cfg(feature = "video_player")]
+pub mod subtitle;
\ No newline at end of file
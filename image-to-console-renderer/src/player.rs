@@ -0,0 +1,307 @@
+use image_to_console_core::converter::cell::{Cell, CellGrid};
+use std::{collections::VecDeque, time::Duration};
+
+/// The memory the played frames may use by default, in bytes
+pub const DEFAULT_CACHE_SIZE: usize = 256 * 1024 * 1024;
+
+/// A converted frame of an animation
+#[derive(Clone)]
+pub struct TimedFrame {
+    /// The output of the frame
+    pub frame: String,
+    /// The cells of the frame, only the changed cells are redrawn if it is set
+    pub cells: Option<CellGrid>,
+    /// The index of the frame
+    pub index: usize,
+    /// The time of the frame since the start of the playback
+    pub time: Duration,
+    /// How long the frame is shown at the normal speed
+    pub duration: Duration,
+    /// The presentation time of a video frame
+    pub pts: Option<Duration>,
+}
+
+impl TimedFrame {
+    /// Get the memory used by the frame, in bytes
+    pub fn size(&self) -> usize {
+        self.frame.len()
+            + self
+                .cells
+                .as_ref()
+                .map_or(0, |cells| cells.cells.len() * size_of::<Cell>())
+    }
+}
+
+/// The frames of an animation which were played, so the player can seek back and loop
+///
+/// The oldest frames are dropped when the frames use more memory than the limit,
+/// the last frame is always kept.
+pub struct FrameCache {
+    frames: VecDeque<TimedFrame>,
+    /// The position of the first frame in the playback
+    first: usize,
+    /// The memory used by the frames, in bytes
+    size: usize,
+    /// The memory the frames may use, in bytes
+    max_size: usize,
+}
+
+impl FrameCache {
+    /// Create an empty cache
+    ///
+    /// # Arguments
+    ///
+    /// * `max_size` - The memory the frames may use, in bytes
+    ///
+    /// # Returns
+    ///
+    /// Returns a new cache
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            frames: VecDeque::new(),
+            first: 0,
+            size: 0,
+            max_size,
+        }
+    }
+
+    /// Add the next frame, the oldest frames are dropped if the memory limit is reached
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The frame following the last one
+    pub fn push(&mut self, frame: TimedFrame) {
+        self.size += frame.size();
+        self.frames.push_back(frame);
+        while self.size > self.max_size && self.frames.len() > 1 {
+            let frame = self.frames.pop_front().unwrap();
+            self.size -= frame.size();
+            self.first += 1;
+        }
+    }
+
+    /// Get a frame
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position of the frame in the playback
+    ///
+    /// # Returns
+    ///
+    /// Returns the frame, or `None` if it was dropped or is not played yet
+    pub fn get(&self, position: usize) -> Option<&TimedFrame> {
+        self.frames.get(position.checked_sub(self.first)?)
+    }
+
+    /// Get the position of the first frame kept in the playback
+    pub fn first(&self) -> usize {
+        self.first
+    }
+
+    /// Get the position after the last frame
+    pub fn end(&self) -> usize {
+        self.first + self.frames.len()
+    }
+
+    /// Get the memory used by the frames, in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Plays the frames of an animation, the played frames are kept in a [`FrameCache`]
+pub struct Player<I: Iterator<Item = TimedFrame>> {
+    /// The frames which are not played yet
+    frames: I,
+    cache: FrameCache,
+    /// The position of the current frame
+    position: Option<usize>,
+    /// Whether the frames of the current pass ended, the next pass is only read by `restart`
+    ended: bool,
+}
+
+impl<I: Iterator<Item = TimedFrame>> Player<I> {
+    /// Create a player before the first frame
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - The frames of the animation
+    /// * `cache_size` - The memory the played frames may use, in bytes
+    ///
+    /// # Returns
+    ///
+    /// Returns a new player
+    pub fn new(frames: I, cache_size: usize) -> Self {
+        Self {
+            frames,
+            cache: FrameCache::new(cache_size),
+            position: None,
+            ended: false,
+        }
+    }
+
+    /// Get the current frame
+    pub fn current(&self) -> Option<&TimedFrame> {
+        self.cache.get(self.position?)
+    }
+
+    /// Move to the next frame
+    ///
+    /// # Returns
+    ///
+    /// Returns `false` at the end of the animation
+    pub fn advance(&mut self) -> bool {
+        let next = self.position.map_or(0, |position| position + 1);
+        if next == self.cache.end() {
+            if self.ended {
+                return false;
+            }
+            match self.frames.next() {
+                Some(frame) => self.cache.push(frame),
+                None => {
+                    self.ended = true;
+                    return false;
+                }
+            }
+        }
+        self.position = Some(next);
+        true
+    }
+
+    /// Move to the previous frame
+    ///
+    /// # Returns
+    ///
+    /// Returns `false` if the previous frame is not kept
+    pub fn back(&mut self) -> bool {
+        match self.position {
+            Some(position) if position > self.cache.first() => {
+                self.position = Some(position - 1);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Move to the frame shown at a time, as close as possible if the frame is not kept
+    /// or the animation ends before
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The time since the start of the playback
+    pub fn seek(&mut self, time: Duration) {
+        while let Some(frame) = self.current() {
+            if frame.time <= time {
+                break;
+            }
+            if !self.back() {
+                return;
+            }
+        }
+        while let Some(frame) = self.current() {
+            if frame.time + frame.duration > time || !self.advance() {
+                break;
+            }
+        }
+    }
+
+    /// Move back to the first frame if it is kept, otherwise move to the first frame of the
+    /// next pass of the frames (see [`Passes`])
+    ///
+    /// # Returns
+    ///
+    /// Returns `false` if the first frame was dropped from the cache and there is no next pass
+    pub fn restart(&mut self) -> bool {
+        if self.cache.first() == 0 && self.cache.end() > 0 {
+            self.position = Some(0);
+            return true;
+        }
+        let Some(frame) = self.frames.next() else {
+            return false;
+        };
+        self.ended = false;
+        self.position = Some(self.cache.end());
+        self.cache.push(frame);
+        true
+    }
+
+    /// Get the played frames
+    pub fn cache(&self) -> &FrameCache {
+        &self.cache
+    }
+}
+
+/// Splits the frames of an animation into passes, a pass ends before the next frame with the index 0
+///
+/// The iterator returns `None` at the end of each pass, and the frames of the next pass after it,
+/// so a [`Player`] plays one pass and reads the next one only if it cannot restart from its cache.
+pub struct Passes<I: Iterator<Item = TimedFrame>> {
+    frames: I,
+    /// The first frame of the next pass
+    next: Option<TimedFrame>,
+    /// Whether a frame of the current pass was returned
+    started: bool,
+    /// Called when the next pass is read
+    on_next_pass: Option<Box<dyn FnMut() + Send>>,
+}
+
+impl<I: Iterator<Item = TimedFrame>> Passes<I> {
+    /// Split the frames into passes
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - The frames of the passes, the index of the frames starts at 0 in each pass
+    ///
+    /// # Returns
+    ///
+    /// Returns the frames of the first pass
+    pub fn new(frames: I) -> Self {
+        Self {
+            frames,
+            next: None,
+            started: false,
+            on_next_pass: None,
+        }
+    }
+
+    /// Sets a function called when the frames after the first frame of a next pass are read
+    ///
+    /// The source of the frames only has to send the first frame of the next pass, which ends
+    /// the current pass, and can wait for this call to decode the other frames, so the passes
+    /// played from the cache of a [`Player`] are not decoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_next_pass` - The function
+    ///
+    /// # Returns
+    ///
+    /// Returns the passes
+    pub fn on_next_pass(mut self, on_next_pass: impl FnMut() + Send + 'static) -> Self {
+        self.on_next_pass = Some(Box::new(on_next_pass));
+        self
+    }
+}
+
+impl<I: Iterator<Item = TimedFrame>> Iterator for Passes<I> {
+    type Item = TimedFrame;
+
+    fn next(&mut self) -> Option<TimedFrame> {
+        let frame = match self.next.take() {
+            Some(frame) => {
+                if let Some(on_next_pass) = &mut self.on_next_pass {
+                    on_next_pass();
+                }
+                frame
+            }
+            None => self.frames.next()?,
+        };
+        if frame.index == 0 && self.started {
+            self.next = Some(frame);
+            self.started = false;
+            return None;
+        }
+        self.started = true;
+        Some(frame)
+    }
+}
@@ -1,20 +1,161 @@
-#[cfg(all(
-    feature = "rodio",
-    any(feature = "video_player", feature = "gif_player")
-))]
-use crate::audio_path::AudioPath;
-use crate::config::Config;
+#[cfg(all(feature = "rodio", feature = "video_player"))]
+use crate::audio::VideoAudio;
+use crate::config::{Config, OutputFormat};
+#[cfg(any(feature = "video_player", feature = "gif_player"))]
+use crate::diff::DiffRenderer;
+#[cfg(any(feature = "video_player", feature = "gif_player"))]
+use crate::{
+    asciicast::AsciicastRecorder,
+    config::LoopCount,
+    controls::{Command, Controls, PlaybackState, SEEK_STEP},
+    player::{DEFAULT_CACHE_SIZE, Player, TimedFrame},
+    scheduler::FrameScheduler,
+};
+#[cfg(any(feature = "video_player", feature = "gif_player"))]
+use crate::export::AnimationExporter;
 #[cfg(feature = "gif_player")]
-use crate::frame::Frame;
+use crate::{frame::Frame, player::Passes, scheduler::gif_frame_duration};
+#[cfg(feature = "video_player")]
+use crate::subtitle::SubtitleOverlay;
+#[cfg(feature = "video_player")]
+use image_to_console_core::converter::cell::CellGrid;
 use image_to_console_colored::{colors::TerminalColor, prelude::ToColoredText};
-use image_to_console_core::processor::ImageProcessorResult;
-#[cfg(any(feature = "video_player", feature = "gif_player"))]
-use std::thread::JoinHandle;
+use image_to_console_core::{
+    capabilities::probed_capabilities, processor::ImageProcessorResult,
+};
 use std::{
     fs::File,
     io::{Read, Result, Write},
+    sync::{
+        Mutex, Once, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
 };
 
+/// Begin a synchronized update (DEC mode 2026), the terminal keeps showing the old frame until it ends
+pub const BEGIN_SYNCHRONIZED_UPDATE: &str = "\x1b[?2026h";
+/// End a synchronized update (DEC mode 2026)
+pub const END_SYNCHRONIZED_UPDATE: &str = "\x1b[?2026l";
+
+const ENTER_ALTERNATE_SCREEN: &str = "\x1b[?1049h\x1b[2J\x1b[1;1H";
+const LEAVE_ALTERNATE_SCREEN: &str = "\x1b[?1049l";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+/// The sequence restoring the terminal, it is set while a [`TerminalGuard`] is alive
+static RESTORE_SEQUENCE: Mutex<Option<String>> = Mutex::new(None);
+/// Whether the alternate screen is active, read by the signal handler which cannot lock
+static IN_ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+
+/// A guard hiding the cursor and optionally switching to the alternate screen while the frames are played
+///
+/// The terminal is restored when the guard is dropped, when the thread panics,
+/// or when [`restore_terminal`] is called before the process exits.
+pub struct TerminalGuard {
+    _private: (),
+}
+
+impl TerminalGuard {
+    /// Hide the cursor and optionally enter the alternate screen
+    ///
+    /// # Arguments
+    ///
+    /// * `alternate_screen` - Whether to play in the alternate screen, the main screen is kept untouched
+    ///
+    /// # Returns
+    ///
+    /// Returns the guard restoring the terminal on drop
+    pub fn new(alternate_screen: bool) -> Self {
+        static HOOKS: Once = Once::new();
+        HOOKS.call_once(|| {
+            let previous = std::panic::take_hook();
+            // Restore the terminal first, so the panic message is printed on the main screen
+            std::panic::set_hook(Box::new(move |info| {
+                restore_terminal();
+                previous(info);
+            }));
+            #[cfg(unix)]
+            unsafe {
+                libc::signal(
+                    libc::SIGINT,
+                    on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+                );
+            }
+        });
+
+        let mut setup = String::new();
+        let mut restore = String::from(SHOW_CURSOR);
+        if alternate_screen {
+            setup.push_str(ENTER_ALTERNATE_SCREEN);
+            restore.push_str(LEAVE_ALTERNATE_SCREEN);
+        }
+        setup.push_str(HIDE_CURSOR);
+        IN_ALTERNATE_SCREEN.store(alternate_screen, Ordering::SeqCst);
+        *RESTORE_SEQUENCE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(restore);
+
+        let mut stdout = std::io::stdout().lock();
+        let _ = stdout.write_all(setup.as_bytes());
+        let _ = stdout.flush();
+        Self { _private: () }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Show the cursor, leave the alternate screen and the raw mode if a [`TerminalGuard`] is alive
+///
+/// Call it before `std::process::exit`, which does not drop the guard.
+pub fn restore_terminal() {
+    let restore = RESTORE_SEQUENCE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    if let Some(restore) = restore {
+        // The keyboard controls may have entered the raw mode
+        #[cfg(feature = "crossterm")]
+        let _ = crossterm::terminal::disable_raw_mode();
+        IN_ALTERNATE_SCREEN.store(false, Ordering::SeqCst);
+        let mut stdout = std::io::stdout().lock();
+        let _ = stdout.write_all(restore.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+/// Restore the terminal on `Ctrl-C`, then terminate with the default handler
+///
+/// Only `write` and `raise` are called here, they are async-signal-safe.
+#[cfg(unix)]
+extern "C" fn on_interrupt(signal: libc::c_int) {
+    let restore = if IN_ALTERNATE_SCREEN.load(Ordering::SeqCst) {
+        "\x1b[?25h\x1b[?1049l"
+    } else {
+        SHOW_CURSOR
+    };
+    unsafe {
+        libc::write(libc::STDOUT_FILENO, restore.as_ptr().cast(), restore.len());
+        libc::signal(signal, libc::SIG_DFL);
+        libc::raise(signal);
+    }
+}
+
+/// Whether the frames are wrapped in synchronized updates
+///
+/// The terminal is only probed to detect the protocol, so the frames are not wrapped when
+/// the protocol is set by a flag.
+///
+/// # Returns
+///
+/// Returns `true` if the terminal reports DEC mode 2026 as supported
+pub fn use_synchronized_update() -> bool {
+    probed_capabilities().is_some_and(|capabilities| capabilities.sync_output)
+}
+
 pub fn get_char() -> char {
     let mut buf = vec![0; 1];
     std::io::stdin().lock().read_exact(&mut buf).unwrap();
@@ -41,18 +182,18 @@ pub fn render(result: ImageProcessorResult, config: Config) -> Result<()> {
             result.width,
             result.height
         );
-        if config.show_file_name {
-            if let Some(file_name) = config.file_name {
-                println!(
-                    "{}: {}",
-                    "File Name"
-                        .to_colored_text()
-                        .set_foreground_color(TerminalColor::Green),
-                    file_name
-                        .to_colored_text()
-                        .set_foreground_color(TerminalColor::LightBlue)
-                );
-            }
+        if config.show_file_name
+            && let Some(file_name) = config.file_name
+        {
+            println!(
+                "{}: {}",
+                "File Name"
+                    .to_colored_text()
+                    .set_foreground_color(TerminalColor::Green),
+                file_name
+                    .to_colored_text()
+                    .set_foreground_color(TerminalColor::LightBlue)
+            );
         }
 
         println!(
@@ -97,8 +238,28 @@ pub fn render(result: ImageProcessorResult, config: Config) -> Result<()> {
         }
     }
     if let Some(filename) = config.output {
+        let content = match (config.output_format, &result.cells) {
+            (OutputFormat::Text, _) => output.into_bytes(),
+            (OutputFormat::Html, Some(cells)) => cells.to_html_page().into_bytes(),
+            (OutputFormat::Svg, Some(cells)) => cells.to_svg().into_bytes(),
+            (OutputFormat::Png, Some(cells)) => cells
+                .to_png(&config.raster_options)
+                .map_err(|e| std::io::Error::other(e.to_string()))?,
+            (format @ (OutputFormat::Gif | OutputFormat::Frames), _) => {
+                return Err(std::io::Error::other(format!(
+                    "The {} output is only for the gif and video subcommands",
+                    format.extension()
+                )));
+            }
+            (format, None) => {
+                return Err(std::io::Error::other(format!(
+                    "The {} output only supports the half-color, full-color, no-color and ascii modes",
+                    format.extension()
+                )));
+            }
+        };
         let mut file = File::create(filename)?;
-        file.write_all(output.as_ref())?;
+        file.write_all(&content)?;
     }
     if config.pause {
         print!("Press the 'enter' to continue...");
@@ -108,8 +269,396 @@ pub fn render(result: ImageProcessorResult, config: Config) -> Result<()> {
     Ok(())
 }
 
+/// The recording of a playback, shared by the threads of the frames
+#[cfg(any(feature = "video_player", feature = "gif_player"))]
+struct Recording {
+    recorder: Option<AsciicastRecorder>,
+    /// The time of the next frame since the start of the playback
+    clock: std::time::Duration,
+    /// The presentation time of the first frame, the recording starts at it, e.g. a video played
+    /// from `--start`
+    origin: Option<std::time::Duration>,
+    /// The first error of writing the recording, nothing is written after it
+    result: Result<()>,
+}
+
+#[cfg(any(feature = "video_player", feature = "gif_player"))]
+impl Recording {
+    /// Start a recording, the recording is cleared first and the cursor is hidden like the playback
+    fn new(recorder: Option<AsciicastRecorder>, back_top: bool) -> Self {
+        let recorder = recorder.map(|mut recorder| {
+            recorder.preamble(HIDE_CURSOR).preamble("\x1b[2J\x1b[1;1H");
+            if !back_top {
+                recorder.preamble("\x1b[s");
+            }
+            recorder
+        });
+        Self {
+            recorder,
+            clock: std::time::Duration::ZERO,
+            origin: None,
+            result: Ok(()),
+        }
+    }
+
+    /// Record the output of a frame
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The presentation time of the frame, counted from the presentation time of the
+    ///   first frame, the frame follows the last one if it is `None`
+    /// * `output` - The output of the frame
+    /// * `duration` - How long the frame is shown
+    fn record(
+        &mut self,
+        time: Option<std::time::Duration>,
+        output: &str,
+        duration: std::time::Duration,
+    ) {
+        if let Some(time) = time {
+            let origin = *self.origin.get_or_insert(time);
+            self.clock = self.clock.max(time.saturating_sub(origin));
+        }
+        if let (Some(recorder), Ok(())) = (&mut self.recorder, &self.result) {
+            self.result = recorder.record(self.clock, output);
+        }
+        self.clock += duration;
+    }
+
+    /// Move the end of the recording, the last frame is shown longer
+    fn wait(&mut self, duration: std::time::Duration) {
+        self.clock += duration;
+    }
+
+    /// Finish the recording after the last frame is shown
+    ///
+    /// # Returns
+    ///
+    /// Returns the first error of writing the recording
+    fn finish(self) -> Result<()> {
+        match self.recorder {
+            Some(recorder) => self
+                .result
+                .and_then(|_| recorder.finish(self.clock, SHOW_CURSOR)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Play the frames with the keyboard controls
+///
+/// The frames are the clock of the playback: the audio is paused, resumed, sped up and moved
+/// with them, and it is moved to the current frame again when the playback resumes.
+///
+/// # Arguments
+///
+/// * `frames` - The frames of the animation
+/// * `controls` - The keyboard controls, the raw mode is left when they are dropped
+/// * `state` - The state at the start of the playback
+/// * `loop_count` - How many times the frames are played while looping, turning looping on with
+///   the keys loops forever
+/// * `recording` - The recording of the playback, the pauses are not recorded
+/// * `compose` - Compose the output of a frame, with the info after the index of the frame
+/// * `sink` - The audio of the playback
+#[cfg(any(feature = "video_player", feature = "gif_player"))]
+fn play_interactive(
+    frames: impl Iterator<Item = TimedFrame>,
+    controls: Controls,
+    mut state: PlaybackState,
+    mut loop_count: LoopCount,
+    recording: &mut Recording,
+    compose: impl Fn(&str, &TimedFrame, Option<&str>) -> String,
+    #[cfg(feature = "rodio")] sink: Option<&rodio::Sink>,
+) {
+    use std::time::{Duration, Instant};
+    /// How long the player waits for a key while nothing is due
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    // The speed of the audio at the normal speed of the playback, the keys change it relatively
+    #[cfg(feature = "rodio")]
+    let base_speed = sink.map_or(1.0, |sink| sink.speed());
+    let mut player = Player::new(frames, DEFAULT_CACHE_SIZE);
+    let mut passes = 1;
+    let mut diff = DiffRenderer::default();
+    // When the next frame is due
+    let mut deadline = Instant::now();
+    // The time played before the last pause, and when the playback resumed
+    let (mut played, mut resumed) = (Duration::ZERO, Instant::now());
+    loop {
+        let mut redraw = false;
+        let mut moved = false;
+        for command in controls.commands() {
+            let current = player.current().map(|frame| frame.time);
+            match command {
+                Command::Quit => return,
+                Command::Backward if state.paused => moved |= player.back(),
+                Command::Forward if state.paused => moved |= player.advance(),
+                Command::Backward => {
+                    player.seek(current.unwrap_or_default().saturating_sub(SEEK_STEP));
+                    moved = true;
+                }
+                Command::Forward => {
+                    player.seek(current.unwrap_or_default() + SEEK_STEP);
+                    moved = true;
+                }
+                command => {
+                    if !state.apply(command) {
+                        continue;
+                    }
+                    redraw = true;
+                    match command {
+                        Command::TogglePause if state.paused => {
+                            played += resumed.elapsed();
+                            #[cfg(feature = "rodio")]
+                            if let Some(sink) = sink {
+                                sink.pause();
+                            }
+                        }
+                        Command::TogglePause => {
+                            resumed = Instant::now();
+                            moved = true;
+                            #[cfg(feature = "rodio")]
+                            if let Some(sink) = sink {
+                                sink.play();
+                            }
+                        }
+                        Command::ToggleLoop => loop_count = LoopCount::Forever,
+                        #[cfg(feature = "rodio")]
+                        Command::SpeedUp | Command::SpeedDown => {
+                            if let Some(sink) = sink {
+                                sink.set_speed(base_speed * state.speed());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        let now = Instant::now();
+        if moved {
+            // The frame is shown for its whole duration after a seek
+            redraw = true;
+            if let Some(frame) = player.current() {
+                deadline = now + frame.duration.div_f32(state.speed());
+                // The seek is in the playback time of the sink, which is sped up by the keys
+                #[cfg(feature = "rodio")]
+                if let Some(sink) = sink {
+                    let _ = sink.try_seek(frame.time.div_f32(state.speed()));
+                }
+            }
+        } else if !state.paused && now >= deadline {
+            if !player.advance() {
+                if !(state.looping && loop_count.has_next(passes) && player.restart()) {
+                    break;
+                }
+                passes += 1;
+                #[cfg(feature = "rodio")]
+                if let Some((sink, frame)) = sink.zip(player.current()) {
+                    let _ = sink.try_seek(frame.time.div_f32(state.speed()));
+                }
+            }
+            redraw = true;
+            let duration = player
+                .current()
+                .map_or(Duration::ZERO, |frame| frame.duration);
+            // Catch up if the frame is late, but do not skip the frame
+            deadline = (deadline + duration.div_f32(state.speed())).max(now);
+        }
+
+        if redraw && let Some(frame) = player.current() {
+            let output = match &frame.cells {
+                Some(cells) => diff.render(cells.clone()),
+                None => frame.frame.clone(),
+            };
+            let status = state.show_info.then(|| format!("{}\x1b[K", state.status()));
+            let output = compose(&output, frame, status.as_deref());
+            let time = played
+                + if state.paused {
+                    Duration::ZERO
+                } else {
+                    resumed.elapsed()
+                };
+            recording.record(Some(time), &output, Duration::ZERO);
+            // The line feeds do not return the cursor in raw mode
+            let mut stdout = std::io::stdout().lock();
+            let _ = stdout.write_all(output.replace('\n', "\r\n").as_bytes());
+            let _ = stdout.flush();
+        }
+
+        let wait = if state.paused {
+            POLL_INTERVAL
+        } else {
+            deadline
+                .saturating_duration_since(Instant::now())
+                .min(POLL_INTERVAL)
+        };
+        std::thread::sleep(wait);
+    }
+    // The last frame is shown until the end of the recording
+    let last = player
+        .current()
+        .map_or(Duration::ZERO, |frame| frame.duration);
+    recording.wait(last.div_f32(state.speed()));
+}
+
+/// Play the passes of the frames one after another
+///
+/// The later passes are played from the cache of the player if the first frame is still kept,
+/// otherwise the frames of the next pass are read.
+///
+/// # Arguments
+///
+/// * `player` - The player of the frames, before the first frame
+/// * `loop_count` - How many times the frames are played
+///
+/// # Returns
+///
+/// Returns the frames of all the passes
+#[cfg(feature = "gif_player")]
+fn play_passes(
+    mut player: Player<impl Iterator<Item = TimedFrame>>,
+    loop_count: LoopCount,
+) -> impl Iterator<Item = TimedFrame> {
+    let mut passes = 1;
+    std::iter::from_fn(move || {
+        if !player.advance() {
+            if !(loop_count.has_next(passes) && player.restart()) {
+                return None;
+            }
+            passes += 1;
+        }
+        player.current().cloned()
+    })
+}
+
+/// Compose the output of a GIF frame, it is written at once so the synchronized update is not split
+///
+/// # Arguments
+///
+/// * `frame` - The frame
+/// * `index` - The index of the frame
+/// * `info` - The text after the index of the frame, the info is hidden if it is `None`
+/// * `back_top` - Whether the frame is drawn at the top-left corner, otherwise at the saved position
+/// * `sync` - Whether the frame is wrapped in a synchronized update
+#[cfg(feature = "gif_player")]
+fn gif_frame_output(
+    frame: &str,
+    index: usize,
+    info: Option<&str>,
+    back_top: bool,
+    sync: bool,
+) -> String {
+    let mut output = String::with_capacity(frame.len() + 64);
+    if sync {
+        output.push_str(BEGIN_SYNCHRONIZED_UPDATE);
+    }
+    if back_top {
+        output.push_str("\x1b[1;1H");
+    }
+    match info {
+        Some(info) => output.push_str(&format!("{frame}\nCurrent frame: {index}{info}\n")),
+        // Clear the info of the last frame
+        None => output.push_str(&format!("{frame}\x1b[J")),
+    }
+    if !back_top {
+        // Back to the saved position
+        output.push_str("\x1b[u");
+    }
+    if sync {
+        output.push_str(END_SYNCHRONIZED_UPDATE);
+    }
+    output
+}
+
+/// Play the frames of a GIF
+///
+/// # Arguments
+///
+/// * `results` - The frames
+/// * `config` - The config of the playback
+/// * `recorder` - Record the playback to an asciicast file, only the recording is made if
+///   `config.record_only` is set, without a terminal and without waiting between the frames
+/// * `exporter` - Export the first pass of the frames instead of playing or recording them
+/// * `next_pass` - Request the frames of the next pass once its first frame was read, `None` if
+///   `results` sends every pass without a request
+///
+/// The frames are played `config.loop_count` times, the index of the frames starts at 0 again
+/// in each pass. The frames of the first pass are kept (up to [`DEFAULT_CACHE_SIZE`]), so the
+/// next passes are only read from `results` if they do not fit.
+///
+/// # Returns
+///
+/// Returns the error of writing the recording or the export
 #[cfg(feature = "gif_player")]
-pub fn render_gif(results: crossbeam_channel::Receiver<Frame>, config: Config) {
+pub fn render_gif(
+    results: crossbeam_channel::Receiver<Frame>,
+    config: Config,
+    recorder: Option<AsciicastRecorder>,
+    exporter: Option<AnimationExporter>,
+    next_pass: Option<crossbeam_channel::Sender<()>>,
+) -> Result<()> {
+    // The frame rate replaces the delays of the frames
+    let frame_duration = config
+        .fps
+        .filter(|fps| *fps > 0)
+        .map(|fps| std::time::Duration::from_secs_f64(1.0 / fps as f64));
+    let start_time = std::time::Instant::now();
+    let mut time = std::time::Duration::ZERO;
+    let mut frames = Passes::new(results.into_iter().map(move |mut frame| {
+        let duration = frame_duration.unwrap_or_else(|| gif_frame_duration(frame.delay));
+        // The time starts again in each pass
+        if frame.index == 0 {
+            time = std::time::Duration::ZERO;
+        }
+        time += duration;
+        TimedFrame {
+            frame: std::mem::take(&mut frame.frame),
+            cells: frame.cells.take(),
+            index: frame.index,
+            time: time - duration,
+            duration,
+            pts: None,
+        }
+    }));
+    if let Some(next_pass) = next_pass {
+        frames = frames.on_next_pass(move || {
+            let _ = next_pass.send(());
+        });
+    }
+    if let Some(mut exporter) = exporter {
+        // The frames stop before the next pass, the loop count is written to the export
+        for frame in frames {
+            exporter.add_frame(&frame.frame, frame.cells.as_ref(), frame.duration)?;
+        }
+        exporter.finish()?;
+        print_render_time(start_time, "");
+        return Ok(());
+    }
+    if config.record_only {
+        // An endless loop is recorded once, the players of the recording can loop it
+        let loop_count = match config.loop_count {
+            LoopCount::Forever => LoopCount::default(),
+            loop_count => loop_count,
+        };
+        let mut recording = Recording::new(recorder, true);
+        let mut diff = DiffRenderer::default();
+        for frame in play_passes(Player::new(frames, DEFAULT_CACHE_SIZE), loop_count) {
+            let output = match frame.cells {
+                Some(cells) => diff.render(cells),
+                None => frame.frame,
+            };
+            recording.record(
+                None,
+                &gif_frame_output(&output, frame.index, Some(""), true, false),
+                frame.duration,
+            );
+        }
+        recording.finish()?;
+        print_render_time(start_time, "");
+        return Ok(());
+    }
+
     // Load the audio if exists
     #[cfg(feature = "rodio")]
     let stream_handle = config.audio.get_path().map(|_| {
@@ -120,68 +669,83 @@ pub fn render_gif(results: crossbeam_channel::Receiver<Frame>, config: Config) {
         let file = std::io::BufReader::new(File::open(path).unwrap());
         rodio::play(stream_handle.as_ref().unwrap().mixer(), file).unwrap()
     });
-    // calculate the delay
-    let delay = config.fps.map(|fps| 100 / fps);
-    let start_time = std::time::Instant::now();
-    let (st, rt) = crossbeam_channel::unbounded::<JoinHandle<()>>();
-    fn play_frame(
-        frames: crossbeam_channel::Receiver<Frame>,
-        delay: Option<u64>,
-        st: crossbeam_channel::Sender<JoinHandle<()>>,
-        back_top: bool,
-        offset: std::time::Duration,
-    ) {
-        let frame = frames.recv();
-        if frame.is_err() {
-            return;
-        }
-        let frame = frame.unwrap();
-        let (frame, index, mut frame_delay) = frame.unpacking();
-        if let Some(delay) = delay {
-            frame_delay = delay;
-        }
-        let d = std::time::Duration::from_micros(frame_delay * 10_000).saturating_sub(offset);
-        let st2 = st.clone();
-        // create a new timer
-        let timer = std::time::Instant::now();
-        let task = std::thread::spawn(move || {
-            std::thread::sleep(d);
-            // calculate the time
-            let time = timer.elapsed();
-            play_frame(frames, delay, st2, back_top, time - d);
-        });
-        st.send(task).unwrap();
-
-        if back_top {
-            print!("\x1b[1;1H");
-        }
-        println!("{frame}");
-        println!("Current frame: {index}");
-        if !back_top {
-            // Back to the saved position
-            print!("\x1b[u");
-        }
-    }
+    let sync = use_synchronized_update();
+    // The terminal is probed before the keys are read
+    let controls = if config.interactive {
+        Controls::start()
+    } else {
+        None
+    };
     if config.clear {
         print!("\x1bc");
     }
-    if !config.clear {
+    let guard = TerminalGuard::new(config.alternate_screen);
+    let back_top = config.clear || config.alternate_screen;
+    if !back_top {
         print!("\x1b[s");
     }
-    play_frame(
-        results,
-        delay,
-        st,
-        config.clear,
-        std::time::Duration::default(),
-    );
+    let mut recording = Recording::new(recorder, back_top);
+    let scheduler = match controls {
+        Some(controls) => {
+            play_interactive(
+                frames,
+                controls,
+                PlaybackState::new(true, config.loop_count.is_looping()),
+                config.loop_count,
+                &mut recording,
+                |output, frame, info| gif_frame_output(output, frame.index, info, back_top, sync),
+                #[cfg(feature = "rodio")]
+                audio.as_ref(),
+            );
+            None
+        }
+        None => {
+            let mut scheduler = FrameScheduler::new();
+            let mut diff = DiffRenderer::default();
+            for frame in play_passes(Player::new(frames, DEFAULT_CACHE_SIZE), config.loop_count) {
+                // A dropped frame is not drawn, the last drawn frame is recorded longer
+                if !scheduler.wait(None, frame.duration) {
+                    recording.wait(frame.duration);
+                    continue;
+                }
+                let output = match frame.cells {
+                    Some(cells) => diff.render(cells),
+                    None => frame.frame,
+                };
+                let output = gif_frame_output(&output, frame.index, Some(""), back_top, sync);
+                // The recording has the delay of the frame, not the time it took to show it
+                recording.record(None, &output, frame.duration);
+                let mut stdout = std::io::stdout().lock();
+                let _ = stdout.write_all(output.as_bytes());
+                let _ = stdout.flush();
+            }
+            scheduler.finish();
+            Some(scheduler)
+        }
+    };
+    drop(guard);
 
-    for task in rt.iter() {
-        task.join().unwrap();
+    print_render_time(start_time, "");
+    if let Some(scheduler) = scheduler {
+        print_frame_rate(&scheduler, "");
+    }
+    // quit the audio stream
+    #[cfg(feature = "rodio")]
+    if let Some(audio) = audio {
+        audio.stop();
+    }
+    #[cfg(feature = "rodio")]
+    if let Some(stream_handle) = stream_handle {
+        std::mem::forget(stream_handle);
     }
+    recording.finish()
+}
 
+/// Print the time of the playback
+#[cfg(any(feature = "video_player", feature = "gif_player"))]
+fn print_render_time(start_time: std::time::Instant, prefix: &str) {
     println!(
-        "{} {}",
+        "{prefix}{} {}",
         "Render in"
             .to_colored_text()
             .set_foreground_color(TerminalColor::Green),
@@ -194,248 +758,365 @@ pub fn render_gif(results: crossbeam_channel::Receiver<Frame>, config: Config) {
         .to_colored_text()
         .set_foreground_color(TerminalColor::LightGreen)
     );
-    // quit the audio stream
-    #[cfg(feature = "rodio")]
-    if let Some(audio) = audio {
-        audio.stop();
+}
+
+/// Print the achieved frame rate of the playback and the number of dropped frames
+#[cfg(any(feature = "video_player", feature = "gif_player"))]
+fn print_frame_rate(scheduler: &FrameScheduler, prefix: &str) {
+    println!(
+        "{prefix}{} {}",
+        "Frame rate"
+            .to_colored_text()
+            .set_foreground_color(TerminalColor::Green),
+        format!(
+            "{:.2} fps ({} shown, {} dropped)",
+            scheduler.frame_rate(),
+            scheduler.shown(),
+            scheduler.dropped()
+        )
+        .to_colored_text()
+        .set_foreground_color(TerminalColor::LightGreen)
+    );
+}
+
+/// The receiver of the video frames: the output, the cells (only the changed cells are redrawn if
+/// they are set), the index and the presentation time
+pub type Vrx = crossbeam_channel::Receiver<(
+    String,
+    Option<image_to_console_core::converter::cell::CellGrid>,
+    usize,
+    Option<std::time::Duration>,
+)>;
+
+/// Compose the output of a video frame with its info
+///
+/// # Arguments
+///
+/// * `frame` - The frame
+/// * `index` - The index of the frame
+/// * `pts` - The presentation time of the frame
+/// * `audio_delay` - How far the frame is behind the audio
+/// * `info` - The text after the index of the frame, the info is hidden if it is `None`
+/// * `back_top` - Whether the frame is drawn at the top-left corner, otherwise at the saved position
+/// * `sync` - Whether the frame is wrapped in a synchronized update
+#[cfg(feature = "video_player")]
+fn video_frame_output(
+    frame: &str,
+    index: usize,
+    pts: Option<std::time::Duration>,
+    audio_delay: Option<std::time::Duration>,
+    info: Option<&str>,
+    back_top: bool,
+    sync: bool,
+) -> String {
+    let mut output = String::with_capacity(frame.len() + 128);
+    if sync {
+        output.push_str(BEGIN_SYNCHRONIZED_UPDATE);
     }
-    #[cfg(feature = "rodio")]
-    if let Some(stream_handle) = stream_handle {
-        std::mem::forget(stream_handle);
+    if back_top {
+        output.push_str("\x1b[1;1H");
+    }
+    output.push_str(frame);
+    if let Some(info) = info {
+        if let Some(pts) = pts {
+            output.push_str(&format!(
+                "\n\x1b[2K\rTime: {:02}:{:02}:{:02}.{:03}\n",
+                pts.as_secs() / 3600,
+                pts.as_secs() / 60,
+                pts.as_secs() % 60,
+                pts.as_millis() % 1000
+            ));
+        }
+        output.push_str(&format!("\x1b[2K\rcurrent frame: {index}{info}\n"));
+        if let Some(audio_delay) = audio_delay {
+            output.push_str(&format!("\x1b[2K\rcurrent delay: {audio_delay:?}\n"));
+        }
+    } else {
+        // Clear the info of the last frame
+        output.push_str("\x1b[J");
+    }
+    if !back_top {
+        // Back to the saved position
+        output.push_str("\x1b[u");
+    }
+    if sync {
+        output.push_str(END_SYNCHRONIZED_UPDATE);
     }
+    output
 }
 
-pub type Vrx = crossbeam_channel::Receiver<(String, usize, Option<std::time::Duration>)>;
-
+/// Play the frames of a video
+///
+/// The frames are played at `speed` times the normal speed, the playback time of a frame is its
+/// presentation time divided by the speed. The audio is played from `start` at the same speed.
+///
+/// The frames are recorded to `recorder` at their playback time since the first frame, so a
+/// recording made from `start` begins at 0. If `record_only` is set, only the recording is
+/// made, without a terminal, without the audio and without waiting between the frames. If
+/// `exporter` is set, the frames are exported instead of played or recorded.
+///
+/// The `subtitles` are looked up with the presentation time of the frames, and drawn over the
+/// cells or under the frames.
+///
+/// # Returns
+///
+/// Returns the error of writing the recording or the export
 #[allow(clippy::too_many_arguments)]
 #[cfg(feature = "video_player")]
 pub fn render_video(
     vrx: Vrx,
-    #[cfg(feature = "rodio")] audio_path: AudioPath,
+    #[cfg(feature = "rodio")] audio: VideoAudio,
+    #[cfg(feature = "rodio")] start: std::time::Duration,
     fps: f32,
+    speed: f32,
     clear: bool,
+    alternate_screen: bool,
+    interactive: bool,
     flush_interval: usize,
     disable_info: bool,
     is_shm: bool,
+    recorder: Option<AsciicastRecorder>,
+    exporter: Option<AnimationExporter>,
+    record_only: bool,
+    subtitles: Option<SubtitleOverlay>,
     #[cfg(feature = "rodio")] sync_pos: std::sync::Arc<std::sync::atomic::AtomicU64>,
-) {
-    // Load the audio if exists
-    #[cfg(feature = "rodio")]
-    let stream_handle =
-        rodio::OutputStreamBuilder::open_default_stream().expect("open default audio stream");
-    #[cfg(feature = "rodio")]
-    let file = audio_path
-        .get_path()
-        .map(|path| std::io::BufReader::new(File::open(path).unwrap()));
-    #[cfg(feature = "rodio")]
-    let sink =
-        std::sync::Arc::new(file.map(|file| rodio::play(stream_handle.mixer(), file).unwrap()));
-
-    // calculate the delay
+) -> Result<()> {
     let start_time = std::time::Instant::now();
-    let (st, rt) = crossbeam_channel::unbounded::<JoinHandle<()>>();
-    let max_frame = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
-
-    #[allow(clippy::too_many_arguments)]
-    fn play_frame(
-        frames: Vrx,
-        delay: f32,
-        st: crossbeam_channel::Sender<JoinHandle<()>>,
-        back_top: bool,
-        offset: std::time::Duration,
-        max_frame: std::sync::Arc<std::sync::atomic::AtomicUsize>,
-        flush_interval: usize,
-        disable_info: bool,
-        _is_shm: bool,
-        #[cfg(feature = "rodio")] sink: std::sync::Arc<Option<rodio::Sink>>,
-    ) {
-        let frame = frames.recv();
-        if frame.is_err() {
-            return;
+    let frame_duration = std::time::Duration::from_secs_f32(1f32 / (fps * speed));
+    let playback_time = |pts: Option<std::time::Duration>| pts.map(|pts| pts.div_f32(speed));
+    let draw_over = |pts: Option<std::time::Duration>, cells: Option<&mut CellGrid>| {
+        if let Some(subtitles) = &subtitles {
+            subtitles.draw_over(pts, cells);
         }
-        let frame = frame.unwrap();
-
-        #[allow(unused)]
-        let (frame, index, pts) = frame;
-
-        #[cfg(feature = "rodio")]
-        let sub = sink
+    };
+    let lines_under = |pts: Option<std::time::Duration>, cells: Option<&CellGrid>| {
+        subtitles
             .as_ref()
-            .as_ref()
-            .zip(pts.as_ref())
-            .map(|(sink, pts)| sink.get_pos().saturating_sub(*pts));
-
-        #[cfg(feature = "rodio")]
-        let offset = sub.unwrap_or(offset);
-
-        let d = std::time::Duration::from_micros((1_000_000f32 / delay).round() as u64)
-            .saturating_sub(offset);
-        let st2 = st.clone();
-        // create a new timer
-        let timer = std::time::Instant::now();
-        let max_frame_clone = max_frame.clone();
-        #[cfg(feature = "rodio")]
-        let other_sink = sink.clone();
-        let task = std::thread::spawn(move || {
-            std::thread::sleep(d);
-            // calculate the time
-            let time = timer.elapsed();
-            play_frame(
-                frames,
-                delay,
-                st2,
-                back_top,
-                time - d,
-                max_frame_clone,
-                flush_interval,
-                disable_info,
-                _is_shm,
-                #[cfg(feature = "rodio")]
-                other_sink,
-            );
-        });
-        st.send(task).unwrap();
-
-        if index <= max_frame.load(std::sync::atomic::Ordering::Relaxed) || index == 0 {
-            return;
-        }
-        max_frame.store(index, std::sync::atomic::Ordering::Relaxed);
-
-        let stdout = std::io::stdout();
-        let mut lock = stdout.lock();
-        // let lines = frame.as_bytes();
-        // for line in lines.chunks(lines.len().saturating_div(100).max(1)) {
-        //     if index < max_frame.load(std::sync::atomic::Ordering::Relaxed) || index == 0 {
-        //         return;
-        //     }
-        if index < max_frame.load(std::sync::atomic::Ordering::Relaxed) || index == 0 {
-            return;
-        }
-        if back_top {
-            lock.write_all("\x1b[1;1H".as_bytes()).unwrap();
+            .map(|subtitles| subtitles.lines_under(pts, cells))
+            .unwrap_or_default()
+    };
+    if let Some(mut exporter) = exporter {
+        // Only the subtitles over the cells are exported
+        for (frame, mut cells, _, pts) in vrx.iter() {
+            draw_over(pts, cells.as_mut());
+            exporter.add_frame(&frame, cells.as_ref(), frame_duration)?;
         }
-        if lock.write_all(frame.as_bytes()).is_err() {
-            return;
-        }
-        // }
-        // Refresh
-        if index % flush_interval == 0 {
-            lock.flush().unwrap();
-        }
-
-        if !disable_info {
-            if let Some(pts) = pts {
-                let _ = lock.write_all(
-                    format!(
-                        "\n\x1b[2K\rTime: {:02}:{:02}:{:02}.{:03}\n",
-                        pts.as_secs() / 3600,
-                        pts.as_secs() / 60,
-                        pts.as_secs() % 60,
-                        pts.as_millis() % 1000
-                    )
-                    .as_bytes(),
-                );
-            }
-            let _ = lock.write_all(format!("\x1b[2K\rcurrent frame: {index}\n").as_bytes());
-
-            #[cfg(feature = "rodio")]
-            if let Some(sub) = sub {
-                let _ = lock.write_all(format!("\x1b[2K\rcurrent delay: {sub:?}\n").as_bytes());
-            }
+        exporter.finish()?;
+        print_render_time(start_time, "\r");
+        return Ok(());
+    }
+    if record_only {
+        let mut recording = Recording::new(recorder, true);
+        let mut diff = DiffRenderer::default();
+        for (frame, mut cells, index, pts) in vrx.iter() {
+            draw_over(pts, cells.as_mut());
+            let under = lines_under(pts, cells.as_ref());
+            let frame = match cells {
+                Some(cells) => diff.render(cells),
+                None => frame,
+            } + &under;
+            recording.record(
+                playback_time(pts),
+                &video_frame_output(
+                    &frame,
+                    index,
+                    pts,
+                    None,
+                    (!disable_info).then_some(""),
+                    true,
+                    false,
+                ),
+                frame_duration,
+            );
         }
+        recording.finish()?;
+        print_render_time(start_time, "\r");
+        return Ok(());
+    }
 
-        if !back_top {
-            // Back to the saved position
-            lock.write_all("\x1b[u".as_bytes()).unwrap();
+    // Load the audio if exists
+    #[cfg(feature = "rodio")]
+    let stream_handle =
+        rodio::OutputStreamBuilder::open_default_stream().expect("open default audio stream");
+    // The decoded audio sets the clock itself, the position of a file is read from the sink
+    #[cfg(feature = "rodio")]
+    let poll_position = matches!(audio, VideoAudio::File(_));
+    #[cfg(feature = "rodio")]
+    let sink = match audio {
+        VideoAudio::File(path) => {
+            let file = std::io::BufReader::new(File::open(path).unwrap());
+            Some(rodio::play(stream_handle.mixer(), file).unwrap())
         }
-        drop(lock);
-
-        #[cfg(feature = "crossterm")]
-        if _is_shm {
-            let mut lock = std::io::stdin().lock();
-            let mut buf = [0; 1024];
-            let _ = lock.read(&mut buf);
+        VideoAudio::Decoded(audio) => {
+            let sink = rodio::Sink::connect_new(stream_handle.mixer());
+            sink.append(audio);
+            Some(sink)
         }
-    }
+        VideoAudio::None => None,
+    };
+    #[cfg(feature = "rodio")]
+    let sink = std::sync::Arc::new(sink.map(|sink| {
+        sink.set_speed(speed);
+        // The seek is in the playback time of the sink, which is sped up
+        let _ = sink.try_seek(start.div_f32(speed));
+        sink
+    }));
 
     #[cfg(feature = "crossterm")]
     if is_shm {
         let _ = crossterm::terminal::enable_raw_mode();
     }
 
+    let sync = use_synchronized_update();
+    // The replies of kitty are read from stdin in the shared memory modes
+    let controls = if interactive && !is_shm {
+        Controls::start()
+    } else {
+        None
+    };
     if clear {
         print!("\x1bc");
     }
+    let guard = TerminalGuard::new(alternate_screen);
 
     // Save current cursor position
     print!("\r\x1b[s");
-    play_frame(
-        vrx,
-        fps,
-        st,
-        clear,
-        std::time::Duration::default(),
-        max_frame.clone(),
-        flush_interval,
-        disable_info,
-        is_shm,
-        #[cfg(feature = "rodio")]
-        sink.clone(),
-    );
-
-    #[cfg(feature = "rodio")]
-    let (sr, rr) = std::sync::mpsc::channel::<()>();
-
-    #[cfg(feature = "rodio")]
-    std::thread::spawn(move || {
-        if sink.is_some() {
-            loop {
-                match rr.try_recv() {
-                    Ok(..) => break,
-                    Err(e) => {
-                        if e == std::sync::mpsc::TryRecvError::Disconnected {
-                            break;
+    let back_top = clear || alternate_screen;
+    let mut recording = Recording::new(recorder, back_top);
+    let scheduler = match controls {
+        Some(controls) => {
+            let frames = vrx.iter().map(|(frame, mut cells, index, pts)| {
+                draw_over(pts, cells.as_mut());
+                TimedFrame {
+                    frame,
+                    cells,
+                    index,
+                    time: playback_time(pts).unwrap_or(frame_duration * index as u32),
+                    duration: frame_duration,
+                    pts,
+                }
+            });
+            play_interactive(
+                frames,
+                controls,
+                PlaybackState::new(!disable_info, false),
+                LoopCount::default(),
+                &mut recording,
+                |output, frame, info| {
+                    let output = output.to_string() + &lines_under(frame.pts, frame.cells.as_ref());
+                    video_frame_output(&output, frame.index, frame.pts, None, info, back_top, sync)
+                },
+                #[cfg(feature = "rodio")]
+                sink.as_ref().as_ref(),
+            );
+            None
+        }
+        None => {
+            // The position of the audio is shared with the decoding, which drops the late frames
+            #[cfg(feature = "rodio")]
+            let (sr, rr) = std::sync::mpsc::channel::<()>();
+            #[cfg(feature = "rodio")]
+            let audio = sink.clone();
+            #[cfg(feature = "rodio")]
+            std::thread::spawn(move || {
+                if let Some(audio) = audio.as_ref().as_ref().filter(|_| poll_position) {
+                    loop {
+                        match rr.try_recv() {
+                            Ok(..) => break,
+                            Err(e) => {
+                                if e == std::sync::mpsc::TryRecvError::Disconnected {
+                                    break;
+                                }
+                            }
                         }
+                        // The position in the video, the sink is at the sped up playback time
+                        sync_pos.store(
+                            audio.get_pos().mul_f32(speed).as_millis() as u64,
+                            std::sync::atomic::Ordering::SeqCst,
+                        );
+                        std::thread::sleep(std::time::Duration::from_millis(5));
+                    }
+                }
+            });
+
+            let mut scheduler = FrameScheduler::new();
+            let mut diff = DiffRenderer::default();
+            for (frame, mut cells, index, pts) in vrx.iter() {
+                // The audio is the clock of the playback, the delay is how far the frame is behind it
+                #[cfg(feature = "rodio")]
+                let audio_delay = match (sink.as_ref().as_ref(), playback_time(pts)) {
+                    (Some(sink), Some(time)) => {
+                        let position = sink.get_pos();
+                        scheduler.sync(position);
+                        Some(position.saturating_sub(time))
                     }
+                    _ => None,
+                };
+                #[cfg(not(feature = "rodio"))]
+                let audio_delay: Option<std::time::Duration> = None;
+
+                // A dropped frame is not drawn, the last drawn frame is recorded longer
+                if !scheduler.wait(playback_time(pts), frame_duration) {
+                    recording.wait(frame_duration);
+                    continue;
                 }
-                sync_pos.store(
-                    sink.as_ref().as_ref().unwrap().get_pos().as_millis() as u64,
-                    std::sync::atomic::Ordering::SeqCst,
+                // Only the frames written to the screen are diffed
+                draw_over(pts, cells.as_mut());
+                let under = lines_under(pts, cells.as_ref());
+                let frame = match cells {
+                    Some(cells) => diff.render(cells),
+                    None => frame,
+                } + &under;
+                let output = video_frame_output(
+                    &frame,
+                    index,
+                    pts,
+                    audio_delay,
+                    (!disable_info).then_some(""),
+                    back_top,
+                    sync,
                 );
-                std::thread::sleep(std::time::Duration::from_millis(5));
-            }
-        }
-    });
+                recording.record(playback_time(pts), &output, frame_duration);
+                let mut stdout = std::io::stdout().lock();
+                if stdout.write_all(output.as_bytes()).is_err() {
+                    break;
+                }
+                // Refresh
+                if index % flush_interval == 0 {
+                    let _ = stdout.flush();
+                }
+                drop(stdout);
 
-    for task in rt.iter() {
-        task.join().unwrap();
-    }
+                #[cfg(feature = "crossterm")]
+                if is_shm {
+                    let mut lock = std::io::stdin().lock();
+                    let mut buf = [0; 1024];
+                    let _ = lock.read(&mut buf);
+                }
+            }
+            let _ = std::io::stdout().flush();
+            scheduler.finish();
 
-    #[cfg(feature = "rodio")]
-    sr.send(()).unwrap();
+            #[cfg(feature = "rodio")]
+            let _ = sr.send(());
+            Some(scheduler)
+        }
+    };
+    drop(guard);
 
     #[cfg(feature = "crossterm")]
     if is_shm {
         let _ = crossterm::terminal::disable_raw_mode();
     }
 
-    println!(
-        "\r{} {}",
-        "Render in"
-            .to_colored_text()
-            .set_foreground_color(TerminalColor::Green),
-        format!(
-            "{:02}:{:02}.{:03}",
-            start_time.elapsed().as_secs() / 60,
-            start_time.elapsed().as_secs() % 60,
-            start_time.elapsed().as_millis() % 1000
-        )
-        .to_colored_text()
-        .set_foreground_color(TerminalColor::LightGreen)
-    );
+    print_render_time(start_time, "\r");
+    if let Some(scheduler) = scheduler {
+        print_frame_rate(&scheduler, "\r");
+    }
 
     // audio_task.join().unwrap();
     // quit the audio stream
     #[cfg(feature = "rodio")]
     std::mem::forget(stream_handle);
+    recording.finish()
 }
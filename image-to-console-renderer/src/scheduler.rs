@@ -0,0 +1,142 @@
+#[cfg(feature = "gif_player")]
+use crate::frame::FrameDelay;
+use std::time::{Duration, Instant};
+
+/// The delay used for the GIF frames with a delay of 0 or 1 hundredth of a second, like browsers
+pub const DEFAULT_GIF_DELAY: u64 = 10;
+
+/// Get how long a frame of an animation is shown
+///
+/// # Arguments
+///
+/// * `delay` - The delay of the frame
+///
+/// # Returns
+///
+/// Returns the duration of the frame, the GIF delays of 0 and 1 are shown for
+/// [`DEFAULT_GIF_DELAY`] hundredths of a second, the delays of the other formats are kept
+#[cfg(feature = "gif_player")]
+pub fn gif_frame_duration(delay: FrameDelay) -> Duration {
+    match delay {
+        FrameDelay::Hundredths(delay) if delay <= 1 => {
+            Duration::from_millis(DEFAULT_GIF_DELAY * 10)
+        }
+        delay => delay.duration(),
+    }
+}
+
+/// Schedules the frames of a playback on one thread
+///
+/// The times of the frames are measured on a monotonic clock from the first frame, so the
+/// waiting and drawing time of a frame does not delay the next ones. A frame is dropped when the
+/// player is so far behind that the next frame is already due.
+#[derive(Debug)]
+pub struct FrameScheduler {
+    /// When the clock started, set by the first frame to its time
+    start: Option<Instant>,
+    /// The time of the next frame since the start
+    clock: Duration,
+    /// When the first frame was shown
+    first: Option<Instant>,
+    /// The number of frames shown
+    shown: usize,
+    /// The number of frames dropped
+    dropped: usize,
+}
+
+impl Default for FrameScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameScheduler {
+    /// Create a scheduler, the clock starts at the time of the first frame
+    pub fn new() -> Self {
+        Self {
+            start: None,
+            clock: Duration::ZERO,
+            first: None,
+            shown: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Move the clock to a time of the playback, e.g. the position of the audio
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The current time of the playback
+    pub fn sync(&mut self, time: Duration) {
+        let now = Instant::now();
+        self.start = Some(now.checked_sub(time).unwrap_or(now));
+    }
+
+    /// Wait until a frame is due
+    ///
+    /// The first frame is always shown.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The presentation time of the frame, the frame follows the last one if it is `None`
+    /// * `duration` - How long the frame is shown
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the frame is shown now, `false` if it is dropped because its time is over
+    pub fn wait(&mut self, time: Option<Duration>, duration: Duration) -> bool {
+        let start = *self.start.get_or_insert_with(|| {
+            // The playback may not start at the beginning, e.g. after a seek
+            let now = Instant::now();
+            time.and_then(|time| now.checked_sub(time)).unwrap_or(now)
+        });
+        if let Some(time) = time {
+            self.clock = self.clock.max(time);
+        }
+        let due = self.clock;
+        self.clock += duration;
+        let elapsed = start.elapsed();
+        if self.shown > 0 && elapsed >= self.clock {
+            self.dropped += 1;
+            return false;
+        }
+        if let Some(wait) = due.checked_sub(elapsed) {
+            std::thread::sleep(wait);
+        }
+        self.first.get_or_insert_with(Instant::now);
+        self.shown += 1;
+        true
+    }
+
+    /// Wait until the last frame has been shown for its duration
+    pub fn finish(&self) {
+        if let Some(start) = self.start
+            && let Some(wait) = self.clock.checked_sub(start.elapsed())
+        {
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// Get the number of frames shown
+    pub fn shown(&self) -> usize {
+        self.shown
+    }
+
+    /// Get the number of frames dropped
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Get the achieved frame rate, the frames shown per second since the first frame
+    pub fn frame_rate(&self) -> f64 {
+        let elapsed = self
+            .first
+            .map_or(Duration::ZERO, |first| first.elapsed())
+            .as_secs_f64();
+        if elapsed > 0.0 {
+            self.shown as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+}
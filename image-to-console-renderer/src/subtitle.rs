@@ -0,0 +1,166 @@
+use image_to_console_core::converter::cell::{Cell, CellAttributes, CellGrid};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// The number of lines kept under the frames for the subtitles, the longer subtitles are cut
+pub const SUBTITLE_LINES: usize = 2;
+
+/// Where the subtitles of a video are drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SubtitlePosition {
+    /// On the lines under the frame
+    #[default]
+    Below,
+    /// Over the last rows of the frame, only in the character-cell modes (the frames drawn with
+    /// a graphics protocol and the subtitles with wide characters are drawn under them)
+    Overlay,
+}
+
+/// The subtitles drawn with the frames of a video
+pub struct SubtitleOverlay {
+    /// Get the text shown at a presentation time
+    text_at: Box<dyn Fn(Duration) -> Option<String> + Send + Sync>,
+    position: SubtitlePosition,
+    /// Whether the last lines under a frame had a subtitle, in the overlay position
+    under: AtomicBool,
+}
+
+impl SubtitleOverlay {
+    /// Create the subtitles of a video
+    ///
+    /// # Arguments
+    ///
+    /// * `text_at` - Get the text shown at a presentation time, the lines are separated by `\n`
+    /// * `position` - Where the subtitles are drawn
+    ///
+    /// # Returns
+    ///
+    /// Returns a new overlay
+    pub fn new(
+        text_at: impl Fn(Duration) -> Option<String> + Send + Sync + 'static,
+        position: SubtitlePosition,
+    ) -> Self {
+        Self {
+            text_at: Box::new(text_at),
+            position,
+            under: AtomicBool::new(false),
+        }
+    }
+
+    pub fn position(&self) -> SubtitlePosition {
+        self.position
+    }
+
+    fn text_at(&self, pts: Option<Duration>) -> Option<String> {
+        pts.and_then(|pts| (self.text_at)(pts))
+    }
+
+    /// Draw the subtitle of a frame over its cells, in the overlay position
+    ///
+    /// # Arguments
+    ///
+    /// * `pts` - The presentation time of the frame
+    /// * `cells` - The cells of the frame, nothing is drawn for the frames without cells
+    pub fn draw_over(&self, pts: Option<Duration>, cells: Option<&mut CellGrid>) {
+        if self.position != SubtitlePosition::Overlay {
+            return;
+        }
+        if let (Some(cells), Some(text)) = (cells, self.text_at(pts))
+            && !is_wide(&text)
+        {
+            overlay_text(cells, &text);
+        }
+    }
+
+    /// Get the lines written under a frame, in the below position, for the frames without cells
+    /// or for the subtitles with wide characters
+    ///
+    /// The lines are always written while they may hold a subtitle, so the subtitle of the last
+    /// frame is cleared. The lines longer than the frame are cut.
+    ///
+    /// # Arguments
+    ///
+    /// * `pts` - The presentation time of the frame
+    /// * `cells` - The cells of the frame, the subtitle is centered under them
+    ///
+    /// # Returns
+    ///
+    /// Returns the lines, each one starting with a line feed
+    pub fn lines_under(&self, pts: Option<Duration>, cells: Option<&CellGrid>) -> String {
+        let text = self.text_at(pts).unwrap_or_default();
+        if self.position == SubtitlePosition::Overlay && cells.is_some() {
+            // The wide characters cannot be drawn in the cells, one takes two columns
+            let under = is_wide(&text);
+            if !under && !self.under.swap(false, Ordering::Relaxed) {
+                return String::new();
+            }
+            self.under.store(under, Ordering::Relaxed);
+        }
+        let width = cells.map(|cells| (cells.left + cells.width) as usize);
+        let mut lines = text.lines();
+        let mut output = String::new();
+        for _ in 0..SUBTITLE_LINES {
+            let mut line = lines.next().unwrap_or_default();
+            if let Some(width) = width {
+                line = cut(line, width);
+            }
+            let padding = width.map_or(0, |width| width.saturating_sub(line.width()) / 2);
+            output.push_str("\n\x1b[2K\r");
+            output.extend(std::iter::repeat_n(' ', padding));
+            output.push_str(line);
+        }
+        output
+    }
+}
+
+/// Check if a text has characters taking more than one column, which cannot be drawn in cells
+fn is_wide(text: &str) -> bool {
+    text.chars().any(|glyph| glyph.width().unwrap_or(0) > 1)
+}
+
+/// Cut a line to the characters fitting in a number of columns
+fn cut(line: &str, columns: usize) -> &str {
+    let mut width = 0;
+    for (index, glyph) in line.char_indices() {
+        width += glyph.width().unwrap_or(0);
+        if width > columns {
+            return &line[..index];
+        }
+    }
+    line
+}
+
+/// Draw a text centered over the last rows of a grid
+///
+/// The text is drawn in reverse video with a blank cell on each side, so it can be read over
+/// any image and in the modes without colors. The lines longer than the grid are cut, the text
+/// should not have wide characters, each character takes one cell.
+///
+/// # Arguments
+///
+/// * `grid` - The cells of the frame
+/// * `text` - The text, the lines are separated by `\n`
+pub fn overlay_text(grid: &mut CellGrid, text: &str) {
+    let lines = text
+        .lines()
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    let lines = &lines[lines.len().saturating_sub(grid.height as usize)..];
+    let attrs = CellAttributes {
+        reverse: true,
+        ..Default::default()
+    };
+    let first_row = grid.height as usize - lines.len();
+    for (row, line) in (first_row..).zip(lines) {
+        let glyphs = std::iter::once(' ')
+            .chain(line.chars())
+            .chain(std::iter::once(' '))
+            .take(grid.width as usize)
+            .collect::<Vec<_>>();
+        let start = row * grid.width as usize + (grid.width as usize - glyphs.len()) / 2;
+        for (cell, glyph) in grid.cells[start..].iter_mut().zip(glyphs) {
+            *cell = Cell::new(glyph, None, None).with_attributes(attrs);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use image_to_console_renderer::asciicast::{AsciicastRecorder, escape_json};
+use std::time::Duration;
+
+fn cast_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{name}-{}.cast", std::process::id()))
+}
+
+fn read_cast(path: &std::path::Path) -> Vec<String> {
+    let cast = std::fs::read_to_string(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    cast.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn test_escape_json() {
+    assert_eq!(escape_json("a\"b\\c"), "a\\\"b\\\\c");
+    assert_eq!(escape_json("\x1b[0m\n\r\t▀"), "\\u001b[0m\\n\\r\\t▀");
+}
+
+#[test]
+fn test_recorder() {
+    let path = cast_path("recorder");
+    let mut recorder = AsciicastRecorder::create(&path).unwrap();
+    recorder.preamble("\x1b[?25l");
+    // The escape sequences are not counted in the size
+    recorder
+        .record(
+            Duration::ZERO,
+            "\x1b[38;2;255;0;0mabc\x1b[0m\n\x1b_Ga=T;AAAA\x1b\\de\n",
+        )
+        .unwrap();
+    recorder
+        .record(Duration::from_millis(1500), "\x1b[1;1Hx")
+        .unwrap();
+    recorder
+        .finish(Duration::from_secs(2), "\x1b[?25h")
+        .unwrap();
+
+    let lines = read_cast(&path);
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("{\"version\": 2, \"width\": 3, \"height\": 3, \"timestamp\": "));
+    assert!(lines[1].starts_with("[0.000000, \"o\", \"\\u001b[?25l\\u001b[38;2;255;0;0mabc"));
+    assert_eq!(lines[2], "[1.500000, \"o\", \"\\u001b[1;1Hx\"]");
+    assert_eq!(lines[3], "[2.000000, \"o\", \"\\u001b[?25h\"]");
+
+    // The size is set instead of measured
+    let path = cast_path("empty");
+    let mut recorder = AsciicastRecorder::create(&path).unwrap();
+    recorder.size(100, 30);
+    recorder.finish(Duration::ZERO, "").unwrap();
+    let lines = read_cast(&path);
+    assert!(lines[0].contains("\"width\": 100, \"height\": 30"));
+    assert_eq!(lines[1], "[0.000000, \"o\", \"\"]");
+}
+
+#[cfg(feature = "gif_player")]
+#[test]
+fn test_record_gif() {
+    use image_to_console_renderer::{
+        config::Config,
+        frame::{Frame, FrameDelay},
+        renderer::render_gif,
+    };
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    for (index, delay) in [(0, 10), (1, 25)] {
+        tx.send(Frame {
+            index,
+            frame: format!("frame {index}"),
+            delay: FrameDelay::Hundredths(delay),
+            cells: None,
+        })
+        .unwrap();
+    }
+    drop(tx);
+    let path = cast_path("gif");
+    let config = Config {
+        record_only: true,
+        ..Default::default()
+    };
+    // The frames are recorded at their delays, without waiting for them
+    let time = std::time::Instant::now();
+    render_gif(
+        rx,
+        config,
+        Some(AsciicastRecorder::create(&path).unwrap()),
+        None,
+        None,
+    )
+    .unwrap();
+    assert!(time.elapsed() < Duration::from_millis(300));
+
+    let lines = read_cast(&path);
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("[0.000000, \"o\", "));
+    assert!(lines[1].ends_with("\\u001b[1;1Hframe 0\\r\\nCurrent frame: 0\\r\\n\"]"));
+    assert!(lines[2].starts_with("[0.100000, \"o\", \"\\u001b[1;1Hframe 1"));
+    assert_eq!(lines[3], "[0.350000, \"o\", \"\\u001b[?25h\"]");
+}
+
+#[cfg(feature = "gif_player")]
+#[test]
+fn test_record_gif_loop() {
+    use image_to_console_renderer::{
+        config::{Config, LoopCount},
+        frame::{Frame, FrameDelay},
+        renderer::render_gif,
+    };
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    // The frames are sent once, the second pass is played from the cache
+    for index in 0..2 {
+        tx.send(Frame {
+            index,
+            frame: format!("frame {index}"),
+            delay: FrameDelay::Hundredths(10),
+            cells: None,
+        })
+        .unwrap();
+    }
+    drop(tx);
+    let path = cast_path("gif-loop");
+    let config = Config {
+        record_only: true,
+        loop_count: LoopCount::Times(2),
+        ..Default::default()
+    };
+    render_gif(
+        rx,
+        config,
+        Some(AsciicastRecorder::create(&path).unwrap()),
+        None,
+        None,
+    )
+    .unwrap();
+
+    let lines = read_cast(&path);
+    assert_eq!(lines.len(), 6);
+    assert!(lines[3].starts_with("[0.200000, \"o\", \"\\u001b[1;1Hframe 0"));
+    assert_eq!(lines[5], "[0.400000, \"o\", \"\\u001b[?25h\"]");
+}
+
+#[cfg(feature = "video_player")]
+#[test]
+fn test_record_video_start() {
+    use image_to_console_renderer::renderer::render_video;
+
+    // The video is played from 10 seconds at twice the speed
+    let (tx, rx) = crossbeam_channel::unbounded();
+    for index in 0..2 {
+        let pts = Duration::from_secs(10) + Duration::from_millis(500) * index as u32;
+        tx.send((format!("frame {index}"), None, index, Some(pts)))
+            .unwrap();
+    }
+    drop(tx);
+    let path = cast_path("video-start");
+    let recorder = Some(AsciicastRecorder::create(&path).unwrap());
+    #[cfg(feature = "rodio")]
+    let result = render_video(
+        rx,
+        Default::default(),
+        Duration::from_secs(10),
+        2.0,
+        2.0,
+        false,
+        false,
+        false,
+        0,
+        true,
+        false,
+        recorder,
+        None,
+        true,
+        None,
+        Default::default(),
+    );
+    #[cfg(not(feature = "rodio"))]
+    let result = render_video(
+        rx, 2.0, 2.0, false, false, false, 0, true, false, recorder, None, true, None,
+    );
+    result.unwrap();
+
+    // The recording starts at the first frame, not at the start of the video
+    let lines = read_cast(&path);
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("[0.000000, \"o\", "));
+    assert!(lines[2].starts_with("[0.250000, \"o\", \"\\u001b[1;1Hframe 1"));
+    assert_eq!(lines[3], "[0.500000, \"o\", \"\\u001b[?25h\"]");
+}
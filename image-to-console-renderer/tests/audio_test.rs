@@ -0,0 +1,56 @@
+#![cfg(feature = "rodio")]
+
+use image_to_console_renderer::audio::DecodedAudio;
+use rodio::Source;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+/// Mono frames of 10 samples at 100 Hz, each one 100 ms long, filled with their index
+fn audio(starts: &[u64], clock: Arc<AtomicU64>) -> DecodedAudio {
+    let frames = starts
+        .iter()
+        .enumerate()
+        .map(|(index, &start)| (Duration::from_millis(start), vec![index as f32; 10]))
+        .collect::<Vec<_>>();
+    DecodedAudio::new(frames.into_iter(), 1, 100, clock)
+}
+
+#[test]
+fn test_gap_is_silent() {
+    let clock = Arc::new(AtomicU64::new(0));
+    let mut audio = audio(&[1000, 1150], clock.clone());
+    assert_eq!(audio.channels(), 1);
+    assert_eq!(audio.sample_rate(), 100);
+    assert_eq!(audio.next(), Some(0.0));
+    // The clock is at the first frame
+    assert_eq!(clock.load(Ordering::SeqCst), 1000);
+    let samples = audio.by_ref().take(24).collect::<Vec<_>>();
+    assert_eq!(samples[..9], [0.0; 9]);
+    // The 50 ms gap is filled with silence before the second frame
+    assert_eq!(samples[14..], [1.0; 10]);
+    assert_eq!(clock.load(Ordering::SeqCst), 1100);
+    assert_eq!(audio.next(), None);
+}
+
+#[test]
+fn test_seek() {
+    let clock = Arc::new(AtomicU64::new(0));
+    let mut audio = audio(&[0, 100, 200], clock.clone());
+    // Forward, the samples are skipped
+    audio.try_seek(Duration::from_millis(150)).unwrap();
+    assert_eq!(clock.load(Ordering::SeqCst), 150);
+    assert_eq!(audio.next(), Some(1.0));
+
+    // Back in the played audio
+    audio.try_seek(Duration::from_millis(50)).unwrap();
+    assert_eq!(clock.load(Ordering::SeqCst), 50);
+    let samples = audio.by_ref().collect::<Vec<_>>();
+    assert_eq!(samples.len(), 25);
+    assert_eq!(samples[..5], [0.0; 5]);
+    assert_eq!(samples[15..], [2.0; 10]);
+}
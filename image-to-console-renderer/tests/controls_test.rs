@@ -0,0 +1,55 @@
+use image_to_console_renderer::controls::{Command, PlaybackState, SPEEDS};
+
+#[test]
+fn test_playback_state() {
+    let mut state = PlaybackState::new(true, false);
+    assert_eq!(state.speed(), 1.0);
+    assert_eq!(state.status(), " | playing | 1x | loop off");
+
+    assert!(state.apply(Command::TogglePause));
+    assert!(state.apply(Command::SpeedUp));
+    assert!(state.apply(Command::ToggleLoop));
+    assert!(state.apply(Command::ToggleInfo));
+    assert_eq!(state.status(), " | paused | 1.25x | loop on");
+    assert!(!state.show_info);
+    // Seeking is left to the player
+    assert!(!state.apply(Command::Forward));
+
+    for _ in 0..SPEEDS.len() {
+        state.apply(Command::SpeedDown);
+    }
+    assert_eq!(state.speed(), SPEEDS[0]);
+    assert!(!state.apply(Command::SpeedDown));
+}
+
+#[cfg(feature = "crossterm")]
+#[test]
+fn test_keys() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    assert_eq!(
+        Command::from_key(key(KeyCode::Char(' '))),
+        Some(Command::TogglePause)
+    );
+    assert_eq!(
+        Command::from_key(key(KeyCode::Left)),
+        Some(Command::Backward)
+    );
+    assert_eq!(
+        Command::from_key(key(KeyCode::Char('='))),
+        Some(Command::SpeedUp)
+    );
+    assert_eq!(
+        Command::from_key(key(KeyCode::Char('q'))),
+        Some(Command::Quit)
+    );
+    assert_eq!(Command::from_key(key(KeyCode::Char('x'))), None);
+    assert_eq!(
+        Command::from_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+        Some(Command::Quit)
+    );
+    let mut release = key(KeyCode::Char('l'));
+    release.kind = KeyEventKind::Release;
+    assert_eq!(Command::from_key(release), None);
+}
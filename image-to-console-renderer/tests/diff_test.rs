@@ -0,0 +1,109 @@
+use image_to_console_core::converter::cell::{Cell, CellGrid};
+use image_to_console_renderer::diff::DiffRenderer;
+
+const RED: Option<[u8; 3]> = Some([255, 0, 0]);
+
+fn grid(changed: &[(u32, u32)]) -> CellGrid {
+    let mut grid = CellGrid::new(20, 3);
+    grid.left = 2;
+    for &(x, y) in changed {
+        grid.cells[(y * grid.width + x) as usize] = Cell::new('▀', RED, None);
+    }
+    grid
+}
+
+#[test]
+fn test_diff_render() {
+    let mut renderer = DiffRenderer::default();
+    // The first frame is drawn completely
+    let first = renderer.render(grid(&[]));
+    assert_eq!(first.lines().count(), 3);
+    assert_eq!(first.lines().next(), Some(" ".repeat(22).as_str()));
+
+    // The close cells are one run, and the cursor ends on the last row
+    let output = renderer.render(grid(&[(1, 1), (3, 1), (15, 1)]));
+    assert_eq!(
+        output,
+        "\x1b[1B\x1b[4G\x1b[38;2;255;0;0m▀\x1b[0m \x1b[38;2;255;0;0m▀\x1b[0m\
+         \x1b[18G\x1b[38;2;255;0;0m▀\x1b[0m\x1b[1B\x1b[23G"
+    );
+
+    // Nothing changed
+    assert_eq!(
+        renderer.render(grid(&[(1, 1), (3, 1), (15, 1)])),
+        "\x1b[2B\x1b[23G"
+    );
+
+    // Too many changed cells
+    let all = (0..20)
+        .flat_map(|x| (0..3).map(move |y| (x, y)))
+        .collect::<Vec<_>>();
+    assert_eq!(renderer.render(grid(&all)).lines().count(), 3);
+
+    // A frame in another layout is drawn completely
+    let mut moved = grid(&all);
+    moved.top = 1;
+    assert!(renderer.render(moved).starts_with('\n'));
+}
+
+/// Draw the output on a screen of characters, only the sequences of the frames are handled
+fn draw(screen: &mut [Vec<char>], output: &str) {
+    let (mut row, mut col) = (0, 0);
+    let mut chars = output.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => (row, col) = (row + 1, 0),
+            '\x1b' => {
+                chars.next();
+                let mut param = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_ascii_alphabetic()) {
+                    param.push(c);
+                }
+                let n = param.parse::<usize>().unwrap_or(1);
+                match chars.next() {
+                    Some('B') => row += n,
+                    Some('G') => col = n - 1,
+                    Some('H') => (row, col) = (0, 0),
+                    // Erase from the cursor to the end of the screen
+                    Some('J') => {
+                        for (y, line) in screen.iter_mut().enumerate().skip(row) {
+                            let from = if y == row { col } else { 0 };
+                            line.iter_mut().skip(from).for_each(|c| *c = ' ');
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            c => {
+                screen[row][col] = c;
+                col += 1;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_erase_below() {
+    // The info is hidden with an erase after the frame, it must not erase the cells
+    let mut renderer = DiffRenderer::default();
+    let mut screen = vec![vec![' '; 30]; 5];
+    let frames = [
+        grid(&[(19, 2)]),
+        grid(&[(19, 2), (5, 2)]),
+        grid(&[(19, 2), (5, 2)]),
+    ];
+    for frame in frames {
+        let expected = frame.clone();
+        let output = renderer.render(frame);
+        draw(&mut screen, &format!("\x1b[1;1H{output}\x1b[J"));
+        for (y, line) in screen.iter().take(3).enumerate() {
+            let row = line[2..22].iter().collect::<String>();
+            let cells = expected
+                .row(y as u32)
+                .iter()
+                .map(|cell| cell.glyph)
+                .collect::<String>();
+            assert_eq!(row, cells);
+        }
+    }
+}
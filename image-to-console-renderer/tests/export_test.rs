@@ -0,0 +1,140 @@
+#![cfg(feature = "gif_player")]
+
+use image_to_console_core::converter::cell::{Cell, CellGrid};
+use image_to_console_renderer::{
+    config::{Config, LoopCount, OutputFormat},
+    export::{AnimationExporter, MANIFEST_NAME},
+    frame::{Frame, FrameDelay},
+    renderer::render_gif,
+};
+use std::{fs, path::PathBuf, time::Duration};
+
+fn export_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("export-{}-{name}", std::process::id()))
+}
+
+fn cells(color: [u8; 3]) -> CellGrid {
+    let mut grid = CellGrid::new(2, 1);
+    grid.cells = vec![Cell::new(' ', None, Some(color)); 2];
+    grid
+}
+
+#[test]
+fn test_output_format() {
+    assert_eq!(OutputFormat::from_path("out.GIF"), OutputFormat::Gif);
+    assert_eq!(OutputFormat::from_path("frames/"), OutputFormat::Frames);
+    assert_eq!(OutputFormat::from_path("frames"), OutputFormat::Text);
+    assert!(OutputFormat::Frames.is_animation() && !OutputFormat::Frames.needs_cells());
+    assert!(OutputFormat::Gif.needs_cells());
+    assert!(
+        AnimationExporter::create(
+            export_path("out.png"),
+            OutputFormat::Png,
+            LoopCount::default()
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_export_frames() {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    // The second pass is not exported
+    for (index, delay) in [(0, 10), (1, 25), (0, 10)] {
+        tx.send(Frame {
+            index,
+            frame: format!("\x1b[31mframe {index}\x1b[0m"),
+            delay: FrameDelay::Hundredths(delay),
+            cells: None,
+        })
+        .unwrap();
+    }
+    drop(tx);
+    let path = export_path("frames");
+    let exporter =
+        AnimationExporter::create(&path, OutputFormat::Frames, LoopCount::Forever).unwrap();
+    let config = Config {
+        loop_count: LoopCount::Forever,
+        ..Default::default()
+    };
+    render_gif(rx, config, None, Some(exporter), None).unwrap();
+
+    let frame = fs::read_to_string(path.join("frame_000001.ans")).unwrap();
+    assert_eq!(frame, "\x1b[31mframe 1\x1b[0m\n");
+    assert!(!path.join("frame_000002.ans").exists());
+    let manifest = fs::read_to_string(path.join(MANIFEST_NAME)).unwrap();
+    fs::remove_dir_all(&path).unwrap();
+    assert_eq!(
+        manifest,
+        "{\"version\": 1, \"loop_count\": 0, \"frames\": [\n  \
+         {\"file\": \"frame_000000.ans\", \"time\": 0.000000, \"duration\": 0.100000},\n  \
+         {\"file\": \"frame_000001.ans\", \"time\": 0.100000, \"duration\": 0.250000}\n]}\n"
+    );
+}
+
+#[test]
+fn test_export_gif() {
+    let path = export_path("out.gif");
+    let mut exporter =
+        AnimationExporter::create(&path, OutputFormat::Gif, LoopCount::Times(3)).unwrap();
+    // The delays are rounded from the times: 0.033 -> 3, 0.066 -> 7, 0.099 -> 10
+    for color in [[255, 0, 0], [0, 0, 255], [255, 0, 0]] {
+        exporter
+            .add_frame("", Some(&cells(color)), Duration::from_millis(33))
+            .unwrap();
+    }
+    // The frames must have the same size
+    let mut grid = CellGrid::new(1, 1);
+    grid.cells = vec![Cell::new(' ', None, None)];
+    assert!(exporter.add_frame("", Some(&grid), Duration::ZERO).is_err());
+    // The cells are needed
+    assert!(exporter.add_frame("", None, Duration::ZERO).is_err());
+    assert_eq!(exporter.frames(), 3);
+    exporter.finish().unwrap();
+
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (16, 16));
+    let mut delays = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        delays.push(frame.delay);
+        if delays.len() == 2 {
+            assert_eq!(&frame.buffer[..4], [0, 0, 255, 255]);
+        }
+    }
+    // The NETSCAPE loop count does not count the first play
+    assert_eq!(decoder.repeat(), gif::Repeat::Finite(2));
+    fs::remove_file(&path).unwrap();
+    assert_eq!(delays, [3, 4, 3]);
+}
+
+#[test]
+fn test_export_gif_60_fps() {
+    let path = export_path("60fps.gif");
+    let mut exporter =
+        AnimationExporter::create(&path, OutputFormat::Gif, LoopCount::Forever).unwrap();
+    for index in 0..60 {
+        let color = if index % 2 == 0 {
+            [255, 0, 0]
+        } else {
+            [0, 0, 255]
+        };
+        exporter
+            .add_frame("", Some(&cells(color)), Duration::from_secs(1) / 60)
+            .unwrap();
+    }
+    exporter.finish().unwrap();
+
+    let decoder = gif::DecodeOptions::new();
+    let mut decoder = decoder.read_info(fs::File::open(&path).unwrap()).unwrap();
+    let mut delays = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        delays.push(frame.delay);
+    }
+    fs::remove_file(&path).unwrap();
+    // The frames shown for less than 2 hundredths are dropped, the total time is kept
+    assert!(delays.iter().all(|&delay| delay >= 2), "{delays:?}");
+    assert_eq!(delays.iter().sum::<u16>(), 100);
+    assert_eq!(delays[..4], [2, 3, 2, 3]);
+}
@@ -0,0 +1,127 @@
+use image_to_console_renderer::{
+    config::LoopCount,
+    player::{FrameCache, Passes, Player, TimedFrame},
+};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+fn frames(count: usize) -> impl Iterator<Item = TimedFrame> {
+    (0..count).map(|index| TimedFrame {
+        frame: "x".repeat(10),
+        cells: None,
+        index,
+        time: Duration::from_millis(100) * index as u32,
+        duration: Duration::from_millis(100),
+        pts: None,
+    })
+}
+
+#[test]
+fn test_frame_cache() {
+    let mut cache = FrameCache::new(25);
+    let mut frames = frames(4);
+    cache.push(frames.next().unwrap());
+    cache.push(frames.next().unwrap());
+    assert_eq!((cache.first(), cache.end(), cache.size()), (0, 2, 20));
+    // The oldest frame is dropped over the limit
+    cache.push(frames.next().unwrap());
+    assert_eq!((cache.first(), cache.end(), cache.size()), (1, 3, 20));
+    assert!(cache.get(0).is_none());
+    assert_eq!(cache.get(2).map(|frame| frame.index), Some(2));
+
+    // The last frame is kept even if it is over the limit
+    let mut cache = FrameCache::new(5);
+    cache.push(frames.next().unwrap());
+    assert_eq!(cache.get(0).map(|frame| frame.index), Some(3));
+}
+
+#[test]
+fn test_player() {
+    let mut player = Player::new(frames(5), usize::MAX);
+    assert!(player.current().is_none());
+    assert!(!player.back());
+    assert!(player.advance() && player.advance());
+    assert_eq!(player.current().unwrap().index, 1);
+    assert!(player.back());
+    assert_eq!(player.current().unwrap().index, 0);
+
+    // Seeking forward plays the frames, seeking back uses the played frames
+    player.seek(Duration::from_millis(350));
+    assert_eq!(player.current().unwrap().index, 3);
+    player.seek(Duration::from_millis(150));
+    assert_eq!(player.current().unwrap().index, 1);
+    player.seek(Duration::from_secs(10));
+    assert_eq!(player.current().unwrap().index, 4);
+    assert!(!player.advance());
+
+    assert!(player.restart());
+    assert_eq!(player.current().unwrap().index, 0);
+    assert_eq!(player.cache().end(), 5);
+
+    // The first frame was dropped, the playback cannot start again
+    let mut player = Player::new(frames(3), 25);
+    while player.advance() {}
+    assert!(!player.restart());
+    assert!(player.back() && !player.back());
+}
+
+#[test]
+fn test_passes() {
+    // The passes end before the first frame
+    let mut passes = Passes::new(frames(2).chain(frames(2)));
+    assert_eq!(passes.next().map(|frame| frame.index), Some(0));
+    assert_eq!(passes.next().map(|frame| frame.index), Some(1));
+    assert!(passes.next().is_none());
+    assert_eq!(passes.next().map(|frame| frame.index), Some(0));
+    assert_eq!(passes.next().map(|frame| frame.index), Some(1));
+    assert!(passes.next().is_none() && passes.next().is_none());
+
+    // The player reads the next pass if the first frame was dropped
+    let mut player = Player::new(Passes::new(frames(3).chain(frames(3))), 25);
+    while player.advance() {}
+    assert!(player.restart());
+    assert_eq!(player.current().unwrap().index, 0);
+    assert_eq!(player.cache().end(), 4);
+    while player.advance() {}
+    assert_eq!(player.current().unwrap().index, 2);
+    assert!(!player.restart());
+}
+
+#[test]
+fn test_next_pass() {
+    // The next pass is requested only when the player reads past its first frame
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let passes = Passes::new(frames(2).chain(frames(2))).on_next_pass(move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    let mut player = Player::new(passes, 25);
+    while player.advance() {}
+    assert!(player.restart());
+    assert_eq!(requests.load(Ordering::SeqCst), 0);
+
+    // The first frame was dropped, the player reads the next pass
+    let counter = requests.clone();
+    let passes = Passes::new(frames(3).chain(frames(3))).on_next_pass(move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    let mut player = Player::new(passes, 25);
+    while player.advance() {}
+    assert!(player.restart());
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_loop_count() {
+    assert_eq!("3".parse(), Ok(LoopCount::Times(3)));
+    assert_eq!("forever".parse(), Ok(LoopCount::Forever));
+    assert!("0".parse::<LoopCount>().is_err());
+    assert!(!LoopCount::default().is_looping());
+    assert!(LoopCount::Times(2).has_next(1) && !LoopCount::Times(2).has_next(2));
+    assert!(LoopCount::Forever.has_next(u32::MAX));
+}
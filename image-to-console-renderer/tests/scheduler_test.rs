@@ -0,0 +1,102 @@
+#![cfg(feature = "gif_player")]
+
+use image_to_console_renderer::{
+    frame::FrameDelay::{Exact, Hundredths},
+    scheduler::{FrameScheduler, gif_frame_duration},
+};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_gif_frame_duration() {
+    // The delays of 0 and 1 are clamped like browsers
+    assert_eq!(
+        gif_frame_duration(Hundredths(0)),
+        Duration::from_millis(100)
+    );
+    assert_eq!(
+        gif_frame_duration(Hundredths(1)),
+        Duration::from_millis(100)
+    );
+    assert_eq!(gif_frame_duration(Hundredths(2)), Duration::from_millis(20));
+    assert_eq!(
+        gif_frame_duration(Hundredths(25)),
+        Duration::from_millis(250)
+    );
+    // The delays of the other formats are not rounded to hundredths nor clamped
+    assert_eq!(
+        gif_frame_duration(Exact(Duration::from_millis(33))),
+        Duration::from_millis(33)
+    );
+    assert_eq!(
+        gif_frame_duration(Exact(Duration::from_millis(10))),
+        Duration::from_millis(10)
+    );
+    assert_eq!(gif_frame_duration(Exact(Duration::ZERO)), Duration::ZERO);
+}
+
+#[test]
+fn test_scheduler_timing() {
+    let start = Instant::now();
+    let mut scheduler = FrameScheduler::new();
+    let frame = Duration::from_millis(20);
+    for _ in 0..5 {
+        assert!(scheduler.wait(None, frame));
+        // The time spent drawing a frame is not added to its delay
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    scheduler.finish();
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(100), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(160), "{elapsed:?}");
+    assert_eq!((scheduler.shown(), scheduler.dropped()), (5, 0));
+    assert!(scheduler.frame_rate() > 30.0 && scheduler.frame_rate() <= 50.0);
+}
+
+#[test]
+fn test_scheduler_drops_late_frames() {
+    let mut scheduler = FrameScheduler::new();
+    let frame = Duration::from_millis(10);
+    // The first frame is always shown
+    assert!(scheduler.wait(None, frame));
+    std::thread::sleep(Duration::from_millis(35));
+    // The frames due at 10ms and 20ms are over, the frame due at 30ms is shown
+    assert!(!scheduler.wait(None, frame));
+    assert!(!scheduler.wait(None, frame));
+    assert!(scheduler.wait(None, frame));
+    // The presentation time moves the clock forward
+    let time = Instant::now();
+    assert!(scheduler.wait(Some(Duration::from_millis(80)), frame));
+    assert!(time.elapsed() >= Duration::from_millis(30));
+    assert_eq!((scheduler.shown(), scheduler.dropped()), (3, 2));
+}
+
+#[test]
+fn test_scheduler_starts_at_first_frame() {
+    let mut scheduler = FrameScheduler::new();
+    let time = Instant::now();
+    // The playback starts at 10s, e.g. after a seek
+    assert!(scheduler.wait(Some(Duration::from_secs(10)), Duration::from_millis(20)));
+    assert!(scheduler.wait(
+        Some(Duration::from_millis(10020)),
+        Duration::from_millis(20)
+    ));
+    let waited = time.elapsed();
+    assert!(waited >= Duration::from_millis(15) && waited < Duration::from_millis(200));
+    // The frame rate is measured from the first frame
+    assert!(scheduler.frame_rate() > 10.0);
+}
+
+#[test]
+fn test_scheduler_sync() {
+    let mut scheduler = FrameScheduler::new();
+    // The playback is already at 1s, e.g. the position of the audio
+    scheduler.sync(Duration::from_secs(1));
+    let time = Instant::now();
+    assert!(scheduler.wait(Some(Duration::from_millis(1020)), Duration::from_millis(20)));
+    let waited = time.elapsed();
+    assert!(waited >= Duration::from_millis(15) && waited < Duration::from_millis(200));
+    // The audio is ahead of the video, the frames before its position are dropped
+    scheduler.sync(Duration::from_secs(2));
+    assert!(!scheduler.wait(Some(Duration::from_millis(1040)), Duration::from_millis(20)));
+    assert_eq!((scheduler.shown(), scheduler.dropped()), (1, 1));
+}
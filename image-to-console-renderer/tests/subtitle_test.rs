@@ -0,0 +1,94 @@
+#![cfg(feature = "video_player")]
+
+use image_to_console_core::converter::cell::CellGrid;
+use image_to_console_renderer::subtitle::{
+    SUBTITLE_LINES, SubtitleOverlay, SubtitlePosition, overlay_text,
+};
+use std::time::Duration;
+
+fn row(grid: &CellGrid, y: u32) -> String {
+    let start = (y * grid.width) as usize;
+    grid.cells[start..start + grid.width as usize]
+        .iter()
+        .map(|cell| cell.glyph)
+        .collect()
+}
+
+fn overlay(position: SubtitlePosition) -> SubtitleOverlay {
+    SubtitleOverlay::new(
+        |time| (time < Duration::from_secs(1)).then(|| "Hi\nthere".to_string()),
+        position,
+    )
+}
+
+#[test]
+fn test_overlay_text() {
+    let mut grid = CellGrid::new(10, 3);
+    overlay_text(&mut grid, "Hi\nthere");
+    assert_eq!(row(&grid, 0), " ".repeat(10));
+    assert_eq!(row(&grid, 1), "    Hi    ");
+    assert_eq!(row(&grid, 2), "  there   ");
+    assert!(grid.cells[(grid.width + 3) as usize].attrs.reverse);
+    assert!(!grid.cells[grid.width as usize].attrs.reverse);
+
+    // The lines longer than the grid are cut, the extra lines keep the last ones
+    let mut grid = CellGrid::new(4, 1);
+    overlay_text(&mut grid, "first\nsecond");
+    assert_eq!(row(&grid, 0), " sec");
+}
+
+#[test]
+fn test_lines_under() {
+    let grid = CellGrid::new(10, 3);
+    let below = overlay(SubtitlePosition::Below);
+    assert_eq!(
+        below.lines_under(Some(Duration::ZERO), Some(&grid)),
+        "\n\x1b[2K\r    Hi\n\x1b[2K\r  there"
+    );
+    // The lines are cleared when no subtitle is shown
+    let cleared = below.lines_under(Some(Duration::from_secs(2)), Some(&grid));
+    assert_eq!(
+        cleared,
+        format!("\n\x1b[2K\r{}", " ".repeat(5)).repeat(SUBTITLE_LINES)
+    );
+
+    // The overlay is drawn over the cells, or under the frames without cells
+    let over = overlay(SubtitlePosition::Overlay);
+    assert_eq!(over.lines_under(Some(Duration::ZERO), Some(&grid)), "");
+    assert_eq!(
+        over.lines_under(Some(Duration::ZERO), None),
+        "\n\x1b[2K\rHi\n\x1b[2K\rthere"
+    );
+    let mut cells = grid.clone();
+    over.draw_over(Some(Duration::ZERO), Some(&mut cells));
+    assert_eq!(row(&cells, 2), "  there   ");
+    let mut cells = grid.clone();
+    below.draw_over(Some(Duration::ZERO), Some(&mut cells));
+    assert_eq!(row(&cells, 2), " ".repeat(10));
+}
+
+#[test]
+fn test_wide_subtitle() {
+    let grid = CellGrid::new(10, 3);
+    let over = SubtitleOverlay::new(
+        |time| (time < Duration::from_secs(1)).then(|| "你好\n很长的一行字幕".to_string()),
+        SubtitlePosition::Overlay,
+    );
+    // The wide characters are not drawn in the cells, they go under the frame in columns
+    let mut cells = grid.clone();
+    over.draw_over(Some(Duration::ZERO), Some(&mut cells));
+    assert_eq!(row(&cells, 2), " ".repeat(10));
+    assert_eq!(
+        over.lines_under(Some(Duration::ZERO), Some(&grid)),
+        "\n\x1b[2K\r   你好\n\x1b[2K\r很长的一行"
+    );
+    // The lines are cleared once after the wide subtitle
+    assert_eq!(
+        over.lines_under(Some(Duration::from_secs(2)), Some(&grid)),
+        format!("\n\x1b[2K\r{}", " ".repeat(5)).repeat(SUBTITLE_LINES)
+    );
+    assert_eq!(
+        over.lines_under(Some(Duration::from_secs(2)), Some(&grid)),
+        ""
+    );
+}
@@ -7,6 +7,7 @@ use crate::{
     config::RunMode::*,
     const_value::IMAGE_EXTS,
     types::ImageType::{self, Image},
+    util::open_image,
 };
 use base64::Engine;
 use build_options::Options;
@@ -16,8 +17,13 @@ use cli::*;
 #[cfg(any(feature = "gif_player", feature = "video_player"))]
 use crossbeam_channel::{bounded, unbounded};
 use image_to_console_core::{DisplayMode, ResizeMode};
+#[cfg(feature = "gif_player")]
+use image_to_console_core::{animation::AnimationSource, gif_processor::GifAnimation};
+#[cfg(all(feature = "video_player", feature = "audio_support"))]
+use image_to_console_renderer::audio::{DecodedAudio, VideoAudio};
 #[cfg(feature = "audio_support")]
 use image_to_console_renderer::audio_path::AudioPath;
+use image_to_console_renderer::config::OutputFormat;
 use rayon::{iter::ParallelIterator, prelude::ParallelBridge};
 use std::{io::Read, path::Path};
 
@@ -25,10 +31,12 @@ use std::{io::Read, path::Path};
 #[derive(Debug, Clone, Options, Default)]
 pub struct Config {
     pub clear: bool,
+    pub alternate_screen: bool,
+    pub interactive: bool,
     pub pause: bool,
     pub center: bool,
     pub no_color: bool,
-    pub loop_play: bool,
+    pub loop_count: image_to_console_renderer::config::LoopCount,
     pub show_time: bool,
     pub fps: Option<u64>,
     pub mode: DisplayMode,
@@ -42,6 +50,11 @@ pub struct Config {
     pub audio: AudioPath,
     pub black_background: bool,
     pub output: Option<String>,
+    pub output_format: OutputFormat,
+    pub record: Option<String>,
+    pub record_only: bool,
+    /// The cell size of the png and gif output
+    pub cell_size: (u32, u32),
     pub resize_mode: ResizeMode,
     pub enable_compression: bool,
     pub file_name: Option<String>,
@@ -49,18 +62,45 @@ pub struct Config {
     pub max_colors: u16,
     #[cfg(feature = "sixel_support")]
     pub color_space: crate::types::ColorSpace,
+    #[cfg(feature = "sixel_support")]
+    pub transparent: bool,
+    #[cfg(feature = "sixel_support")]
+    pub global_palette: Option<u32>,
+    #[cfg(feature = "sixel_support")]
+    pub sixel_palette: Option<std::sync::Arc<image_to_console_core::indexed_image::SixelPalette>>,
     #[cfg(feature = "video_player")]
     pub flush_interval: crate::types::FlushInterval,
+    /// The time in the video where the playback starts
+    #[cfg(feature = "video_player")]
+    pub start: std::time::Duration,
+    /// The playback speed of a video, set with the video
+    #[cfg(feature = "video_player")]
+    pub speed: f32,
+    /// The subtitles shown with a video
+    #[cfg(feature = "video_player")]
+    pub subtitles: Option<crate::types::VideoSubtitles>,
+    #[cfg(feature = "video_player")]
+    pub subtitle_position: image_to_console_renderer::subtitle::SubtitlePosition,
+    /// Keep the cells of the frames, so the player redraws only the changed cells
+    pub cell_grid: bool,
 }
 
 impl From<&Cli> for Config {
     fn from(cli: &Cli) -> Self {
+        // HTML and SVG are made from the cells of the image
+        let output_format = cli.output_format.resolve(cli.output.as_deref());
         Self {
             clear: cli.clear,
+            alternate_screen: cli.alternate_screen,
+            interactive: cli.interactive,
             center: cli.center,
             no_color: cli.no_color,
             show_time: cli.show_time,
             output: cli.output.clone(),
+            output_format,
+            record: cli.record.clone(),
+            record_only: cli.record_only && cli.record.is_some(),
+            cell_size: (cli.cell_width, cli.cell_height),
             resize_mode: ResizeMode::from(cli),
             full_resolution: !cli.half_resolution,
             black_background: cli.black_background,
@@ -80,11 +120,23 @@ impl From<&Cli> for Config {
             disable_dither: cli.disable_dither,
             #[cfg(feature = "sixel_support")]
             color_space: cli.color_space,
+            #[cfg(feature = "sixel_support")]
+            transparent: cli.transparent,
+            #[cfg(feature = "sixel_support")]
+            global_palette: cli.global_palette,
+            cell_grid: output_format.needs_cells(),
             ..Default::default()
         }
     }
 }
 
+impl Config {
+    /// Check whether the animation is exported instead of played
+    pub fn is_exporting(&self) -> bool {
+        self.output.is_some() && self.output_format.is_animation()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum RunMode {
     Once(Result<(ImageType, Config), String>),
@@ -99,6 +151,7 @@ pub fn parse2(cli: Cli) -> RunMode {
     #[cfg(any(feature = "video_player", feature = "gif_player"))]
     let cli2 = cli.clone();
     let output_base = cli.output.clone();
+    let output_format = cli.output_format.resolve(None);
     match cli.command {
         Commands::File(ref args) => {
             let path = Path::new(&args.path);
@@ -108,7 +161,26 @@ pub fn parse2(cli: Cli) -> RunMode {
             if !path.is_file() {
                 return Once(Err("Path is not a file".to_string()));
             }
-            let img = image::open(&args.path).expect("Failed to open image");
+            // The animated images are played like the gif subcommand, unless a still image is written
+            #[cfg(feature = "gif_player")]
+            if (cli.output.is_none()
+                || cli
+                    .output_format
+                    .resolve(cli.output.as_deref())
+                    .is_animation())
+                && let Ok(Some(animation)) = image_to_console_core::animation::open_animation(path)
+            {
+                use crate::types::ImageType::Gif;
+                let loop_count = animation.loop_count().into();
+                let path = path.to_path_buf();
+                let rx = decode_animation(
+                    move || image_to_console_core::animation::open_animation(&path),
+                    loop_count,
+                );
+                let config = Config::from(&cli2).loop_count(loop_count).get_options();
+                return Video(Ok((Gif(rx), config)));
+            }
+            let img = open_image(&args.path).expect("Failed to open image");
             let config = Config::from(&cli)
                 .file_name(Some(
                     path.file_name().unwrap().to_string_lossy().to_string(),
@@ -161,12 +233,18 @@ pub fn parse2(cli: Cli) -> RunMode {
                                         }
                                     };
                                     let img = if args.read_all {
-                                        Image(image::open(&path).unwrap())
+                                        Image(open_image(&path).unwrap())
                                     } else {
                                         ImageType::Path(path.to_str().unwrap().to_string())
                                     };
                                     let config = Config::from(&cli)
-                                        .output(Some(output.to_str().unwrap().to_string() + ".txt"))
+                                        .output(Some(format!(
+                                            "{}.{}",
+                                            output.to_str().unwrap(),
+                                            output_format.extension()
+                                        )))
+                                        .output_format(output_format)
+                                        .cell_grid(output_format.needs_cells())
                                         .show_file_name(false)
                                         .get_options();
                                     Some(Ok((img, config)))
@@ -184,56 +262,44 @@ pub fn parse2(cli: Cli) -> RunMode {
             Multiple(configs)
         }
         #[cfg(feature = "gif_player")]
-        Commands::Gif(args) => match std::fs::File::open(&args.path) {
-            Ok(file) => {
+        Commands::Gif(args) => match GifAnimation::open(&args.path) {
+            Ok(animation) => {
                 use crate::types::ImageType::Gif;
-                use image::DynamicImage;
-                use image_to_console_core::gif_processor::GifFrameProcessor;
-                let mut decoder = gif::DecodeOptions::new();
-                decoder.set_color_output(gif::ColorOutput::Indexed);
-                match decoder.read_info(file) {
-                    Ok(mut decoder) => {
-                        let (tx, rx) = bounded::<Result<(DynamicImage, usize, u16), String>>(6);
-                        std::thread::spawn(move || {
-                            let mut index: usize = 0;
-                            let mut gif_processor = GifFrameProcessor::new(
-                                decoder.width() as u32,
-                                decoder.height() as u32,
-                                decoder.global_palette().map(|p| p.to_vec()),
-                            );
-                            loop {
-                                match decoder.read_next_frame() {
-                                    Ok(frame) => match frame {
-                                        Some(frame) => {
-                                            let img = gif_processor.process_frame(frame);
-                                            tx.send(Ok((img, index, frame.delay))).unwrap();
-                                            index += 1;
-                                        }
-                                        None => {
-                                            break;
-                                        }
-                                    },
-                                    Err(err) => tx.send(Err(err.to_string())).unwrap(),
-                                }
-                            }
-                        });
-                        #[allow(unused_mut)]
-                        let mut config = Config::from(&cli2)
-                            .fps(args.fps)
-                            .loop_play(args.loop_play)
-                            .get_options();
-                        #[cfg(feature = "audio_support")]
-                        {
-                            config.audio = args
-                                .audio
-                                .map(|path| AudioPath::Custom(Path::new(&path).to_path_buf()))
-                                .unwrap_or_default();
-                        }
-
-                        Video(Ok((Gif(rx), config)))
-                    }
-                    Err(err) => Once(Err(err.to_string())),
+                // Follow the loop count of the file unless it is set
+                let loop_count = args
+                    .loop_play
+                    .unwrap_or_else(|| animation.loop_count().into());
+                #[cfg(feature = "sixel_support")]
+                let global_palette = animation.global_palette().map(|p| p.to_vec());
+                let path = args.path.clone();
+                let rx = decode_animation(
+                    move || Ok(Some(Box::new(GifAnimation::open(&path)?) as _)),
+                    loop_count,
+                );
+                #[allow(unused_mut)]
+                let mut config = Config::from(&cli2)
+                    .fps(args.fps)
+                    .loop_count(loop_count)
+                    .get_options();
+                #[cfg(feature = "audio_support")]
+                {
+                    config.audio = args
+                        .audio
+                        .map(|path| AudioPath::Custom(Path::new(&path).to_path_buf()))
+                        .unwrap_or_default();
                 }
+                // Prefer the global palette of the GIF file to the first frames
+                #[cfg(feature = "sixel_support")]
+                if config.global_palette.is_some() && config.mode.is_sixel() {
+                    use image_to_console_core::indexed_image::SixelPalette;
+                    config.sixel_palette = global_palette
+                        .and_then(|palette| {
+                            SixelPalette::from_gif_palette(&palette, config.max_colors).ok()
+                        })
+                        .map(std::sync::Arc::new);
+                }
+
+                Video(Ok((Gif(rx), config)))
             }
             Err(err) => Once(Err(err.to_string())),
         },
@@ -308,40 +374,35 @@ pub fn parse2(cli: Cli) -> RunMode {
         }
         #[cfg(feature = "video_player")]
         Commands::Video(args) => {
+            let start = args.start.unwrap_or_default().0;
+            let end = args
+                .end
+                .map(|end| end.0)
+                .or(args.duration.map(|duration| start + duration.0));
+            if end.is_some_and(|end| end <= start) {
+                return Video(Err(
+                    "The end of the playback must be after the start".to_string()
+                ));
+            }
+            // Read the subtitles before the playback, so a bad file is reported at once
+            let subtitles = match load_subtitles(&args) {
+                Ok(subtitles) => subtitles,
+                Err(e) => return Video(Err(format!("Failed to load the subtitles: {e}"))),
+            };
+            let embedded = subtitles.clone();
+            // The recording without a terminal and the export do not play the audio, its packets
+            // would be queued by the demuxer for the whole file
+            #[cfg(feature = "audio_support")]
+            let silent = cli2.output.is_some() || (cli2.record_only && cli2.record.is_some());
+            let subtitle_position = args.subs_position.into();
             let (etx, erx) = bounded(1);
 
             // decode the audio and video in another thread
             std::thread::spawn(move || {
                 etx.send(Ok(Starting)).unwrap();
-                #[cfg(feature = "audio_support")]
-                let audio_path = if args.audio.is_none() {
-                    let function = || {
-                        // First, extract the audio to temp folder
-                        let path = Path::new(&args.path);
-                        let audio_path = std::env::temp_dir().join(
-                            path.file_stem().unwrap().to_str().unwrap().to_owned() + "-tmp.aac",
-                        );
-                        crate::util::pick_audio(path, &audio_path)?;
-                        Ok::<std::path::PathBuf, ffmpeg_next::error::Error>(audio_path)
-                    };
-                    function().map(AudioPath::Temp).unwrap_or_default()
-                } else if args
-                    .audio
-                    .as_ref()
-                    .map(|s| s.to_lowercase() == "none")
-                    .unwrap_or_default()
-                {
-                    AudioPath::None
-                } else {
-                    AudioPath::Custom(Path::new(&args.audio.unwrap()).to_path_buf())
-                };
-                // And then, extract the video
-                // We will to use ffmpeg-next lib to do this
                 use crate::{errors::FrameError, types::VideoEvent::*};
 
-                // create frame channel
-
-                // Open the video file
+                // Open the video file, the audio and the video are decoded from its demuxer
                 let mut video = match video_decoder::open(&args.path) {
                     Ok(v) => v,
                     Err(err) => {
@@ -349,14 +410,57 @@ pub fn parse2(cli: Cli) -> RunMode {
                         return;
                     }
                 };
-                let decoder = match video.video_decoder() {
+                // Seek before the decoders are created, so both of them start at the time
+                if !start.is_zero()
+                    && let Err(err) = video.seek(start)
+                {
+                    etx.send(Err(format!(
+                        "Failed to seek to {}: {err}",
+                        crate::types::VideoTime(start)
+                    )))
+                    .unwrap();
+                    return;
+                }
+                // The decoder of the subtitles gets the packets the other decoders read past,
+                // so it is created before them
+                if let Some(crate::types::VideoSubtitles::Embedded { index, decoder }) = &embedded {
+                    match video.subtitle_decoder(Some(*index)) {
+                        Ok(subtitles) => {
+                            *decoder.lock().unwrap_or_else(|e| e.into_inner()) = Some(subtitles)
+                        }
+                        Err(err) => {
+                            etx.send(Err(format!("Failed to load the subtitles: {err}")))
+                                .unwrap();
+                            return;
+                        }
+                    }
+                }
+                let mut decoder = match video.video_decoder() {
                     Ok(d) => d,
                     Err(err) => {
                         etx.send(Err(err.to_string())).unwrap();
                         return;
                     }
                 };
-                let (vtx, vrx) = bounded(decoder.frame_rate().max(3f32).ceil() as usize);
+                // The position of the audio, the late frames are dropped by the decoding
+                #[cfg(feature = "audio_support")]
+                let pos = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+                #[cfg(feature = "audio_support")]
+                let audio = match args.audio.as_deref() {
+                    _ if silent => VideoAudio::None,
+                    Some(path) if path.eq_ignore_ascii_case("none") => VideoAudio::None,
+                    Some(path) => VideoAudio::File(Path::new(path).to_path_buf()),
+                    // A video without an audio stream is played without audio
+                    None => video
+                        .audio_decoder()
+                        .map(|mut audio| {
+                            audio.end(end);
+                            decode_audio(audio, pos.clone())
+                        })
+                        .unwrap_or_default(),
+                };
+                let frame_rate = decoder.frame_rate();
+                let (vtx, vrx) = bounded(frame_rate.max(3f32).ceil() as usize);
 
                 #[cfg(target_os = "linux")]
                 {
@@ -370,22 +474,33 @@ pub fn parse2(cli: Cli) -> RunMode {
                     shm.set_max_len(len);
                 }
 
-                #[cfg(feature = "audio_support")]
-                let pos = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+                let (ftx, frx) = bounded(1);
                 // tell the channel
                 #[cfg(not(feature = "audio_support"))]
-                etx.send(Ok(Initialized((vrx, decoder.frame_rate()))))
-                    .unwrap();
+                etx.send(Ok(Initialized((
+                    vrx,
+                    decoder.frame_rate(),
+                    decoder.size(),
+                    ftx,
+                ))))
+                .unwrap();
                 #[cfg(feature = "audio_support")]
                 etx.send(Ok(Initialized((
                     vrx,
-                    audio_path,
+                    audio,
                     decoder.frame_rate(),
                     pos.clone(),
+                    decoder.size(),
+                    ftx,
                 ))))
                 .unwrap();
                 std::thread::scope(|s| {
                     s.spawn(|| {
+                        // Scale and convert the frames in swscale, so the processor does not
+                        // have to. The frames are left as they are if the player gives up.
+                        if let Ok((size, format)) = frx.recv() {
+                            decoder.scale(size).pixel_format(format);
+                        }
                         #[cfg(not(feature = "audio_support"))]
                         let frames = decoder.frames();
                         #[cfg(feature = "audio_support")]
@@ -400,8 +515,24 @@ pub fn parse2(cli: Cli) -> RunMode {
                         for (index, frame) in frames.enumerate() {
                             match frame {
                                 Ok(frame) => {
-                                    vtx.send(Ok((frame.frame.into(), index, frame.pts)))
-                                        .unwrap();
+                                    // The time of a frame without a timestamp is counted from the start
+                                    let time = frame.pts.or_else(|| {
+                                        std::time::Duration::try_from_secs_f32(
+                                            index as f32 / frame_rate,
+                                        )
+                                        .ok()
+                                        .map(|time| start + time)
+                                    });
+                                    if end.zip(time).is_some_and(|(end, time)| time >= end) {
+                                        break;
+                                    }
+                                    // The playback was stopped
+                                    if vtx
+                                        .send(Ok((frame.frame.into(), index, frame.pts)))
+                                        .is_err()
+                                    {
+                                        return;
+                                    }
                                 }
                                 // Other errors
                                 Err(err) => {
@@ -419,9 +550,18 @@ pub fn parse2(cli: Cli) -> RunMode {
                 ImageType::Video(erx),
                 Config::from(&cli2)
                     .flush_interval(args.flush_interval)
+                    .start(start)
+                    .speed(args.speed)
+                    .subtitles(subtitles)
+                    .subtitle_position(subtitle_position)
                     .get_options(),
             )))
         }
+        #[cfg(feature = "video_player")]
+        Commands::Probe(ref args) => match crate::probe::probe(args) {
+            Ok(()) => std::process::exit(0),
+            Err(e) => Error(e),
+        },
         #[cfg(feature = "dot_file")]
         Commands::DotFile(args) => {
             use cli::DotFileSubcommands::*;
@@ -535,3 +675,142 @@ pub fn parse() -> RunMode {
     let cli = Cli::parse();
     parse2(cli)
 }
+
+/// Decode the frames of an animation on another thread
+///
+/// The animation is opened again for each pass when it loops, but only after the player requests
+/// the next pass, which it does if the frames of the first pass are not kept. The first frame
+/// of the pass is not sent again, the player has it to find the end of the pass.
+///
+/// # Arguments
+///
+/// * `open` - Open the animation, it is called on the decoding thread
+/// * `loop_count` - How many times the animation is played
+///
+/// # Returns
+///
+/// Returns the receiver of the frames and the sender of the requests for the next passes
+#[cfg(feature = "gif_player")]
+fn decode_animation(
+    open: impl Fn() -> image::ImageResult<Option<Box<dyn AnimationSource>>> + Send + 'static,
+    loop_count: image_to_console_renderer::config::LoopCount,
+) -> crate::types::GifType {
+    use crate::types::AnimationEvent::{Frame, PassEnd};
+    use image_to_console_renderer::frame::FrameDelay;
+    let (tx, rx) = bounded(6);
+    let (next_pass, requests) = bounded(1);
+    std::thread::spawn(move || {
+        let mut pass = 0;
+        loop {
+            let mut animation = match open() {
+                Ok(Some(animation)) => animation,
+                Ok(None) => return,
+                Err(err) => {
+                    let _ = tx.send(Err(err.to_string()));
+                    return;
+                }
+            };
+            let mut index: usize = 0;
+            // Only the GIF delays are in hundredths of a second
+            let gif = animation.is_gif();
+            while let Some(frame) = animation.next_frame() {
+                let frame = frame.map(|(image, delay)| {
+                    let delay = match gif {
+                        true => FrameDelay::Hundredths(
+                            ((delay.as_millis() + 5) / 10).min(u16::MAX as u128) as u16,
+                        ),
+                        false => FrameDelay::Exact(delay),
+                    };
+                    Frame(image::DynamicImage::ImageRgba8(image), index, delay)
+                });
+                let failed = frame.is_err();
+                // The player kept the first frame of the first pass
+                if pass > 0 && index == 0 && !failed {
+                    index += 1;
+                    continue;
+                }
+                // The playback was stopped
+                if tx.send(frame.map_err(|err| err.to_string())).is_err() || failed {
+                    return;
+                }
+                index += 1;
+            }
+            if !loop_count.is_looping() || index == 0 {
+                return;
+            }
+            // The playback was stopped or it did not need the next pass
+            if tx.send(Ok(PassEnd)).is_err() || requests.recv().is_err() {
+                return;
+            }
+            pass += 1;
+        }
+    });
+    crate::types::GifType {
+        frames: rx,
+        next_pass,
+    }
+}
+
+/// Load the subtitles chosen by the args of the video subcommand
+///
+/// `--subs-stream` picks an embedded stream by itself, `--subs embedded` picks the best one. A
+/// subtitle file is read at once, an embedded stream is only looked up, its cues are decoded
+/// from the demuxer of the playback as the video is played.
+///
+/// # Returns
+///
+/// Returns the subtitles, `None` if they are not shown
+#[cfg(feature = "video_player")]
+fn load_subtitles(args: &VideoArgs) -> Result<Option<crate::types::VideoSubtitles>, String> {
+    use crate::types::VideoSubtitles;
+    match (args.subs.as_deref(), args.subs_stream) {
+        (None, None) => Ok(None),
+        (Some(subs), None) if !subs.eq_ignore_ascii_case("embedded") => {
+            let subtitles = video_decoder::Subtitles::open(subs).map_err(|e| e.to_string())?;
+            if subtitles.is_empty() {
+                return Err("no text subtitle was found".to_string());
+            }
+            Ok(Some(VideoSubtitles::File(std::sync::Arc::new(subtitles))))
+        }
+        (_, index) => {
+            let index = video_decoder::open(&args.path)
+                .and_then(|video| video.subtitle_stream(index))
+                .map_err(|e| e.to_string())?;
+            Ok(Some(VideoSubtitles::Embedded {
+                index,
+                decoder: Default::default(),
+            }))
+        }
+    }
+}
+
+/// Decode the audio of a video on its own thread, ahead of the playback
+///
+/// # Arguments
+///
+/// * `decoder` - The decoder of the audio stream
+/// * `clock` - The clock of the playback, set to the position of the audio
+#[cfg(all(feature = "video_player", feature = "audio_support"))]
+fn decode_audio(
+    decoder: video_decoder::AudioDecoder,
+    clock: std::sync::Arc<std::sync::atomic::AtomicU64>,
+) -> VideoAudio {
+    let (channels, sample_rate) = (decoder.channels(), decoder.sample_rate());
+    // About a second of audio
+    let (tx, rx) = bounded(48);
+    std::thread::spawn(move || {
+        // A broken audio stream ends the audio, the video goes on
+        for frame in decoder.map_while(Result::ok) {
+            // The playback was stopped
+            if tx.send((frame.pts, frame.samples)).is_err() {
+                return;
+            }
+        }
+    });
+    VideoAudio::Decoded(DecodedAudio::new(
+        rx.into_iter(),
+        channels,
+        sample_rate,
+        clock,
+    ))
+}
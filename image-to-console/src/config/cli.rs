@@ -1,4 +1,4 @@
-use crate::types::ClapResizeMode;
+use crate::types::{ClapOutputFormat, ClapResizeMode};
 use clap::{
     Parser, Subcommand,
     builder::{
@@ -29,6 +29,14 @@ pub struct Cli {
     #[clap(long, default_value_t = false)]
     pub clear: bool,
 
+    /// Play in the alternate screen, the terminal is restored at the end (Only run in gif and video)
+    #[clap(long, default_value_t = false)]
+    pub alternate_screen: bool,
+
+    /// Control the playback with the keyboard, the terminal is in raw mode while playing (Only run in gif and video)
+    #[clap(long, default_value_t = false)]
+    pub interactive: bool,
+
     /// Pause at the edn
     #[clap(long, default_value_t = false)]
     pub pause: bool,
@@ -41,6 +49,26 @@ pub struct Cli {
     #[clap(short, long)]
     pub output: Option<String>,
 
+    /// Output file format, auto follows the extension (.html, .svg, .png, .gif, a directory of frames if it ends with /, otherwise text)
+    #[clap(long, default_value = "auto")]
+    pub output_format: ClapOutputFormat,
+
+    /// Record the playback to an asciicast v2 file (Only run in gif and video)
+    #[clap(long)]
+    pub record: Option<String>,
+
+    /// Only record the playback, without printing the frames or waiting between them (Only run with --record)
+    #[clap(long, default_value_t = false, requires = "record")]
+    pub record_only: bool,
+
+    /// Width of a cell in pixels (Only run in the png and gif output)
+    #[clap(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+    pub cell_width: u32,
+
+    /// Height of a cell in pixels (Only run in the png and gif output)
+    #[clap(long, default_value_t = 16, value_parser = clap::value_parser!(u32).range(1..))]
+    pub cell_height: u32,
+
     /// Operates at half resolution
     #[clap(long, default_value_t = false)]
     pub half_resolution: bool,
@@ -108,6 +136,16 @@ pub struct Cli {
     #[clap(long, default_value = "srgb")]
     pub color_space: crate::types::ColorSpace,
 
+    #[cfg(feature = "sixel_support")]
+    /// Keep the transparent pixels unpainted (Only run in sixel protocol)
+    #[clap(long, default_value_t = false)]
+    pub transparent: bool,
+
+    #[cfg(feature = "sixel_support")]
+    /// Reuse one palette for every frame, built from the GIF global palette or the first N frames (Only run in sixel protocol)
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub global_palette: Option<u32>,
+
     #[clap(subcommand)]
     pub command: Commands,
 }
@@ -138,6 +176,10 @@ pub enum Commands {
     /// Load a video from a file
     Video(VideoArgs),
 
+    #[cfg(feature = "video_player")]
+    /// Print the metadata of a video file
+    Probe(ProbeArgs),
+
     #[cfg(feature = "dot_file")]
     /// dot-file command
     DotFile(DotFileArgs),
@@ -167,12 +209,18 @@ pub struct DirectoryArgs {
 #[derive(Clone, Parser)]
 pub struct GifArgs {
     /// Set the frames per second for gif playback
-    #[clap(long)]
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
     pub fps: Option<u64>,
 
-    /// Loop the gif playback
-    #[clap(long = "loop", default_value_t = false)]
-    pub loop_play: bool,
+    /// Loop the gif playback, `--loop=N` plays it N times (the loop count of the gif file by default)
+    #[clap(
+        long = "loop",
+        value_name = "N|forever",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "forever"
+    )]
+    pub loop_play: Option<image_to_console_renderer::config::LoopCount>,
 
     /// Audio file path
     #[cfg(feature = "audio_support")]
@@ -207,10 +255,58 @@ pub struct VideoArgs {
     #[clap(long)]
     pub audio: Option<String>,
 
+    /// Start the playback at a time, in seconds, `M:S` or `H:M:S`
+    #[clap(long, value_name = "TIME")]
+    pub start: Option<crate::types::VideoTime>,
+
+    /// Stop the playback at a time, in seconds, `M:S` or `H:M:S`
+    #[clap(long, value_name = "TIME")]
+    pub end: Option<crate::types::VideoTime>,
+
+    /// Stop the playback after a duration from the start, in seconds, `M:S` or `H:M:S`
+    #[clap(long, value_name = "TIME", conflicts_with = "end")]
+    pub duration: Option<crate::types::VideoTime>,
+
+    /// Set the playback speed, e.g. `2` plays twice as fast
+    #[clap(long, default_value_t = 1.0, value_parser = parse_speed)]
+    pub speed: f32,
+
+    /// Show subtitles, from a `.srt` or `.vtt` file or `embedded` for a subtitle stream of the video
+    #[clap(long, value_name = "FILE|embedded")]
+    pub subs: Option<String>,
+
+    /// Show the embedded subtitle stream with an index, as listed by `probe`
+    #[clap(long, value_name = "INDEX")]
+    pub subs_stream: Option<usize>,
+
+    /// Where the subtitles are drawn
+    #[clap(long, default_value = "below")]
+    pub subs_position: crate::types::ClapSubtitlePosition,
+
     /// Path to the video
     pub path: String,
 }
 
+#[cfg(feature = "video_player")]
+#[derive(Clone, Parser)]
+pub struct ProbeArgs {
+    /// Print the metadata as JSON
+    #[clap(long, default_value_t = false)]
+    pub json: bool,
+
+    /// Path to the video
+    pub path: String,
+}
+
+/// Parse a playback speed, a positive number
+#[cfg(feature = "video_player")]
+pub fn parse_speed(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        _ => Err(format!("invalid speed `{s}`, expected a positive number")),
+    }
+}
+
 #[cfg(feature = "dot_file")]
 #[derive(Clone, Subcommand)]
 pub enum DotFileSubcommands {
@@ -283,7 +379,14 @@ impl Default for Cli {
             width: None,
             height: None,
             output: None,
+            output_format: ClapOutputFormat::Auto,
+            record: None,
+            record_only: false,
+            cell_width: 8,
+            cell_height: 16,
             clear: false,
+            alternate_screen: false,
+            interactive: false,
             pause: false,
             center: false,
             no_color: false,
@@ -308,6 +411,10 @@ impl Default for Cli {
             disable_dither: false,
             #[cfg(feature = "sixel_support")]
             color_space: Default::default(),
+            #[cfg(feature = "sixel_support")]
+            transparent: false,
+            #[cfg(feature = "sixel_support")]
+            global_palette: None,
         }
     }
 }
@@ -72,6 +72,52 @@ impl From<ClapResizeMode> for crate::types::ClapResizeMode {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, Schema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Auto,
+    Text,
+    Html,
+    Svg,
+    Png,
+    Gif,
+    Frames,
+}
+
+impl From<OutputFormat> for crate::types::ClapOutputFormat {
+    fn from(value: OutputFormat) -> Self {
+        match value {
+            OutputFormat::Auto => Self::Auto,
+            OutputFormat::Text => Self::Text,
+            OutputFormat::Html => Self::Html,
+            OutputFormat::Svg => Self::Svg,
+            OutputFormat::Png => Self::Png,
+            OutputFormat::Gif => Self::Gif,
+            OutputFormat::Frames => Self::Frames,
+        }
+    }
+}
+
+#[cfg(feature = "video_player")]
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, Schema)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitlePosition {
+    #[default]
+    Below,
+    Overlay,
+}
+
+#[cfg(feature = "video_player")]
+impl From<SubtitlePosition> for crate::types::ClapSubtitlePosition {
+    fn from(value: SubtitlePosition) -> Self {
+        match value {
+            SubtitlePosition::Below => Self::Below,
+            SubtitlePosition::Overlay => Self::Overlay,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, Schema)]
 #[serde(rename_all = "lowercase")]
 pub enum ColorSpace {
@@ -123,15 +169,66 @@ pub struct GifArgs {
     #[serde(default)]
     pub fps: Option<u64>,
 
-    /// Loop the GIF playback
+    /// Loop the GIF playback, `true` or `"forever"` loops forever and a number plays it that many
+    /// times, otherwise the loop count of the GIF file is used
     #[serde(default)]
-    pub loop_play: bool,
+    pub loop_play: LoopPlay,
 
     /// Audio file path
     #[serde(default)]
     pub audio: Option<String>,
 }
 
+/// The loop count of the GIF playback, `None` uses the loop count of the GIF file
+#[cfg(feature = "gif_player")]
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(try_from = "LoopPlayValue", into = "LoopPlayValue")]
+pub struct LoopPlay(pub Option<image_to_console_renderer::config::LoopCount>);
+
+/// The values of `loop-play` in the dot-file
+#[cfg(feature = "gif_player")]
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum LoopPlayValue {
+    Enabled(bool),
+    Times(u32),
+    Count(String),
+}
+
+#[cfg(feature = "gif_player")]
+impl TryFrom<LoopPlayValue> for LoopPlay {
+    type Error = String;
+
+    fn try_from(value: LoopPlayValue) -> Result<Self, Self::Error> {
+        use image_to_console_renderer::config::LoopCount;
+        match value {
+            LoopPlayValue::Enabled(true) => Ok(Self(Some(LoopCount::Forever))),
+            LoopPlayValue::Enabled(false) => Ok(Self(None)),
+            LoopPlayValue::Times(times) => times.to_string().parse().map(|count| Self(Some(count))),
+            LoopPlayValue::Count(count) => count.parse().map(|count| Self(Some(count))),
+        }
+    }
+}
+
+#[cfg(feature = "gif_player")]
+impl From<LoopPlay> for LoopPlayValue {
+    fn from(value: LoopPlay) -> Self {
+        use image_to_console_renderer::config::LoopCount;
+        match value.0 {
+            None => Self::Enabled(false),
+            Some(LoopCount::Forever) => Self::Enabled(true),
+            Some(LoopCount::Times(times)) => Self::Times(times),
+        }
+    }
+}
+
+#[cfg(feature = "gif_player")]
+impl summon_schema::ToSchema for LoopPlay {
+    fn schema_type() -> serde_json::Value {
+        serde_json::json!(["boolean", "integer", "string"])
+    }
+}
+
 #[cfg(feature = "video_player")]
 fn from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
@@ -166,7 +263,7 @@ where
 
 /// Video type args
 #[cfg(feature = "video_player")]
-#[derive(Debug, Clone, Default, Deserialize, Serialize, Schema)]
+#[derive(Debug, Clone, Deserialize, Serialize, Schema)]
 #[serde(rename_all = "kebab-case")]
 pub struct VideoArgs {
     /// Set flush interval
@@ -177,12 +274,112 @@ pub struct VideoArgs {
     /// Audio file path
     #[serde(default)]
     pub audio: Option<String>,
+
+    /// Start the playback at a time, in seconds, `M:S` or `H:M:S`
+    #[serde(default)]
+    pub start: Option<crate::types::VideoTime>,
+
+    /// Stop the playback at a time, in seconds, `M:S` or `H:M:S`
+    #[serde(default)]
+    pub end: Option<crate::types::VideoTime>,
+
+    /// Stop the playback after a duration from the start, in seconds, `M:S` or `H:M:S`
+    #[serde(default)]
+    pub duration: Option<crate::types::VideoTime>,
+
+    /// Set the playback speed, e.g. `2` plays twice as fast
+    #[serde(default = "default_speed", deserialize_with = "deserialize_speed")]
+    pub speed: f32,
+
+    /// Show subtitles, from a `.srt` or `.vtt` file or `embedded` for a subtitle stream of the video
+    #[serde(default)]
+    pub subs: Option<String>,
+
+    /// Show the embedded subtitle stream with an index, as listed by `probe`
+    #[serde(default)]
+    pub subs_stream: Option<usize>,
+
+    /// Where the subtitles are drawn
+    #[serde(default)]
+    pub subs_position: SubtitlePosition,
+}
+
+#[cfg(feature = "video_player")]
+impl Default for VideoArgs {
+    fn default() -> Self {
+        Self {
+            flush_interval: Default::default(),
+            audio: None,
+            start: None,
+            end: None,
+            duration: None,
+            speed: default_speed(),
+            subs: None,
+            subs_stream: None,
+            subs_position: Default::default(),
+        }
+    }
+}
+
+#[cfg(feature = "video_player")]
+fn default_speed() -> f32 {
+    1.0
+}
+
+#[cfg(feature = "video_player")]
+fn deserialize_speed<'de, D>(deserializer: D) -> Result<f32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = f32::deserialize(deserializer)?;
+    if value.is_finite() && value > 0.0 {
+        Ok(value)
+    } else {
+        Err(serde::de::Error::custom(format!(
+            "speed must be a positive number, got {value}"
+        )))
+    }
+}
+
+#[cfg(feature = "video_player")]
+impl<'de> Deserialize<'de> for crate::types::VideoTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        from_str(deserializer)
+    }
+}
+
+#[cfg(feature = "video_player")]
+impl Serialize for crate::types::VideoTime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "video_player")]
+impl summon_schema::ToSchema for crate::types::VideoTime {
+    fn schema_type() -> Value {
+        serde_json::json!("string")
+    }
 }
 
 fn default_max_colors() -> u16 {
     256
 }
 
+fn default_cell_width() -> u32 {
+    8
+}
+
+fn default_cell_height() -> u32 {
+    16
+}
+
 fn deserialize_max_colors<'de, D>(deserializer: D) -> Result<u16, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -218,6 +415,14 @@ pub struct DotFileContent {
     #[serde(default)]
     pub clear: bool,
 
+    /// Play in the alternate screen, the terminal is restored at the end (Only run in gif and video)
+    #[serde(default)]
+    pub alternate_screen: bool,
+
+    /// Control the playback with the keyboard, the terminal is in raw mode while playing (Only run in gif and video)
+    #[serde(default)]
+    pub interactive: bool,
+
     /// Pause at end
     #[serde(default)]
     pub pause: bool,
@@ -230,6 +435,28 @@ pub struct DotFileContent {
     #[serde(default)]
     pub output: Option<String>,
 
+    /// Output file format, auto follows the extension (.html, .svg, .png, otherwise text)
+    #[serde(default)]
+    pub output_format: OutputFormat,
+
+    /// Record the playback to an asciicast v2 file (Only run in gif and video)
+    #[serde(default)]
+    pub record: Option<String>,
+
+    /// Only record the playback, without printing the frames or waiting between them (Only run with record)
+    #[serde(default)]
+    pub record_only: bool,
+
+    /// Width of a cell in pixels (Only run in the png and gif output)
+    #[schema(minimum = 1)]
+    #[serde(default = "default_cell_width")]
+    pub cell_width: u32,
+
+    /// Height of a cell in pixels (Only run in the png and gif output)
+    #[schema(minimum = 1)]
+    #[serde(default = "default_cell_height")]
+    pub cell_height: u32,
+
     /// Operates at half resolution
     #[serde(default)]
     pub half_resolution: bool,
@@ -301,6 +528,16 @@ pub struct DotFileContent {
     #[cfg(feature = "sixel_support")]
     pub color_space: ColorSpace,
 
+    /// Keep the transparent pixels unpainted (Only run in sixel protocol)
+    #[serde(default)]
+    #[cfg(feature = "sixel_support")]
+    pub transparent: bool,
+
+    /// Reuse one palette for every frame, built from the GIF global palette or the first N frames (Only run in sixel protocol)
+    #[serde(default)]
+    #[cfg(feature = "sixel_support")]
+    pub global_palette: Option<u32>,
+
     #[serde(default)]
     pub file: Option<FileArgs>,
 
@@ -324,9 +561,16 @@ macro_rules! make_cli {
             command,
             center: var.center,
             clear: var.clear,
+            alternate_screen: var.alternate_screen,
+            interactive: var.interactive,
             pause: var.pause,
             show_time: var.show_time,
             output: var.output.clone(),
+            output_format: var.output_format.into(),
+            record: var.record.clone(),
+            record_only: var.record_only,
+            cell_width: var.cell_width,
+            cell_height: var.cell_height,
             half_resolution: var.half_resolution,
             disable_print: var.disable_print,
             disable_info: var.disable_info,
@@ -343,6 +587,8 @@ macro_rules! make_cli {
             enable_compression: var.enable_compression,
             disable_dither: var.disable_dither,
             color_space: var.color_space.into(),
+            transparent: var.transparent,
+            global_palette: var.global_palette,
         }
     }};
     ($var:ident: $cmd:ident) => {
@@ -387,7 +633,7 @@ impl From<&DotFileContent> for super::cli::Cli {
                     fps: config.fps,
                     #[cfg(feature = "audio_support")]
                     audio: config.audio,
-                    loop_play: config.loop_play,
+                    loop_play: config.loop_play.0,
                 })
             }
             #[cfg(feature = "video_player")]
@@ -397,6 +643,13 @@ impl From<&DotFileContent> for super::cli::Cli {
                     path: value.input.clone(),
                     audio: config.audio,
                     flush_interval: config.flush_interval,
+                    start: config.start,
+                    end: config.end,
+                    duration: config.duration,
+                    speed: config.speed,
+                    subs: config.subs,
+                    subs_stream: config.subs_stream,
+                    subs_position: config.subs_position.into(),
                 })
             }
         }
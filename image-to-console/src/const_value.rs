@@ -1,4 +1,4 @@
-pub const IMAGE_EXTS: [&str; 6] = ["png", "jpg", "jpeg", "webp", "tiff", "bmp"];
+pub const IMAGE_EXTS: [&str; 7] = ["png", "jpg", "jpeg", "webp", "tiff", "bmp", "six"];
 
 #[cfg(target_os = "linux")]
 pub const DEFAULT_LEN: std::num::NonZeroUsize = match std::num::NonZeroUsize::new(200) {
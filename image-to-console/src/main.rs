@@ -2,6 +2,8 @@ extern crate core;
 
 mod config;
 mod const_value;
+#[cfg(feature = "video_player")]
+mod probe;
 mod runner;
 mod types;
 mod util;
@@ -12,10 +14,11 @@ use crate::runner::*;
 use config::RunMode::*;
 
 fn main() {
-    let config = config::parse();
     // 这里可以 unwrap，ffmpeg 都无法启动可以看作是严重问题
+    // The probe subcommand opens the video while the args are parsed
     #[cfg(feature = "video_player")]
     ffmpeg_next::init().unwrap();
+    let config = config::parse();
     match config {
         Once(config) => run(config),
         Multiple(configs) => run_multiple(configs),
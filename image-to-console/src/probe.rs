@@ -0,0 +1,139 @@
+use crate::{config::cli::ProbeArgs, types::VideoTime};
+use serde_json::{Value, json};
+use video_decoder::VideoInfo;
+
+/// Print the metadata of a video file
+///
+/// # Arguments
+///
+/// * `args` - The args of the probe subcommand
+pub fn probe(args: &ProbeArgs) -> Result<(), String> {
+    let video = video_decoder::open(&args.path).map_err(|e| e.to_string())?;
+    let info = video.info().map_err(|e| e.to_string())?;
+    if args.json {
+        let json =
+            serde_json::to_string_pretty(&to_json(&args.path, &info)).map_err(|e| e.to_string())?;
+        println!("{json}");
+    } else {
+        print_info(&args.path, &info);
+    }
+    Ok(())
+}
+
+fn seconds(duration: Option<std::time::Duration>) -> Option<f64> {
+    duration.map(|duration| duration.as_secs_f64())
+}
+
+fn to_json(path: &str, info: &VideoInfo) -> Value {
+    let video = &info.video;
+    json!({
+        "path": path,
+        "format": info.format,
+        "duration": seconds(info.duration),
+        "bit_rate": info.bit_rate,
+        "video": {
+            "index": video.index,
+            "codec": video.codec,
+            "width": video.size.0,
+            "height": video.size.1,
+            "pixel_format": video.pixel_format,
+            "frame_rate": video.frame_rate,
+            "bit_rate": video.bit_rate,
+            "rotation": video.rotation.degrees(),
+            "duration": seconds(video.duration),
+            "frames": video.frames,
+        },
+        "audio": info.audio.iter().map(|audio| json!({
+            "index": audio.index,
+            "codec": audio.codec,
+            "sample_rate": audio.sample_rate,
+            "channels": audio.channels,
+            "bit_rate": audio.bit_rate,
+            "language": audio.language,
+            "title": audio.title,
+            "default": audio.default,
+        })).collect::<Vec<_>>(),
+        "subtitles": info.subtitles.iter().map(|subtitle| json!({
+            "index": subtitle.index,
+            "codec": subtitle.codec,
+            "language": subtitle.language,
+            "title": subtitle.title,
+            "default": subtitle.default,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+/// Join the language, the title and the default flag of a stream, e.g. `eng, "Commentary", default`
+fn stream_labels(language: Option<&str>, title: Option<&str>, default: bool) -> String {
+    language
+        .map(str::to_string)
+        .into_iter()
+        .chain(title.map(|title| format!("\"{title}\"")))
+        .chain(default.then(|| "default".to_string()))
+        .map(|label| format!(", {label}"))
+        .collect()
+}
+
+fn bit_rate(bit_rate: Option<u64>) -> String {
+    bit_rate
+        .map(|rate| format!(", {} kb/s", rate / 1000))
+        .unwrap_or_default()
+}
+
+fn print_info(path: &str, info: &VideoInfo) {
+    let video = &info.video;
+    println!("File: {path}");
+    println!("Format: {}", info.format);
+    match info.duration {
+        Some(duration) => println!("Duration: {}", VideoTime(duration)),
+        None => println!("Duration: unknown"),
+    }
+    if let Some(rate) = info.bit_rate {
+        println!("Bit rate: {} kb/s", rate / 1000);
+    }
+    println!(
+        "Video #{}: {}, {}, {}x{}, {:.3} fps{}{}{}",
+        video.index,
+        video.codec,
+        video.pixel_format,
+        video.size.0,
+        video.size.1,
+        video.frame_rate,
+        bit_rate(video.bit_rate),
+        match video.rotation.degrees() {
+            0 => String::new(),
+            degrees => format!(", rotated {degrees}°"),
+        },
+        video
+            .frames
+            .map(|frames| format!(", ~{frames} frames"))
+            .unwrap_or_default()
+    );
+    for audio in &info.audio {
+        println!(
+            "Audio #{}: {}, {} Hz, {} channels{}{}",
+            audio.index,
+            audio.codec,
+            audio.sample_rate,
+            audio.channels,
+            bit_rate(audio.bit_rate),
+            stream_labels(
+                audio.language.as_deref(),
+                audio.title.as_deref(),
+                audio.default
+            )
+        );
+    }
+    for subtitle in &info.subtitles {
+        println!(
+            "Subtitle #{}: {}{}",
+            subtitle.index,
+            subtitle.codec,
+            stream_labels(
+                subtitle.language.as_deref(),
+                subtitle.title.as_deref(),
+                subtitle.default
+            )
+        );
+    }
+}
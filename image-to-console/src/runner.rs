@@ -6,7 +6,7 @@ use image::DynamicImage;
 use image_to_console_colored::colors::TerminalColor;
 use image_to_console_colored::prelude::ToColoredText;
 use image_to_console_core::processor::{ImageProcessor, ImageProcessorResult};
-use image_to_console_renderer::renderer::render;
+use image_to_console_renderer::renderer::{render, restore_terminal};
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::iter::IntoParallelIterator;
 use rayon::iter::ParallelIterator;
@@ -15,6 +15,8 @@ use rayon::prelude::*;
 use std::time::Duration;
 
 pub fn err<E: std::fmt::Display>(err_msg: E) -> ! {
+    // The process exits without dropping the terminal guard of the player
+    restore_terminal();
     eprintln!(
         "{}: {err_msg}",
         "error"
@@ -57,6 +59,8 @@ fn process(
 ) -> image_to_console_core::ConvertResult<ImageProcessorResult> {
     let option = config.into();
     let mut processor = ImageProcessor::new(img, option);
+    #[cfg(feature = "sixel_support")]
+    processor.sixel_palette(config.sixel_palette.clone());
     #[cfg(target_os = "linux")]
     if config.mode.is_kitty_shm() {
         let time = std::time::Instant::now();
@@ -84,12 +88,177 @@ fn process(
             width: w,
             height: h,
             air_lines: air_line,
-            lines: vec![kitty_img.to_string()],
+            cells: None,
+            lines: vec![match option.passthrough {
+                Some(multiplexer) => multiplexer.wrap(&kitty_img.to_string()),
+                None => kitty_img.to_string(),
+            }],
         });
     }
     processor.process()
 }
 
+/// Build the shared sixel palette from the first frames if it is enabled
+///
+/// # Arguments
+///
+/// * `frames` - The frames of the animation
+/// * `config` - The config of the animation
+/// * `image` - Get the image of a frame
+///
+/// # Returns
+///
+/// Returns the config with the shared palette and the frames (the first frames are buffered)
+#[cfg(all(
+    any(feature = "video_player", feature = "gif_player"),
+    feature = "sixel_support"
+))]
+fn shared_palette<T>(
+    mut frames: impl Iterator<Item = T>,
+    config: &Config,
+    image: impl Fn(&T) -> Option<&DynamicImage>,
+) -> (Config, impl Iterator<Item = T>) {
+    use image_to_console_core::{indexed_image::SixelPalette, quantette};
+    let mut config = config.clone();
+    let mut head = Vec::new();
+    if let Some(count) = config.global_palette
+        && config.mode.is_sixel()
+        && config.sixel_palette.is_none()
+    {
+        head.extend(frames.by_ref().take(count as usize));
+        let images = head
+            .iter()
+            .filter_map(&image)
+            .map(DynamicImage::to_rgb8)
+            .collect::<Vec<_>>();
+        config.sixel_palette = SixelPalette::from_frames(
+            &images,
+            config.max_colors,
+            quantette::QuantizeMethod::wu(),
+            quantette::ColorSpace::from(&config.color_space),
+        )
+        .ok()
+        .map(std::sync::Arc::new);
+    }
+    (config, head.into_iter().chain(frames))
+}
+
+/// Without sixel support there is no shared palette, the frames are passed through
+#[cfg(all(
+    any(feature = "video_player", feature = "gif_player"),
+    not(feature = "sixel_support")
+))]
+fn shared_palette<T>(
+    frames: impl Iterator<Item = T>,
+    config: &Config,
+    _image: impl Fn(&T) -> Option<&DynamicImage>,
+) -> (Config, impl Iterator<Item = T>) {
+    (config.clone(), frames)
+}
+
+/// Share the color registers between the frames when the shared sixel palette is used
+///
+/// # Arguments
+///
+/// * `frame` - The first frame to print
+/// * `config` - The config of the animation
+#[cfg(all(
+    any(feature = "video_player", feature = "gif_player"),
+    feature = "sixel_support"
+))]
+fn begin_shared_palette(frame: &mut String, config: &Config) {
+    use image_to_console_core::{indexed_image::SixelPalette, passthrough::Multiplexer};
+    if config.sixel_palette.is_some() {
+        match Multiplexer::detect() {
+            Some(multiplexer) => frame.insert_str(
+                0,
+                &multiplexer.wrap_sequence(SixelPalette::DISABLE_PRIVATE_REGISTERS),
+            ),
+            None => frame.insert_str(0, SixelPalette::DISABLE_PRIVATE_REGISTERS),
+        }
+    }
+}
+
+#[cfg(all(
+    any(feature = "video_player", feature = "gif_player"),
+    not(feature = "sixel_support")
+))]
+fn begin_shared_palette(_frame: &mut String, _config: &Config) {}
+
+/// Restore the private color registers after the animation
+#[cfg(all(
+    any(feature = "video_player", feature = "gif_player"),
+    feature = "sixel_support"
+))]
+fn end_shared_palette(config: &Config) {
+    use image_to_console_core::{indexed_image::SixelPalette, passthrough::Multiplexer};
+    if config.sixel_palette.is_some() {
+        match Multiplexer::detect() {
+            Some(multiplexer) => {
+                print!(
+                    "{}",
+                    multiplexer.wrap_sequence(SixelPalette::ENABLE_PRIVATE_REGISTERS)
+                )
+            }
+            None => print!("{}", SixelPalette::ENABLE_PRIVATE_REGISTERS),
+        }
+    }
+}
+
+#[cfg(all(
+    any(feature = "video_player", feature = "gif_player"),
+    not(feature = "sixel_support")
+))]
+fn end_shared_palette(_config: &Config) {}
+
+/// Create the asciicast recorder of the playback if it is enabled
+///
+/// # Arguments
+///
+/// * `config` - The config of the animation
+///
+/// # Returns
+///
+/// Returns the recorder, the process exits if the file cannot be created
+#[cfg(any(feature = "video_player", feature = "gif_player"))]
+fn create_recorder(
+    config: &Config,
+) -> Option<image_to_console_renderer::asciicast::AsciicastRecorder> {
+    use image_to_console_renderer::asciicast::AsciicastRecorder;
+    let path = config.record.as_ref()?;
+    match AsciicastRecorder::create(path) {
+        Ok(recorder) => Some(recorder),
+        Err(e) => err(format!("Failed to create {path}: {e}")),
+    }
+}
+
+/// Create the exporter of the animation if it is exported
+///
+/// # Arguments
+///
+/// * `config` - The config of the animation
+/// * `loop_count` - How many times the animation is played
+///
+/// # Returns
+///
+/// Returns the exporter, the process exits if the output is not an animation format or the
+/// directory of the frames cannot be created
+#[cfg(any(feature = "video_player", feature = "gif_player"))]
+fn create_exporter(
+    config: &Config,
+    loop_count: image_to_console_renderer::config::LoopCount,
+) -> Option<image_to_console_renderer::export::AnimationExporter> {
+    use image_to_console_renderer::export::AnimationExporter;
+    let path = config.output.as_ref()?;
+    match AnimationExporter::create(path, config.output_format, loop_count) {
+        Ok(mut exporter) => {
+            exporter.raster_options(crate::util::raster_options(config.cell_size));
+            Some(exporter)
+        }
+        Err(e) => err(format!("Failed to export to {path}: {e}")),
+    }
+}
+
 #[cfg(any(feature = "video_player", feature = "gif_player"))]
 pub fn run_video(config: Result<(ImageType, Config), String>) {
     use crate::types::ImageType;
@@ -111,22 +280,50 @@ pub fn run_video(config: Result<(ImageType, Config), String>) {
 
 #[cfg(feature = "gif_player")]
 fn gif(gif: crate::types::GifType, config: &Config) {
+    use crate::types::AnimationEvent;
     use image_to_console_renderer::frame::Frame;
     use image_to_console_renderer::renderer::render_gif;
     let (st, rt) = bounded::<Frame>(config.fps.unwrap_or(30) as _);
+    let crate::types::GifType { frames, next_pass } = gif;
+    let (mut config, frames) = shared_palette(frames.into_iter(), config, |event| match event {
+        Ok(AnimationEvent::Frame(frame, _, _)) => Some(frame),
+        _ => None,
+    });
+    config.cell_grid = true;
+    let config = &config;
     // Process the every frame image
     std::thread::scope(|s| {
-        s.spawn(|| {
-            for frame in gif {
-                match frame {
-                    Ok((frame, index, delay)) => {
+        s.spawn(move || {
+            // The first frame ends each pass, the next passes are decoded without it
+            let mut first: Option<Frame> = None;
+            for event in frames {
+                match event {
+                    Ok(AnimationEvent::Frame(frame, index, delay)) => {
                         let r = process(frame, config).map_err(err).unwrap();
-                        st.send(Frame {
+                        let mut frame = r.display().to_string();
+                        if first.is_none() {
+                            begin_shared_palette(&mut frame, config);
+                        }
+                        let frame = Frame {
                             index,
-                            delay: delay as u64,
-                            frame: r.display().to_string(),
-                        })
-                        .unwrap()
+                            delay,
+                            frame,
+                            cells: r.cells,
+                        };
+                        if first.is_none() {
+                            first = Some(frame.clone());
+                        }
+                        // The playback was stopped
+                        if st.send(frame).is_err() {
+                            return;
+                        }
+                    }
+                    Ok(AnimationEvent::PassEnd) => {
+                        if let Some(frame) = first.clone()
+                            && st.send(frame).is_err()
+                        {
+                            return;
+                        }
                     }
                     Err(e) => {
                         err(e);
@@ -136,12 +333,52 @@ fn gif(gif: crate::types::GifType, config: &Config) {
         });
 
         s.spawn(|| {
-            render_gif(
+            let exporter = create_exporter(config, config.loop_count);
+            let exporting = exporter.is_some();
+            if let Err(e) = render_gif(
                 rt,
                 image_to_console_renderer::config::Config::from(config.clone()),
-            );
+                create_recorder(config),
+                exporter,
+                Some(next_pass),
+            ) {
+                if exporting {
+                    err(format!("Failed to export the animation: {e}"));
+                }
+                err(format!("Failed to write the recording: {e}"));
+            }
         });
     });
+    end_shared_palette(config);
+}
+
+/// Get the size and the pixel format the video frames are decoded to
+///
+/// The frames are scaled to the size the processor would resize them to, and the luma modes
+/// get grayscale frames.
+#[cfg(feature = "video_player")]
+fn frame_format(config: &Config, size: (u32, u32)) -> crate::types::FrameFormat {
+    use image_to_console_core::processor::ImageProcessorOptions;
+    use video_decoder::PixelFormat;
+    let option: ImageProcessorOptions = config.into();
+    let format = if config.mode.is_luma() {
+        PixelFormat::Gray8
+    } else {
+        PixelFormat::Rgb24
+    };
+    (option.fit_size(size).ok(), format)
+}
+
+/// Create the overlay of the subtitles chosen for a video
+#[cfg(feature = "video_player")]
+fn subtitle_overlay(
+    config: &Config,
+) -> Option<image_to_console_renderer::subtitle::SubtitleOverlay> {
+    let subtitles = config.subtitles.clone()?;
+    Some(image_to_console_renderer::subtitle::SubtitleOverlay::new(
+        move |time| subtitles.text_at(time),
+        config.subtitle_position,
+    ))
 }
 
 #[cfg(feature = "video_player")]
@@ -157,11 +394,14 @@ fn video(video_event: crate::types::VideoType, config: &Config) {
                 }
                 Initialized(args) => {
                     #[cfg(not(feature = "audio_support"))]
-                    let (vrx, fps) = args;
+                    let (vrx, fps, size, format) = args;
                     #[cfg(feature = "audio_support")]
-                    let (vrx, audio_path, fps, sync_pos) = args;
+                    let (vrx, audio, fps, sync_pos, size, format) = args;
+                    // The decoder waits for the format before decoding the first frame
+                    let _ = format.send(frame_format(config, size));
                     let (st, rt) = bounded(10);
-                    let flush_interval = config.flush_interval.get_frames(fps);
+                    // The interval is in the playback time, which is sped up
+                    let flush_interval = config.flush_interval.get_frames(fps * config.speed);
 
                     #[cfg(feature = "audio_support")]
                     let per_frame = Duration::from_secs_f32(1f32 / fps);
@@ -180,88 +420,119 @@ fn video(video_event: crate::types::VideoType, config: &Config) {
 
                     #[cfg(feature = "audio_support")]
                     let pos = sync_pos.clone();
+                    let (mut config, frames) = shared_palette(vrx.iter(), config, |frame| {
+                        frame.as_ref().ok().map(|(frame, _, _)| frame)
+                    });
+                    config.cell_grid = true;
+                    let config = &config;
                     std::thread::scope(|s| {
                         s.spawn(|| {
-                            loop {
-                                match vrx.recv() {
-                                    Err(_) => {
-                                        // Channel disconnected
-                                        break;
-                                    }
-                                    Ok(frame) => match frame {
-                                        Ok((frame, index, pts)) => {
-                                            #[cfg(feature = "audio_support")]
-                                            if let Some(pts) = pts {
-                                                let p = Duration::from_millis(
-                                                    pos.load(std::sync::atomic::Ordering::SeqCst),
-                                                )
-                                                .saturating_sub(pts);
-
-                                                if p > two_frame && spare {
-                                                    continue;
-                                                }
+                            let mut first = true;
+                            // The loop ends when the channel is disconnected
+                            for frame in frames {
+                                match frame {
+                                    Ok((frame, index, pts)) => {
+                                        #[cfg(feature = "audio_support")]
+                                        if let Some(pts) = pts {
+                                            let p = Duration::from_millis(
+                                                pos.load(std::sync::atomic::Ordering::SeqCst),
+                                            )
+                                            .saturating_sub(pts);
 
-                                                if p.as_millis() > 250 {
-                                                    continue;
-                                                }
-
-                                                if p.as_millis() > if buzy { 180 } else { 200 }
-                                                    && discarded % 3 != 2
-                                                {
-                                                    discarded += 1;
-                                                    continue;
-                                                }
+                                            if p > two_frame && spare {
+                                                continue;
+                                            }
 
-                                                if p.as_millis() < 180 {
-                                                    buzy = false;
-                                                }
+                                            if p.as_millis() > 250 {
+                                                continue;
                                             }
-                                            #[cfg(feature = "audio_support")]
-                                            let timer = std::time::Instant::now();
-                                            let r = process(frame, config).map_err(err).unwrap();
-                                            st.send((r.lines.join("\n"), index, pts)).unwrap();
-                                            #[cfg(feature = "audio_support")]
+
+                                            if p.as_millis() > if buzy { 180 } else { 200 }
+                                                && discarded % 3 != 2
                                             {
-                                                discarded = 0;
-                                                spare = timer.elapsed() <= two_frame;
+                                                discarded += 1;
+                                                continue;
+                                            }
+
+                                            if p.as_millis() < 180 {
+                                                buzy = false;
                                             }
                                         }
-                                        Err(Eof) => {
-                                            drop(st);
+                                        #[cfg(feature = "audio_support")]
+                                        let timer = std::time::Instant::now();
+                                        let r = process(frame, config).map_err(err).unwrap();
+                                        let mut frame = r.lines.join("\n");
+                                        if first {
+                                            begin_shared_palette(&mut frame, config);
+                                            first = false;
+                                        }
+                                        // The playback was stopped
+                                        if st.send((frame, r.cells, index, pts)).is_err() {
                                             return;
                                         }
-                                        Err(DecodeError) => {
-                                            err("cannot decode this frame".to_string())
+                                        #[cfg(feature = "audio_support")]
+                                        {
+                                            discarded = 0;
+                                            spare = timer.elapsed() <= two_frame;
                                         }
-                                        Err(Other(e)) => err(format!("Other decode error: {e}")),
-                                    },
+                                    }
+                                    Err(Eof) => {
+                                        drop(st);
+                                        return;
+                                    }
+                                    Err(DecodeError) => err("cannot decode this frame".to_string()),
+                                    Err(Other(e)) => err(format!("Other decode error: {e}")),
                                 }
                             }
                         });
 
                         s.spawn(|| {
-                            #[cfg(all(feature = "sixel_support", feature = "audio_support"))]
-                            render_video(
+                            let exporter = create_exporter(config, Default::default());
+                            let exporting = exporter.is_some();
+                            #[cfg(feature = "audio_support")]
+                            let result = render_video(
                                 rt,
-                                audio_path,
+                                audio,
+                                config.start,
                                 fps,
+                                config.speed,
                                 config.clear,
+                                config.alternate_screen,
+                                config.interactive,
                                 flush_interval,
                                 config.disable_info,
                                 config.mode.is_kitty_shm(),
+                                create_recorder(config),
+                                exporter,
+                                config.record_only,
+                                subtitle_overlay(config),
                                 sync_pos,
                             );
                             #[cfg(not(feature = "audio_support"))]
-                            render_video(
+                            let result = render_video(
                                 rt,
                                 fps,
+                                config.speed,
                                 config.clear,
+                                config.alternate_screen,
+                                config.interactive,
                                 flush_interval,
                                 config.disable_info,
                                 config.mode.is_kitty_shm(),
+                                create_recorder(config),
+                                exporter,
+                                config.record_only,
+                                subtitle_overlay(config),
                             );
+                            if let Err(e) = result {
+                                if exporting {
+                                    err(format!("Failed to export the video: {e}"));
+                                }
+                                err(format!("Failed to write the recording: {e}"));
+                            }
                         });
                     });
+                    end_shared_palette(config);
                 }
                 Finished => break,
             },
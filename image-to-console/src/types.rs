@@ -1,9 +1,32 @@
 use clap::{ValueEnum, builder::PossibleValue};
 use image::DynamicImage;
+use image_to_console_renderer::config::OutputFormat;
 use std::fmt::Debug;
 
+/// An event of a decoded animation
 #[cfg(feature = "gif_player")]
-pub type GifType = crossbeam_channel::Receiver<Result<(DynamicImage, usize, u16), String>>;
+#[derive(Debug, Clone)]
+pub enum AnimationEvent {
+    /// A frame, with its index in the pass and its delay
+    Frame(
+        DynamicImage,
+        usize,
+        image_to_console_renderer::frame::FrameDelay,
+    ),
+    /// The end of a pass, the frames of the next pass are decoded when they are requested
+    PassEnd,
+}
+
+/// The decoded frames of an animation
+#[cfg(feature = "gif_player")]
+#[derive(Debug, Clone)]
+pub struct GifType {
+    /// The channel to receive the frames
+    pub frames: crossbeam_channel::Receiver<Result<AnimationEvent, String>>,
+    /// Request the next pass after a [`AnimationEvent::PassEnd`], the decoding stops when it is
+    /// dropped
+    pub next_pass: crossbeam_channel::Sender<()>,
+}
 
 #[cfg(feature = "video_player")]
 pub type VideoType = crossbeam_channel::Receiver<Result<VideoEvent, String>>;
@@ -46,28 +69,158 @@ impl ValueEnum for ClapResizeMode {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ClapOutputFormat {
+    /// Follow the extension of the output file
+    #[default]
+    Auto,
+    Text,
+    Html,
+    Svg,
+    Png,
+    Gif,
+    Frames,
+}
+
+impl ClapOutputFormat {
+    /// Resolve the format of an output file
+    pub fn resolve(&self, output: Option<&str>) -> OutputFormat {
+        match self {
+            Self::Auto => output.map(OutputFormat::from_path).unwrap_or_default(),
+            Self::Text => OutputFormat::Text,
+            Self::Html => OutputFormat::Html,
+            Self::Svg => OutputFormat::Svg,
+            Self::Png => OutputFormat::Png,
+            Self::Gif => OutputFormat::Gif,
+            Self::Frames => OutputFormat::Frames,
+        }
+    }
+}
+
+impl ValueEnum for ClapOutputFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self::Auto,
+            Self::Text,
+            Self::Html,
+            Self::Svg,
+            Self::Png,
+            Self::Gif,
+            Self::Frames,
+        ]
+    }
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Auto => PossibleValue::new("auto"),
+            Self::Text => PossibleValue::new("text"),
+            Self::Html => PossibleValue::new("html"),
+            Self::Svg => PossibleValue::new("svg"),
+            Self::Png => PossibleValue::new("png"),
+            Self::Gif => PossibleValue::new("gif"),
+            Self::Frames => PossibleValue::new("frames"),
+        })
+    }
+}
+
+/// Where the subtitles of a video are drawn
+#[cfg(feature = "video_player")]
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ClapSubtitlePosition {
+    /// On the lines under the frame
+    #[default]
+    Below,
+    /// Over the last rows of the frame
+    Overlay,
+}
+
+#[cfg(feature = "video_player")]
+impl ValueEnum for ClapSubtitlePosition {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Below, Self::Overlay]
+    }
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Below => PossibleValue::new("below"),
+            Self::Overlay => PossibleValue::new("overlay"),
+        })
+    }
+}
+
+#[cfg(feature = "video_player")]
+impl From<ClapSubtitlePosition> for image_to_console_renderer::subtitle::SubtitlePosition {
+    fn from(position: ClapSubtitlePosition) -> Self {
+        match position {
+            ClapSubtitlePosition::Below => Self::Below,
+            ClapSubtitlePosition::Overlay => Self::Overlay,
+        }
+    }
+}
+
+/// The subtitles shown with a video
+#[cfg(feature = "video_player")]
+#[derive(Debug, Clone)]
+pub enum VideoSubtitles {
+    /// The cues of a subtitle file, read before the playback
+    File(std::sync::Arc<video_decoder::Subtitles>),
+    /// A subtitle stream of the video, decoded from the demuxer of the playback as it goes on
+    Embedded {
+        index: usize,
+        /// Set when the video is opened for the playback
+        decoder: std::sync::Arc<std::sync::Mutex<Option<video_decoder::SubtitleDecoder>>>,
+    },
+}
+
+#[cfg(feature = "video_player")]
+impl VideoSubtitles {
+    /// Get the text shown at a time of the video, the lines are separated by `\n`
+    pub fn text_at(&self, time: std::time::Duration) -> Option<String> {
+        match self {
+            Self::File(subtitles) => subtitles.text_at(time),
+            Self::Embedded { decoder, .. } => decoder
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .as_mut()?
+                .text_at(time),
+        }
+    }
+}
+
 #[cfg(feature = "video_player")]
 pub type FrameReceiver = crossbeam_channel::Receiver<
     Result<(DynamicImage, usize, Option<std::time::Duration>), crate::errors::FrameError>,
 >;
 
+/// The size the video frames are scaled to (the size of the video if it is `None`) and their
+/// pixel format
+#[cfg(feature = "video_player")]
+pub type FrameFormat = (Option<(u32, u32)>, video_decoder::PixelFormat);
+
+#[cfg(feature = "video_player")]
+pub type FrameFormatSender = crossbeam_channel::Sender<FrameFormat>;
+
 /// The event type to of video parser
 #[cfg(feature = "video_player")]
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub enum VideoEvent {
     Starting,
     /// The first one is the receiver of the video data
     ///
-    /// The last one is the frame rate.
+    /// The next ones are the frame rate, the size of the video, and the sender of the format
+    /// the frames are decoded to. The frames are decoded after the format is sent.
+    ///
+    /// With the audio support, the audio follows the receiver, and the position of the audio,
+    /// which the late frames are dropped by, follows the frame rate.
     #[cfg(not(feature = "audio_support"))]
-    Initialized((FrameReceiver, f32)),
+    Initialized((FrameReceiver, f32, (u32, u32), FrameFormatSender)),
     #[cfg(feature = "audio_support")]
     Initialized(
         (
             FrameReceiver,
-            image_to_console_renderer::audio_path::AudioPath,
+            image_to_console_renderer::audio::VideoAudio,
             f32,
             std::sync::Arc<std::sync::atomic::AtomicU64>,
+            (u32, u32),
+            FrameFormatSender,
         ),
     ),
     Finished,
@@ -204,6 +357,62 @@ impl FlushInterval {
     }
 }
 
+#[cfg(feature = "video_player")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+/// A time in a video, e.g. where the playback starts.
+pub struct VideoTime(pub std::time::Duration);
+
+#[cfg(feature = "video_player")]
+impl std::str::FromStr for VideoTime {
+    type Err = String;
+
+    /// Parses a string into a `VideoTime`.
+    ///
+    /// Supports the following formats:
+    /// - "N" or "Ns" where N is a number of seconds (e.g., "90", "2.5s")
+    /// - "M:S" (e.g., "1:30.5") - minutes and seconds
+    /// - "H:M:S" (e.g., "01:02:03") - hours, minutes and seconds
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid time `{s}`, expected seconds, `M:S` or `H:M:S`");
+        let mut parts = s.strip_suffix('s').unwrap_or(s).rsplit(':');
+        // The seconds may have a fraction, the minutes and the hours are whole numbers
+        let seconds = parts
+            .next()
+            .filter(|seconds| seconds.chars().all(|c| c.is_ascii_digit() || c == '.'))
+            .and_then(|seconds| seconds.parse::<f64>().ok())
+            .ok_or_else(invalid)?;
+        let mut total = seconds;
+        for (part, unit) in parts.by_ref().zip([60f64, 3600f64]) {
+            let value = Some(part)
+                .filter(|part| part.chars().all(|c| c.is_ascii_digit()))
+                .and_then(|part| part.parse::<u64>().ok())
+                .ok_or_else(invalid)?;
+            total += value as f64 * unit;
+        }
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        std::time::Duration::try_from_secs_f64(total)
+            .map(Self)
+            .map_err(|_| invalid())
+    }
+}
+
+#[cfg(feature = "video_player")]
+impl std::fmt::Display for VideoTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let seconds = self.0.as_secs();
+        write!(
+            f,
+            "{:02}:{:02}:{:02}.{:03}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60,
+            self.0.subsec_millis()
+        )
+    }
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 pub enum ColorSpace {
     #[default]
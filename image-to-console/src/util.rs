@@ -10,8 +10,11 @@ use crate::{
 use image_to_console_core::quantette::ColorSpace;
 use image_to_console_core::{
     AutoResizeOption, CustomResizeOption, ResizeMode,
+    converter::raster::RasterOptions,
+    passthrough::Multiplexer,
     processor::{ImageProcessor, ImageProcessorOptions},
 };
+use std::io::IsTerminal;
 
 pub trait CreateIPFromConfig {
     fn from_config(img: ImageType, config: &Config) -> image_to_console_core::ConvertResult<Self>
@@ -34,6 +37,14 @@ impl From<&Config> for ImageProcessorOptions {
             max_colors: config.max_colors,
             #[cfg(feature = "sixel_support")]
             color_space: ColorSpace::from(&config.color_space),
+            #[cfg(feature = "sixel_support")]
+            transparent: config.transparent,
+            passthrough: Multiplexer::detect(),
+            cell_grid: config.cell_grid,
+            // A recording or an export made without a terminal has the default size of asciicast
+            terminal_size: ((config.record_only || config.is_exporting())
+                && !std::io::stdout().is_terminal())
+            .then_some(image_to_console_renderer::asciicast::DEFAULT_SIZE),
         }
     }
 }
@@ -44,7 +55,7 @@ impl CreateIPFromConfig for ImageProcessor {
         match img {
             Image(image) => Ok(Self::new(image, option)),
             Path(path) => {
-                let image = image::open(path).map_err(|e| {
+                let image = open_image(path).map_err(|e| {
                     image_to_console_core::error::ConvertError::ImageError(
                         image_to_console_core::error::ConvertErrorContext::new(
                             image_to_console_core::error::ConvertErrorContextSource::Function(
@@ -52,7 +63,7 @@ impl CreateIPFromConfig for ImageProcessor {
                             ),
                             String::from("Open failed"),
                         )
-                        .with_inner(Box::new(e)),
+                        .with_inner(e),
                     )
                 })?;
                 Ok(Self::new(image, option))
@@ -82,14 +93,31 @@ impl From<&Cli> for ResizeMode {
     }
 }
 
+/// Get the options of the png and gif output
+///
+/// # Arguments
+///
+/// * `(width, height)` - The size of a cell in pixels
+pub fn raster_options((width, height): (u32, u32)) -> RasterOptions {
+    RasterOptions::default()
+        .cell_size(width, height)
+        .get_options()
+}
+
 impl From<Config> for image_to_console_renderer::config::Config {
     fn from(config: Config) -> Self {
         Self {
             fps: config.fps,
             clear: config.clear,
+            alternate_screen: config.alternate_screen,
+            interactive: config.interactive,
             pause: config.pause,
             center: config.center,
             output: config.output,
+            output_format: config.output_format,
+            record_only: config.record_only,
+            raster_options: raster_options(config.cell_size),
+            loop_count: config.loop_count,
             file_name: config.file_name,
             show_time: config.show_time,
             disable_info: config.disable_info,
@@ -108,10 +136,16 @@ impl From<&Config> for image_to_console_renderer::config::Config {
         Self {
             fps: config.fps,
             clear: config.clear,
+            alternate_screen: config.alternate_screen,
+            interactive: config.interactive,
             pause: config.pause,
             center: config.center,
             show_time: config.show_time,
             output: config.output.clone(),
+            output_format: config.output_format,
+            record_only: config.record_only,
+            raster_options: raster_options(config.cell_size),
+            loop_count: config.loop_count,
             disable_info: config.disable_info,
             file_name: config.file_name.clone(),
             disable_print: config.disable_print,
@@ -124,38 +158,6 @@ impl From<&Config> for image_to_console_renderer::config::Config {
     }
 }
 
-#[cfg(all(feature = "video_player", feature = "audio_support"))]
-pub fn pick_audio(
-    path: &std::path::Path,
-    target: &std::path::Path,
-) -> Result<(), ffmpeg_next::Error> {
-    let mut input_ctx = ffmpeg_next::format::input(path)?;
-    let index = input_ctx
-        .streams()
-        .best(ffmpeg_next::media::Type::Audio)
-        .ok_or(ffmpeg_next::error::Error::StreamNotFound)?
-        .index();
-    let input_stream = input_ctx.stream(index).unwrap();
-
-    let mut output_ctx = ffmpeg_next::format::output(target)?;
-    let mut output_stream = output_ctx.add_stream_with(
-        &ffmpeg_next::codec::Context::from_parameters(input_stream.parameters())?,
-    )?;
-
-    output_stream.set_parameters(input_stream.parameters());
-    output_ctx.write_header()?;
-
-    for (stream, mut packet) in input_ctx.packets() {
-        if stream.index() == index {
-            packet.set_stream(0);
-            packet.write_interleaved(&mut output_ctx)?;
-        }
-    }
-
-    output_ctx.write_trailer()?;
-    Ok(())
-}
-
 #[cfg(feature = "dot_file")]
 #[derive(Debug)]
 struct StringSpan<'a> {
@@ -308,3 +310,23 @@ pub fn get_schema_dir() -> Result<std::path::PathBuf, String> {
         _ => Err("Cannot get the local data dir".to_string()),
     }
 }
+
+/// Open an image file, the sixel files (`.six`) are decoded from their sixel sequence
+pub fn open_image<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<image::DynamicImage, Box<dyn std::error::Error + Send>> {
+    let path = path.as_ref();
+    let is_sixel = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("six"));
+    if !is_sixel {
+        return image::open(path).map_err(|e| Box::new(e) as _);
+    }
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) => return Err(Box::new(e)),
+    };
+    image_to_console_core::decoder::sixel::decode(&data)
+        .map(image::DynamicImage::ImageRgba8)
+        .map_err(|e| Box::new(e) as _)
+}
@@ -157,6 +157,18 @@ integer_impl!(u64);
 integer_impl!(u128);
 integer_impl!(usize);
 
+impl ToSchema for f32 {
+    fn schema_type() -> Value {
+        serde_json::json!("number")
+    }
+}
+
+impl ToSchema for f64 {
+    fn schema_type() -> Value {
+        serde_json::json!("number")
+    }
+}
+
 impl ToSchema for String {
     /// Returns the schema type identifier for string types
     fn schema_type() -> Value {
@@ -0,0 +1,212 @@
+use crate::{Error, VideoResult, demuxer::SharedDemuxer};
+use ffmpeg_next::{ChannelLayout, format::Sample, format::sample::Type};
+use std::time::Duration;
+
+/// A frame of decoded audio
+#[derive(Debug, Clone)]
+pub struct AudioFrame {
+    /// The time of the first sample
+    pub pts: Duration,
+    /// The samples of the channels, interleaved
+    pub samples: Vec<f32>,
+}
+
+/// The decoder of an audio stream, the samples are decoded to interleaved 32-bit floats
+///
+/// Mono is kept as it is, the other layouts are mixed down to stereo.
+pub struct AudioDecoder {
+    decoder: ffmpeg_next::codec::decoder::Audio,
+    audio_stream: usize,
+    demuxer: SharedDemuxer,
+    audio_frame: ffmpeg_next::frame::Audio,
+    /// Created with the format of the first frame, which the decoder may not know before
+    resampler: Option<ffmpeg_next::software::resampling::Context>,
+    channels: u16,
+    rate: u32,
+    time_base: f64,
+    /// The time seeked to, the samples before it are decoded and skipped
+    target: Option<Duration>,
+    /// The samples from this time are not decoded
+    end: Option<Duration>,
+    /// The time after the last frame, for the frames without a timestamp
+    next_pts: f64,
+}
+
+impl AudioDecoder {
+    pub(crate) fn new(demuxer: SharedDemuxer, audio_stream: usize) -> VideoResult<Self> {
+        let (decoder, time_base) = {
+            let mut locked = demuxer.lock();
+            let Some(stream) = locked.input().stream(audio_stream) else {
+                return Err(Error::InvalidStream);
+            };
+            let decoder = ffmpeg_next::codec::Context::from_parameters(stream.parameters())?
+                .decoder()
+                .audio()?;
+            let time_base = {
+                let time_base = stream.time_base();
+                time_base.0 as f64 / time_base.1 as f64
+            };
+            locked.subscribe(audio_stream);
+            (decoder, time_base)
+        };
+        Ok(Self {
+            channels: if decoder.channels() == 1 { 1 } else { 2 },
+            rate: decoder.rate(),
+            decoder,
+            audio_stream,
+            demuxer,
+            audio_frame: ffmpeg_next::frame::Audio::empty(),
+            resampler: None,
+            time_base,
+            target: None,
+            end: None,
+            next_pts: 0.0,
+        })
+    }
+
+    /// Sets the time the audio ends at
+    ///
+    /// # Arguments
+    ///
+    /// * `end` - The samples from this time are not decoded, the audio is decoded to the end of
+    ///   the stream if it is `None`
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn end(&mut self, end: Option<Duration>) -> &mut Self {
+        self.end = end;
+        self
+    }
+
+    /// Skip the samples before a time, after the input was moved to a keyframe before it
+    pub(crate) fn skip_to(&mut self, position: Duration) {
+        self.target = Some(position);
+    }
+
+    /// Get the number of the interleaved channels, 1 or 2
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.rate
+    }
+
+    fn decode_frame(&mut self) -> VideoResult<Option<ffmpeg_next::frame::Audio>> {
+        if self.decoder.receive_frame(&mut self.audio_frame).is_ok() {
+            return Ok(Some(self.audio_frame.clone()));
+        }
+        loop {
+            // The lock is released before decoding, so the video is read in the meantime
+            let packet = self.demuxer.lock().next_packet(self.audio_stream);
+            let Some(packet) = packet else {
+                break;
+            };
+            match self.decoder.send_packet(&packet) {
+                Err(ffmpeg_next::Error::Other {
+                    errno: ffmpeg_next::error::EAGAIN,
+                }) => {}
+                // A broken packet is a short gap in the audio
+                Err(ffmpeg_next::Error::InvalidData) => {}
+                Err(e) => return Err(e.into()),
+                _ => {}
+            }
+            if self.decoder.receive_frame(&mut self.audio_frame).is_ok() {
+                return Ok(Some(self.audio_frame.clone()));
+            }
+        }
+
+        // The last frames are kept in the decoder until the end of the stream is sent
+        let _ = self.decoder.send_eof();
+        if self.decoder.receive_frame(&mut self.audio_frame).is_ok() {
+            return Ok(Some(self.audio_frame.clone()));
+        }
+        Ok(None)
+    }
+
+    /// Convert a decoded frame to interleaved floats
+    fn resample(&mut self, frame: &ffmpeg_next::frame::Audio) -> VideoResult<Vec<f32>> {
+        let resampler = match &mut self.resampler {
+            Some(resampler) => resampler,
+            None => {
+                let layout = match frame.channel_layout() {
+                    layout if layout.is_empty() => ChannelLayout::default(frame.channels().into()),
+                    layout => layout,
+                };
+                let output = match self.channels {
+                    1 => ChannelLayout::MONO,
+                    _ => ChannelLayout::STEREO,
+                };
+                self.resampler
+                    .insert(ffmpeg_next::software::resampling::Context::get(
+                        frame.format(),
+                        layout,
+                        frame.rate(),
+                        Sample::F32(Type::Packed),
+                        output,
+                        self.rate,
+                    )?)
+            }
+        };
+        let mut output = ffmpeg_next::frame::Audio::empty();
+        resampler.run(frame, &mut output)?;
+        Ok(output
+            .data(0)
+            .chunks_exact(4)
+            .take(output.samples() * self.channels as usize)
+            .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect())
+    }
+
+    fn read_frame(&mut self) -> VideoResult<Option<AudioFrame>> {
+        let channels = self.channels as usize;
+        while let Some(frame) = self.decode_frame()? {
+            let start = frame
+                .pts()
+                .map_or(self.next_pts, |pts| pts as f64 * self.time_base);
+            if self.end.is_some_and(|end| start >= end.as_secs_f64()) {
+                return Ok(None);
+            }
+            let mut samples = self.resample(&frame)?;
+            let frames = samples.len() / channels;
+            self.next_pts = start + frames as f64 / self.rate as f64;
+
+            // The samples before the time seeked to and the priming samples before zero are
+            // skipped, and the samples after the end are cut
+            let from = self.target.map_or(0.0, |target| target.as_secs_f64());
+            let skip = ((from - start) * self.rate as f64)
+                .round()
+                .clamp(0.0, frames as f64);
+            let keep = self.end.map_or(frames as f64, |end| {
+                ((end.as_secs_f64() - start) * self.rate as f64).ceil()
+            });
+            let keep = keep.clamp(skip, frames as f64);
+            samples.truncate(keep as usize * channels);
+            samples.drain(..skip as usize * channels);
+            if samples.is_empty() {
+                continue;
+            }
+            let pts = start + skip / self.rate as f64;
+            return Ok(Some(AudioFrame {
+                pts: Duration::try_from_secs_f64(pts).unwrap_or_default(),
+                samples,
+            }));
+        }
+        Ok(None)
+    }
+}
+
+impl Drop for AudioDecoder {
+    fn drop(&mut self) {
+        self.demuxer.lock().unsubscribe(self.audio_stream);
+    }
+}
+
+impl Iterator for AudioDecoder {
+    type Item = VideoResult<AudioFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
@@ -3,20 +3,55 @@ use crate::{Error, VideoResult};
 #[derive(Debug)]
 pub struct VideoFrame {
     pub pts: Option<std::time::Duration>,
-    pub frame: image::RgbImage,
+    pub frame: image::DynamicImage,
 }
 
 impl VideoFrame {
-    pub fn new(frame: image::RgbImage, pts: Option<std::time::Duration>) -> Self {
+    pub fn new(frame: image::DynamicImage, pts: Option<std::time::Duration>) -> Self {
         Self { frame, pts }
     }
 }
 
-fn process_frame(frame: &ffmpeg_next::util::frame::Video) -> VideoResult<image::RgbImage> {
+/// The pixel format the frames are converted to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 8-bit RGB
+    #[default]
+    Rgb24,
+    /// 8-bit RGBA
+    Rgba,
+    /// 8-bit grayscale
+    Gray8,
+}
+
+impl PixelFormat {
+    /// Get the pixel format of FFmpeg
+    fn to_ffmpeg(self) -> ffmpeg_next::format::Pixel {
+        match self {
+            Self::Rgb24 => ffmpeg_next::format::Pixel::RGB24,
+            Self::Rgba => ffmpeg_next::format::Pixel::RGBA,
+            Self::Gray8 => ffmpeg_next::format::Pixel::GRAY8,
+        }
+    }
+
+    /// Get the number of bytes of a pixel
+    fn channels(self) -> usize {
+        match self {
+            Self::Rgb24 => 3,
+            Self::Rgba => 4,
+            Self::Gray8 => 1,
+        }
+    }
+}
+
+fn process_frame(
+    frame: &ffmpeg_next::util::frame::Video,
+    format: PixelFormat,
+) -> VideoResult<image::DynamicImage> {
     let width = frame.width() as usize;
     let height = frame.height() as usize;
     let stride = frame.stride(0);
-    let row_size = width * 3;
+    let row_size = width * format.channels();
 
     let data = frame.data(0);
 
@@ -30,50 +65,148 @@ fn process_frame(frame: &ffmpeg_next::util::frame::Video) -> VideoResult<image::
             pixels.extend_from_slice(&data[start..end]);
         }
     }
-    image::RgbImage::from_raw(width as u32, height as u32, pixels).ok_or(Error::ToImageFailed)
+    let (width, height) = (width as u32, height as u32);
+    match format {
+        PixelFormat::Rgb24 => image::RgbImage::from_raw(width, height, pixels).map(Into::into),
+        PixelFormat::Rgba => image::RgbaImage::from_raw(width, height, pixels).map(Into::into),
+        PixelFormat::Gray8 => image::GrayImage::from_raw(width, height, pixels).map(Into::into),
+    }
+    .ok_or(Error::ToImageFailed)
+}
+
+/// Move an input to the last keyframe at or before a time
+///
+/// # Arguments
+///
+/// * `input` - The input to seek
+/// * `position` - The time to seek to, in the timestamps of the streams
+pub(crate) fn seek_input(
+    input: &mut ffmpeg_next::format::context::Input,
+    position: std::time::Duration,
+) -> VideoResult<()> {
+    // The timestamp is in `AV_TIME_BASE` (microseconds) when no stream is given
+    let timestamp = i64::try_from(position.as_micros()).unwrap_or(i64::MAX);
+    input.seek(timestamp, ..timestamp)?;
+    Ok(())
 }
 
-pub struct VideoDecoder<'a> {
+pub struct VideoDecoder {
     decoder: ffmpeg_next::codec::decoder::Video,
     video_stream: usize,
-    pockets: ffmpeg_next::format::context::input::PacketIter<'a>,
+    demuxer: crate::demuxer::SharedDemuxer,
     video_frame: ffmpeg_next::frame::Video,
     width: u32,
     height: u32,
     rate: f32,
     time_base: f64,
+    /// The time seeked to in seconds, the frames ending before it are decoded and skipped
+    target: Option<f64>,
+    /// The size the frames are scaled to, the size of the video if it is `None`
+    scale: Option<(u32, u32)>,
+    /// The pixel format the frames are converted to
+    pixel_format: PixelFormat,
+    /// The rotation the frames are turned by to be displayed upright
+    rotation: crate::Rotation,
 }
 
-impl<'a> VideoDecoder<'a> {
-    pub fn new(
-        input: &'a mut ffmpeg_next::format::context::Input,
+impl VideoDecoder {
+    pub(crate) fn new(
+        demuxer: crate::demuxer::SharedDemuxer,
         video_stream: usize,
     ) -> VideoResult<Self> {
-        let Some(stream) = input.stream(video_stream) else {
-            return Err(Error::InvalidStream);
+        let (codec, time_base, rotation) = {
+            let mut locked = demuxer.lock();
+            let Some(stream) = locked.input().stream(video_stream) else {
+                return Err(Error::InvalidStream);
+            };
+            let codec = ffmpeg_next::codec::Context::from_parameters(stream.parameters())?
+                .decoder()
+                .video()?;
+            let time_base = {
+                let time_base = stream.time_base();
+                time_base.0 as f64 / time_base.1 as f64
+            };
+            let rotation = crate::Rotation::of_stream(&stream);
+            locked.subscribe(video_stream);
+            (codec, time_base, rotation)
         };
-        let codec = ffmpeg_next::codec::Context::from_parameters(stream.parameters())?
-            .decoder()
-            .video()?;
         let rate = codec.frame_rate().ok_or(Error::GetVideoInfoFailed)?;
-        let time_base = {
-            let time_base = stream.time_base();
-            time_base.0 as f64 / time_base.1 as f64
-        };
         Ok(Self {
             video_stream,
-            pockets: input.packets(),
+            demuxer,
             width: codec.width(),
             height: codec.height(),
             time_base,
             rate: rate.0 as f32 / rate.1 as f32,
             decoder: codec,
             video_frame: ffmpeg_next::frame::Video::empty(),
+            target: None,
+            scale: None,
+            pixel_format: PixelFormat::default(),
+            rotation,
         })
     }
 
+    /// Sets the size the frames are scaled to
+    ///
+    /// The frames are scaled while they are converted, so they do not need to be resized again.
+    /// The size is the size of the frames after they are rotated.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The width and the height of the frames, `None` keeps the size of the video
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn scale(&mut self, size: Option<(u32, u32)>) -> &mut Self {
+        self.scale = size.filter(|&(width, height)| width > 0 && height > 0);
+        self
+    }
+
+    /// Sets the pixel format the frames are converted to
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The pixel format of the frames
+    ///
+    /// # Returns
+    ///
+    /// Returns a mutable reference to self for method chaining
+    pub fn pixel_format(&mut self, format: PixelFormat) -> &mut Self {
+        self.pixel_format = format;
+        self
+    }
+
+    /// Seek to a time of the video
+    ///
+    /// The input is moved to the last keyframe before the time, and the frames from the keyframe
+    /// are decoded and skipped until the frame shown at the time. The input is shared with the
+    /// audio decoder, so the video is seeked before the audio decoder is created.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The time to seek to, in the timestamps of the video
+    pub fn seek(&mut self, position: std::time::Duration) -> VideoResult<()> {
+        self.demuxer.lock().seek(position)?;
+        self.decoder.flush();
+        self.skip_to(position);
+        Ok(())
+    }
+
+    /// Skip the frames ending before a time, after the input was moved to a keyframe before it
+    pub(crate) fn skip_to(&mut self, position: std::time::Duration) {
+        self.target = Some(position.as_secs_f64());
+    }
+
+    /// Get the size of the frames as they are displayed, after the rotation
     pub fn size(&self) -> (u32, u32) {
-        (self.width, self.height)
+        self.rotation.rotate_size((self.width, self.height))
+    }
+
+    /// Get the rotation the frames are turned by, from the display matrix of the video
+    pub fn rotation(&self) -> crate::Rotation {
+        self.rotation
     }
 
     pub fn frame_rate(&self) -> f32 {
@@ -83,22 +216,33 @@ impl<'a> VideoDecoder<'a> {
     fn to_frames(
         self,
         pos: Option<std::sync::Arc<std::sync::atomic::AtomicU64>>,
-    ) -> VideoResult<VideoFrames<'a>> {
+    ) -> VideoResult<VideoFrames> {
         let codec = &self.decoder;
+        // The frames are rotated after they are scaled, so the size is rotated back
+        let (width, height) = self
+            .scale
+            .map(|size| self.rotation.rotate_size(size))
+            .unwrap_or((codec.width(), codec.height()));
+        // The area averaging keeps the details of the frames scaled down
+        let flags = if width < codec.width() || height < codec.height() {
+            ffmpeg_next::software::scaling::Flags::AREA
+        } else {
+            ffmpeg_next::software::scaling::Flags::BILINEAR
+        };
         let scaler = ffmpeg_next::software::scaling::Context::get(
             codec.format(),
             codec.width(),
             codec.height(),
-            ffmpeg_next::format::Pixel::RGB24,
-            codec.width(),
-            codec.height(),
-            ffmpeg_next::software::scaling::Flags::BILINEAR,
+            self.pixel_format.to_ffmpeg(),
+            width,
+            height,
+            flags,
         )?;
         Ok(VideoFrames::new(self, scaler, pos))
     }
 
     #[inline]
-    pub fn frames(self) -> VideoResult<VideoFrames<'a>> {
+    pub fn frames(self) -> VideoResult<VideoFrames> {
         self.to_frames(None)
     }
 
@@ -106,25 +250,43 @@ impl<'a> VideoDecoder<'a> {
     pub fn frames_with_pos(
         self,
         pos: std::sync::Arc<std::sync::atomic::AtomicU64>,
-    ) -> VideoResult<VideoFrames<'a>> {
+    ) -> VideoResult<VideoFrames> {
         self.to_frames(Some(pos))
     }
 
     fn read_frame(&mut self) -> VideoResult<Option<ffmpeg_next::util::frame::Video>> {
+        while let Some(frame) = self.decode_frame()? {
+            // The frame is shown at the time seeked to if it ends after it
+            if let (Some(target), Some(pts)) = (self.target, frame.pts())
+                && pts as f64 * self.time_base + 1.0 / self.rate as f64 <= target
+            {
+                continue;
+            }
+            self.target = None;
+            return Ok(Some(frame));
+        }
+        Ok(None)
+    }
+
+    fn decode_frame(&mut self) -> VideoResult<Option<ffmpeg_next::util::frame::Video>> {
         if self.decoder.receive_frame(&mut self.video_frame).is_ok() {
             return Ok(Some(self.video_frame.clone()));
         }
-        for (stream, packet) in self.pockets.by_ref() {
-            if stream.index() == self.video_stream {
-                match self.decoder.send_packet(&packet) {
-                    Err(ffmpeg_next::Error::Other { errno })
-                        if errno == ffmpeg_next::error::EAGAIN => {}
-                    Err(e) => return Err(e.into()),
-                    _ => {}
-                }
-                if self.decoder.receive_frame(&mut self.video_frame).is_ok() {
-                    return Ok(Some(self.video_frame.clone()));
-                }
+        loop {
+            // The lock is released before decoding, so the audio is read in the meantime
+            let packet = self.demuxer.lock().next_packet(self.video_stream);
+            let Some(packet) = packet else {
+                break;
+            };
+            match self.decoder.send_packet(&packet) {
+                Err(ffmpeg_next::Error::Other {
+                    errno: ffmpeg_next::error::EAGAIN,
+                }) => {}
+                Err(e) => return Err(e.into()),
+                _ => {}
+            }
+            if self.decoder.receive_frame(&mut self.video_frame).is_ok() {
+                return Ok(Some(self.video_frame.clone()));
             }
         }
 
@@ -135,7 +297,13 @@ impl<'a> VideoDecoder<'a> {
     }
 }
 
-impl<'a> Iterator for VideoDecoder<'a> {
+impl Drop for VideoDecoder {
+    fn drop(&mut self) {
+        self.demuxer.lock().unsubscribe(self.video_stream);
+    }
+}
+
+impl Iterator for VideoDecoder {
     type Item = VideoResult<ffmpeg_next::util::frame::Video>;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -143,16 +311,16 @@ impl<'a> Iterator for VideoDecoder<'a> {
     }
 }
 
-pub struct VideoFrames<'a> {
-    decoder: VideoDecoder<'a>,
+pub struct VideoFrames {
+    decoder: VideoDecoder,
     scaler: ffmpeg_next::software::scaling::Context,
     rgb_frame: ffmpeg_next::frame::Video,
     pos: Option<std::sync::Arc<std::sync::atomic::AtomicU64>>,
 }
 
-impl<'a> VideoFrames<'a> {
+impl VideoFrames {
     pub fn new(
-        decoder: VideoDecoder<'a>,
+        decoder: VideoDecoder,
         scaler: ffmpeg_next::software::scaling::Context,
         pos: Option<std::sync::Arc<std::sync::atomic::AtomicU64>>,
     ) -> Self {
@@ -181,13 +349,14 @@ impl<'a> VideoFrames<'a> {
         }
 
         self.scaler.run(&frame, &mut self.rgb_frame)?;
-        let img = process_frame(&self.rgb_frame)?;
+        let img = process_frame(&self.rgb_frame, self.decoder.pixel_format)?;
+        let img = self.decoder.rotation.apply(img);
         // dbg!(self.decoder.time_base);
         Ok(Some(VideoFrame::new(img, pts)))
     }
 }
 
-impl<'a> Iterator for VideoFrames<'a> {
+impl Iterator for VideoFrames {
     type Item = VideoResult<VideoFrame>;
 
     fn next(&mut self) -> Option<Self::Item> {
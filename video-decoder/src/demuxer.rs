@@ -0,0 +1,100 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// An input shared by the decoders of its streams
+///
+/// The packets are read once: a decoder gets the packets of its stream, and the packets of the
+/// other decoded streams are queued for their decoders, so the audio and the video are decoded
+/// from one demuxer.
+pub(crate) struct Demuxer {
+    input: ffmpeg_next::format::context::Input,
+    /// The packets read ahead, by the index of the decoded streams
+    queues: HashMap<usize, VecDeque<ffmpeg_next::Packet>>,
+}
+
+/// A demuxer shared by the decoders, which may run on other threads
+#[derive(Clone)]
+pub(crate) struct SharedDemuxer(Arc<Mutex<Demuxer>>);
+
+impl SharedDemuxer {
+    pub(crate) fn new(input: ffmpeg_next::format::context::Input) -> Self {
+        Self(Arc::new(Mutex::new(Demuxer {
+            input,
+            queues: HashMap::new(),
+        })))
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, Demuxer> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Demuxer {
+    pub(crate) fn input(&self) -> &ffmpeg_next::format::context::Input {
+        &self.input
+    }
+
+    /// Queue the packets of a stream for its decoder
+    pub(crate) fn subscribe(&mut self, stream: usize) {
+        self.queues.entry(stream).or_default();
+    }
+
+    /// Stop queuing the packets of a stream, when its decoder is dropped
+    pub(crate) fn unsubscribe(&mut self, stream: usize) {
+        self.queues.remove(&stream);
+    }
+
+    /// Get the next packet of a stream
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The index of the stream, it must be subscribed
+    ///
+    /// # Returns
+    ///
+    /// Returns the packet, `None` at the end of the input
+    pub(crate) fn next_packet(&mut self, stream: usize) -> Option<ffmpeg_next::Packet> {
+        if let Some(packet) = self.queues.get_mut(&stream)?.pop_front() {
+            return Some(packet);
+        }
+        while let Some((_, packet)) = self.input.packets().next() {
+            if packet.stream() == stream {
+                return Some(packet);
+            }
+            // The packets of the streams without a decoder are dropped
+            if let Some(queue) = self.queues.get_mut(&packet.stream()) {
+                queue.push_back(packet);
+            }
+        }
+        None
+    }
+
+    /// Get the next packet of a stream that was read ahead, the input is not read
+    ///
+    /// The packets of a sparse stream, e.g. subtitles, are taken as the other decoders read
+    /// past them, so its next packet is not looked for far ahead of the playback.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The index of the stream, it must be subscribed
+    ///
+    /// # Returns
+    ///
+    /// Returns the packet, `None` if no packet of the stream is queued
+    pub(crate) fn queued_packet(&mut self, stream: usize) -> Option<ffmpeg_next::Packet> {
+        self.queues.get_mut(&stream)?.pop_front()
+    }
+
+    /// Move the input to the last keyframe at or before a time, the queued packets are dropped
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The time to seek to, in the timestamps of the streams
+    pub(crate) fn seek(&mut self, position: std::time::Duration) -> crate::VideoResult<()> {
+        crate::decoder::seek_input(&mut self.input, position)?;
+        self.queues.values_mut().for_each(VecDeque::clear);
+        Ok(())
+    }
+}
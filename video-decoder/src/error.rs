@@ -5,20 +5,26 @@ pub type VideoResult<T> = Result<T, Error>;
 #[derive(Debug)]
 pub enum Error {
     CannotFindVideoStream,
+    CannotFindAudioStream,
+    CannotFindSubtitleStream,
     InvalidStream,
     GetVideoInfoFailed,
     ToImageFailed,
     FFmpegError(ffmpeg_next::Error),
+    IOError(std::io::Error),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::CannotFindVideoStream => write!(f, "Cannot find video stream"),
+            Self::CannotFindAudioStream => write!(f, "Cannot find audio stream"),
+            Self::CannotFindSubtitleStream => write!(f, "Cannot find subtitle stream"),
             Self::InvalidStream => write!(f, "Invalid video stream"),
             Self::GetVideoInfoFailed => write!(f, "Get video info failed"),
             Self::ToImageFailed => write!(f, "To image failed"),
             Self::FFmpegError(err) => write!(f, "FFmpeg Error: {err}"),
+            Self::IOError(err) => write!(f, "IO Error: {err}"),
         }
     }
 }
@@ -27,6 +33,7 @@ impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::FFmpegError(err) => Some(err),
+            Self::IOError(err) => Some(err),
             _ => None,
         }
     }
@@ -0,0 +1,278 @@
+use crate::{Error, VideoResult};
+
+/// The rotation a video is displayed with, read from its display matrix
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    /// Rotated 90 degrees clockwise
+    Clockwise90,
+    /// Rotated 180 degrees
+    Half,
+    /// Rotated 270 degrees clockwise (90 degrees counterclockwise)
+    Clockwise270,
+}
+
+impl Rotation {
+    /// Get the rotation from the clockwise degrees, rounded to the nearest quarter turn
+    pub fn from_degrees(degrees: f64) -> Self {
+        match ((degrees / 90.0).round() as i64).rem_euclid(4) {
+            1 => Self::Clockwise90,
+            2 => Self::Half,
+            3 => Self::Clockwise270,
+            _ => Self::None,
+        }
+    }
+
+    /// Get the rotation of a display matrix
+    ///
+    /// The matrix is nine 32-bit integers in native byte order, like `av_display_rotation_get`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data of the display matrix side data
+    pub fn from_display_matrix(data: &[u8]) -> Option<Self> {
+        let matrix = data
+            .chunks_exact(4)
+            .take(9)
+            .filter_map(|bytes| bytes.try_into().ok().map(i32::from_ne_bytes))
+            .map(|value| value as f64 / 65536.0)
+            .collect::<Vec<_>>();
+        if matrix.len() != 9 {
+            return None;
+        }
+        let scale = (matrix[0].hypot(matrix[3]), matrix[1].hypot(matrix[4]));
+        if scale.0 == 0.0 || scale.1 == 0.0 {
+            return None;
+        }
+        // Like the ffmpeg tool, the angle of the matrix is how far the frames are turned clockwise
+        let degrees = (matrix[1] / scale.1)
+            .atan2(matrix[0] / scale.0)
+            .to_degrees();
+        Some(Self::from_degrees(degrees))
+    }
+
+    /// Get the rotation of a stream, from its display matrix or its `rotate` tag
+    pub(crate) fn of_stream(stream: &ffmpeg_next::format::stream::Stream) -> Self {
+        stream
+            .side_data()
+            .find(|data| data.kind() == ffmpeg_next::codec::packet::side_data::Type::DisplayMatrix)
+            .and_then(|data| Self::from_display_matrix(data.data()))
+            .or_else(|| {
+                stream
+                    .metadata()
+                    .get("rotate")
+                    .and_then(|rotate| rotate.parse::<f64>().ok())
+                    .map(Self::from_degrees)
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the clockwise degrees of the rotation
+    pub fn degrees(self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Clockwise90 => 90,
+            Self::Half => 180,
+            Self::Clockwise270 => 270,
+        }
+    }
+
+    /// Check whether the width and the height are swapped by the rotation
+    pub fn swaps_size(self) -> bool {
+        matches!(self, Self::Clockwise90 | Self::Clockwise270)
+    }
+
+    /// Get the size of a frame after the rotation
+    pub fn rotate_size(self, (width, height): (u32, u32)) -> (u32, u32) {
+        if self.swaps_size() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Rotate a frame to be displayed upright
+    pub fn apply(self, image: image::DynamicImage) -> image::DynamicImage {
+        match self {
+            Self::None => image,
+            Self::Clockwise90 => image.rotate90(),
+            Self::Half => image.rotate180(),
+            Self::Clockwise270 => image.rotate270(),
+        }
+    }
+}
+
+/// The video stream of a file
+#[derive(Debug, Clone)]
+pub struct VideoStreamInfo {
+    pub index: usize,
+    pub codec: String,
+    /// The size of the frames as they are displayed, after the rotation
+    pub size: (u32, u32),
+    pub pixel_format: String,
+    pub frame_rate: f32,
+    pub bit_rate: Option<u64>,
+    pub rotation: Rotation,
+    pub duration: Option<std::time::Duration>,
+    /// The number of frames in the file, or estimated from the duration and the frame rate
+    pub frames: Option<u64>,
+}
+
+/// An audio stream of a file
+#[derive(Debug, Clone)]
+pub struct AudioStreamInfo {
+    pub index: usize,
+    pub codec: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bit_rate: Option<u64>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub default: bool,
+}
+
+/// A subtitle stream of a file
+#[derive(Debug, Clone)]
+pub struct SubtitleStreamInfo {
+    pub index: usize,
+    pub codec: String,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub default: bool,
+}
+
+/// The metadata of a video file and its streams
+#[derive(Debug, Clone)]
+pub struct VideoInfo {
+    /// The name of the container format
+    pub format: String,
+    pub duration: Option<std::time::Duration>,
+    pub bit_rate: Option<u64>,
+    pub video: VideoStreamInfo,
+    pub audio: Vec<AudioStreamInfo>,
+    pub subtitles: Vec<SubtitleStreamInfo>,
+}
+
+/// Get the duration of a timestamp, `None` if it is not set
+fn timestamp_duration(timestamp: i64, time_base: f64) -> Option<std::time::Duration> {
+    if timestamp == ffmpeg_next::ffi::AV_NOPTS_VALUE || timestamp <= 0 {
+        return None;
+    }
+    std::time::Duration::try_from_secs_f64(timestamp as f64 * time_base).ok()
+}
+
+fn codec_name(parameters: &ffmpeg_next::codec::Parameters) -> String {
+    parameters.id().name().to_string()
+}
+
+fn tag(stream: &ffmpeg_next::format::stream::Stream, key: &str) -> Option<String> {
+    stream
+        .metadata()
+        .get(key)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+fn is_default(stream: &ffmpeg_next::format::stream::Stream) -> bool {
+    stream
+        .disposition()
+        .contains(ffmpeg_next::format::stream::Disposition::DEFAULT)
+}
+
+impl VideoInfo {
+    /// Read the metadata of an opened input
+    pub(crate) fn new(input: &ffmpeg_next::format::context::Input) -> VideoResult<Self> {
+        let stream = input
+            .streams()
+            .best(ffmpeg_next::media::Type::Video)
+            .ok_or(Error::CannotFindVideoStream)?;
+        let parameters = stream.parameters();
+        let decoder = ffmpeg_next::codec::Context::from_parameters(parameters.clone())?
+            .decoder()
+            .video()?;
+        let frame_rate = decoder
+            .frame_rate()
+            .or_else(|| Some(stream.avg_frame_rate()))
+            .filter(|rate| rate.numerator() > 0 && rate.denominator() > 0)
+            .map(|rate| rate.numerator() as f32 / rate.denominator() as f32)
+            .unwrap_or_default();
+        let time_base = {
+            let time_base = stream.time_base();
+            time_base.0 as f64 / time_base.1 as f64
+        };
+        // The duration of the container is in `AV_TIME_BASE` (microseconds)
+        let duration = timestamp_duration(input.duration(), 1e-6);
+        let stream_duration = timestamp_duration(stream.duration(), time_base).or(duration);
+        let frames = u64::try_from(stream.frames())
+            .ok()
+            .filter(|&frames| frames > 0)
+            .or_else(|| {
+                stream_duration
+                    .filter(|_| frame_rate > 0.0)
+                    .map(|duration| (duration.as_secs_f64() * frame_rate as f64).round() as u64)
+            });
+        let rotation = Rotation::of_stream(&stream);
+        let video = VideoStreamInfo {
+            index: stream.index(),
+            codec: codec_name(&parameters),
+            size: rotation.rotate_size((decoder.width(), decoder.height())),
+            pixel_format: decoder
+                .format()
+                .descriptor()
+                .map(|descriptor| descriptor.name().to_string())
+                .unwrap_or_default(),
+            frame_rate,
+            bit_rate: Some(decoder.bit_rate() as u64).filter(|&rate| rate > 0),
+            rotation,
+            duration: stream_duration,
+            frames,
+        };
+
+        let mut audio = Vec::new();
+        let mut subtitles = Vec::new();
+        for stream in input.streams() {
+            let parameters = stream.parameters();
+            match parameters.medium() {
+                ffmpeg_next::media::Type::Audio => {
+                    // A stream without a decoder is still listed, without its format
+                    let decoder = ffmpeg_next::codec::Context::from_parameters(parameters.clone())
+                        .and_then(|context| context.decoder().audio())
+                        .ok();
+                    audio.push(AudioStreamInfo {
+                        index: stream.index(),
+                        codec: codec_name(&parameters),
+                        sample_rate: decoder.as_ref().map(|d| d.rate()).unwrap_or_default(),
+                        channels: decoder.as_ref().map(|d| d.channels()).unwrap_or_default(),
+                        bit_rate: decoder
+                            .as_ref()
+                            .map(|d| d.bit_rate() as u64)
+                            .filter(|&rate| rate > 0),
+                        language: tag(&stream, "language"),
+                        title: tag(&stream, "title"),
+                        default: is_default(&stream),
+                    });
+                }
+                ffmpeg_next::media::Type::Subtitle => subtitles.push(SubtitleStreamInfo {
+                    index: stream.index(),
+                    codec: codec_name(&parameters),
+                    language: tag(&stream, "language"),
+                    title: tag(&stream, "title"),
+                    default: is_default(&stream),
+                }),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            format: input.format().name().to_string(),
+            duration,
+            bit_rate: u64::try_from(input.bit_rate())
+                .ok()
+                .filter(|&rate| rate > 0),
+            video,
+            audio,
+            subtitles,
+        })
+    }
+}
@@ -1,10 +1,17 @@
 #![deny(clippy::unwrap_used)]
+mod audio;
 mod decoder;
+mod demuxer;
 mod error;
+mod info;
+mod subtitle;
 mod video;
 
-pub use decoder::{VideoDecoder, VideoFrame};
+pub use audio::{AudioDecoder, AudioFrame};
+pub use decoder::{PixelFormat, VideoDecoder, VideoFrame};
 pub use error::*;
+pub use info::{AudioStreamInfo, Rotation, SubtitleStreamInfo, VideoInfo, VideoStreamInfo};
+pub use subtitle::{SubtitleCue, SubtitleDecoder, Subtitles};
 pub use video::Video;
 
 pub fn open<P>(path: P) -> VideoResult<Video>
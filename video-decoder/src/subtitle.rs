@@ -0,0 +1,329 @@
+use crate::{Error, VideoResult, demuxer::SharedDemuxer};
+use std::time::Duration;
+
+/// A subtitle shown between two times of a video
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtitleCue {
+    pub start: Duration,
+    pub end: Duration,
+    /// The text without the style tags, the lines are separated by `\n`
+    pub text: String,
+}
+
+/// The subtitles of a video, sorted by their start
+#[derive(Debug, Clone, Default)]
+pub struct Subtitles {
+    cues: Vec<SubtitleCue>,
+}
+
+impl Subtitles {
+    /// Create the subtitles from cues in any order, the empty cues are dropped
+    pub fn new(mut cues: Vec<SubtitleCue>) -> Self {
+        cues.retain(|cue| cue.end > cue.start && !cue.text.is_empty());
+        cues.sort_by_key(|cue| cue.start);
+        Self { cues }
+    }
+
+    /// Read a SubRip (`.srt`) or WebVTT (`.vtt`) file
+    ///
+    /// The format is chosen by the `WEBVTT` header, the other files are read as SubRip.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the subtitle file
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> VideoResult<Self> {
+        let data = std::fs::read(path).map_err(Error::IOError)?;
+        let text = String::from_utf8_lossy(&data);
+        let text = text.trim_start_matches('\u{feff}');
+        if text.starts_with("WEBVTT") {
+            Ok(Self::parse_vtt(text))
+        } else {
+            Ok(Self::parse_srt(text))
+        }
+    }
+
+    /// Parse SubRip subtitles, the malformed cues are skipped
+    pub fn parse_srt(text: &str) -> Self {
+        Self::new(blocks(text).filter_map(parse_cue).collect())
+    }
+
+    /// Parse WebVTT subtitles, the notes, styles and regions are skipped
+    pub fn parse_vtt(text: &str) -> Self {
+        Self::new(
+            blocks(text)
+                .filter(|block| {
+                    !["WEBVTT", "NOTE", "STYLE", "REGION"]
+                        .iter()
+                        .any(|keyword| block.first().is_some_and(|line| line.starts_with(keyword)))
+                })
+                .filter_map(parse_cue)
+                .collect(),
+        )
+    }
+
+    /// Add a cue in the order of the starts, an empty cue is dropped
+    pub fn insert(&mut self, cue: SubtitleCue) {
+        if cue.end > cue.start && !cue.text.is_empty() {
+            let index = self.cues.partition_point(|other| other.start <= cue.start);
+            self.cues.insert(index, cue);
+        }
+    }
+
+    pub fn cues(&self) -> &[SubtitleCue] {
+        &self.cues
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cues.is_empty()
+    }
+
+    /// Get the text shown at a time of the video
+    ///
+    /// # Returns
+    ///
+    /// Returns the lines of the cues shown at the time, `None` if no cue is shown
+    pub fn text_at(&self, time: Duration) -> Option<String> {
+        let started = self.cues.partition_point(|cue| cue.start <= time);
+        let lines = self.cues[..started]
+            .iter()
+            .filter(|cue| cue.end > time)
+            .map(|cue| cue.text.as_str())
+            .collect::<Vec<_>>();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+/// Split the text into the blocks separated by blank lines
+fn blocks(text: &str) -> impl Iterator<Item = Vec<&str>> {
+    let mut lines = text.lines().map(|line| line.trim_end_matches('\r')).peekable();
+    std::iter::from_fn(move || {
+        while lines.next_if(|line| line.trim().is_empty()).is_some() {}
+        lines.peek()?;
+        let mut block = Vec::new();
+        while let Some(line) = lines.next_if(|line| !line.trim().is_empty()) {
+            block.push(line);
+        }
+        Some(block)
+    })
+}
+
+/// Parse a cue block, the identifier before the timing line is ignored
+fn parse_cue(block: Vec<&str>) -> Option<SubtitleCue> {
+    let timing = block.iter().position(|line| line.contains("-->"))?;
+    let (start, end) = block[timing].split_once("-->")?;
+    // The settings of WebVTT follow the end time
+    let end = end.split_whitespace().next()?;
+    let text = block[timing + 1..]
+        .iter()
+        .map(|line| strip_tags(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(SubtitleCue {
+        start: parse_timestamp(start.trim())?,
+        end: parse_timestamp(end)?,
+        text: text.trim().to_string(),
+    })
+}
+
+/// Parse a timestamp, `HH:MM:SS,mmm` (SubRip) or `[HH:]MM:SS.mmm` (WebVTT)
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let mut parts = timestamp.rsplit(':');
+    let seconds = parts.next()?.replace(',', ".");
+    if !seconds.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    let mut total = seconds.parse::<f64>().ok()?;
+    for (part, unit) in parts.by_ref().zip([60f64, 3600f64]) {
+        total += part.trim().parse::<u64>().ok()? as f64 * unit;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
+
+/// The names of the style tags of SubRip and WebVTT
+const TAGS: [&str; 10] = ["b", "i", "u", "s", "font", "c", "v", "lang", "ruby", "rt"];
+
+/// Remove the style tags (`<i>`, `<c.red>`, `{\an8}`) and decode the entities of a line
+///
+/// Only the known tags, the WebVTT timestamps and the ASS overrides are removed, the other
+/// brackets are kept as text, e.g. `a < b` or `<3`.
+fn strip_tags(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(open) = rest.find(['<', '{']) {
+        text.push_str(&rest[..open]);
+        let close = if rest[open..].starts_with('<') {
+            '>'
+        } else {
+            '}'
+        };
+        let tag = rest[open + 1..]
+            .find(close)
+            .map(|end| &rest[open + 1..open + 1 + end])
+            .filter(|tag| match close {
+                '>' => is_tag(tag),
+                _ => tag.starts_with('\\'),
+            });
+        match tag {
+            Some(tag) => rest = &rest[open + tag.len() + 2..],
+            None => {
+                text.push_str(&rest[open..open + 1]);
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Check whether the text between `<` and `>` is a style tag or a WebVTT timestamp
+fn is_tag(tag: &str) -> bool {
+    let name = tag.strip_prefix('/').unwrap_or(tag);
+    let name = name
+        .split(['.', ' ', '\t'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    TAGS.contains(&name.as_str()) || (tag.contains(':') && parse_timestamp(tag).is_some())
+}
+
+/// Get the text of an ASS dialogue decoded by FFmpeg
+///
+/// The dialogue is `ReadOrder,Layer,Style,Name,MarginL,MarginR,MarginV,Effect,Text`, or a
+/// `Dialogue:` line with the start and the end instead of the read order.
+fn ass_text(dialogue: &str) -> String {
+    let dialogue = dialogue.trim_end();
+    let text = match dialogue.strip_prefix("Dialogue:") {
+        Some(dialogue) => dialogue.splitn(10, ',').nth(9),
+        None => dialogue.splitn(9, ',').nth(8),
+    }
+    .unwrap_or(dialogue);
+    strip_tags(text)
+        .replace("\\N", "\n")
+        .replace("\\n", "\n")
+        .replace("\\h", " ")
+}
+
+/// The decoder of a text subtitle stream of a video
+///
+/// The decoder shares the demuxer of the video, the packets of the stream are queued as the
+/// video is decoded and they are decoded when the text is looked up, so the input is read once,
+/// along with the playback.
+pub struct SubtitleDecoder {
+    decoder: ffmpeg_next::codec::decoder::Subtitle,
+    subtitle_stream: usize,
+    demuxer: SharedDemuxer,
+    time_base: f64,
+    /// The cues decoded so far
+    subtitles: Subtitles,
+}
+
+impl SubtitleDecoder {
+    pub(crate) fn new(demuxer: SharedDemuxer, subtitle_stream: usize) -> VideoResult<Self> {
+        let (decoder, time_base) = {
+            let mut locked = demuxer.lock();
+            let Some(stream) = locked.input().stream(subtitle_stream) else {
+                return Err(Error::InvalidStream);
+            };
+            let decoder = ffmpeg_next::codec::Context::from_parameters(stream.parameters())?
+                .decoder()
+                .subtitle()?;
+            let time_base = {
+                let time_base = stream.time_base();
+                time_base.0 as f64 / time_base.1 as f64
+            };
+            locked.subscribe(subtitle_stream);
+            (decoder, time_base)
+        };
+        Ok(Self {
+            decoder,
+            subtitle_stream,
+            demuxer,
+            time_base,
+            subtitles: Subtitles::default(),
+        })
+    }
+
+    /// Get the text shown at a time of the video
+    ///
+    /// The packets queued since the last call are decoded first, the broken packets are skipped.
+    ///
+    /// # Returns
+    ///
+    /// Returns the lines of the cues shown at the time, `None` if no cue is shown
+    pub fn text_at(&mut self, time: Duration) -> Option<String> {
+        loop {
+            // The lock is released before decoding, so the video is read in the meantime
+            let packet = self.demuxer.lock().queued_packet(self.subtitle_stream);
+            let Some(packet) = packet else {
+                break;
+            };
+            if let Ok(Some(cue)) = self.decode(&packet) {
+                self.subtitles.insert(cue);
+            }
+        }
+        self.subtitles.text_at(time)
+    }
+
+    /// Decode the cue of a packet
+    ///
+    /// # Returns
+    ///
+    /// Returns the cue, `None` if the packet has no cue or no time
+    fn decode(&mut self, packet: &ffmpeg_next::Packet) -> VideoResult<Option<SubtitleCue>> {
+        let time_base = self.time_base;
+        let seconds =
+            |timestamp: i64| Duration::try_from_secs_f64(timestamp as f64 * time_base).ok();
+        let Some(pts) = packet.pts().and_then(seconds) else {
+            return Ok(None);
+        };
+        let mut subtitle = ffmpeg_next::Subtitle::new();
+        if !self.decoder.decode(packet, &mut subtitle)? {
+            return Ok(None);
+        }
+        let text = subtitle
+            .rects()
+            .filter_map(|rect| match rect {
+                ffmpeg_next::subtitle::Rect::Text(text) => Some(strip_tags(text.get())),
+                ffmpeg_next::subtitle::Rect::Ass(ass) => Some(ass_text(ass.get())),
+                // The bitmap subtitles cannot be drawn as text
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let start = pts + Duration::from_millis(subtitle.start() as u64);
+        // Most text decoders leave the end to the duration of the packet
+        let end = if subtitle.end() > subtitle.start() {
+            Some(pts + Duration::from_millis(subtitle.end() as u64))
+        } else {
+            seconds(packet.duration()).map(|duration| pts + duration)
+        };
+        // The wrapper does not free the rects of the subtitle on drop
+        unsafe { ffmpeg_next::ffi::avsubtitle_free(subtitle.as_mut_ptr()) };
+        Ok(end.map(|end| SubtitleCue {
+            start,
+            end,
+            text: text.trim().to_string(),
+        }))
+    }
+}
+
+impl std::fmt::Debug for SubtitleDecoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubtitleDecoder")
+            .field("subtitle_stream", &self.subtitle_stream)
+            .field("subtitles", &self.subtitles)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for SubtitleDecoder {
+    fn drop(&mut self) {
+        self.demuxer.lock().unsubscribe(self.subtitle_stream);
+    }
+}
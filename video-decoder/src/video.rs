@@ -1,21 +1,109 @@
 use crate::VideoResult;
 
 pub struct Video {
-    input: ffmpeg_next::format::context::Input,
+    /// The input shared by the decoders of the video
+    demuxer: crate::demuxer::SharedDemuxer,
+    /// The time seeked to, the decoders skip the frames before it
+    position: Option<std::time::Duration>,
 }
 
 impl Video {
     pub fn new(input: ffmpeg_next::format::context::Input) -> Self {
-        Self { input }
+        Self {
+            demuxer: crate::demuxer::SharedDemuxer::new(input),
+            position: None,
+        }
     }
 
-    pub fn video_decoder(&mut self) -> VideoResult<crate::VideoDecoder<'_>> {
+    /// Seek to a time of the video
+    ///
+    /// The input is moved to the last keyframe before the time, and the decoder created after
+    /// decodes and skips the frames until the frame shown at the time.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The time to seek to, in the timestamps of the video
+    pub fn seek(&mut self, position: std::time::Duration) -> VideoResult<()> {
+        self.demuxer.lock().seek(position)?;
+        self.position = Some(position);
+        Ok(())
+    }
+
+    /// Get the metadata of the file and its streams
+    pub fn info(&self) -> VideoResult<crate::VideoInfo> {
+        crate::VideoInfo::new(self.demuxer.lock().input())
+    }
+
+    /// Find a subtitle stream
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the subtitle stream, the default subtitle stream if it is `None`
+    ///
+    /// # Returns
+    ///
+    /// Returns the index of the stream, or an error if it is not a subtitle stream
+    pub fn subtitle_stream(&self, index: Option<usize>) -> VideoResult<usize> {
+        let Some(index) = index else {
+            return self
+                .best_stream(ffmpeg_next::media::Type::Subtitle)
+                .ok_or(crate::Error::CannotFindSubtitleStream);
+        };
+        let demuxer = self.demuxer.lock();
+        let stream = demuxer
+            .input()
+            .stream(index)
+            .ok_or(crate::Error::InvalidStream)?;
+        if stream.parameters().medium() != ffmpeg_next::media::Type::Subtitle {
+            return Err(crate::Error::CannotFindSubtitleStream);
+        }
+        Ok(index)
+    }
+
+    fn best_stream(&self, medium: ffmpeg_next::media::Type) -> Option<usize> {
+        let demuxer = self.demuxer.lock();
+        let stream = demuxer.input().streams().best(medium)?;
+        Some(stream.index())
+    }
+
+    /// Create the decoder of the video stream
+    ///
+    /// The decoder and the audio decoder share the input, they can run on their own threads.
+    pub fn video_decoder(&self) -> VideoResult<crate::VideoDecoder> {
+        let index = self
+            .best_stream(ffmpeg_next::media::Type::Video)
+            .ok_or(crate::Error::CannotFindVideoStream)?;
+        let mut decoder = crate::VideoDecoder::new(self.demuxer.clone(), index)?;
+        if let Some(position) = self.position {
+            decoder.skip_to(position);
+        }
+        Ok(decoder)
+    }
+
+    /// Create the decoder of a text subtitle stream
+    ///
+    /// The decoder shares the input with the video decoder, it gets the packets of the stream the
+    /// other decoders read past, so it is created before the video is decoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the subtitle stream, the default subtitle stream if it is `None`
+    pub fn subtitle_decoder(&self, index: Option<usize>) -> VideoResult<crate::SubtitleDecoder> {
+        let index = self.subtitle_stream(index)?;
+        crate::SubtitleDecoder::new(self.demuxer.clone(), index)
+    }
+
+    /// Create the decoder of the audio stream
+    ///
+    /// The decoder and the video decoder share the input, they can run on their own threads.
+    pub fn audio_decoder(&self) -> VideoResult<crate::AudioDecoder> {
         let index = self
-            .input
-            .streams()
-            .best(ffmpeg_next::media::Type::Video)
-            .ok_or(crate::Error::CannotFindVideoStream)?
-            .index();
-        crate::VideoDecoder::new(&mut self.input, index)
+            .best_stream(ffmpeg_next::media::Type::Audio)
+            .ok_or(crate::Error::CannotFindAudioStream)?;
+        let mut decoder = crate::AudioDecoder::new(self.demuxer.clone(), index)?;
+        if let Some(position) = self.position {
+            decoder.skip_to(position);
+        }
+        Ok(decoder)
     }
 }
@@ -0,0 +1,121 @@
+use std::time::Duration;
+use video_decoder::{SubtitleCue, Subtitles};
+
+fn sample(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name)
+}
+
+#[test]
+fn test_parse_srt() {
+    let subtitles = Subtitles::open(sample("sample.srt")).unwrap();
+    // The cue without a timing line is skipped
+    assert_eq!(
+        subtitles.cues(),
+        &[
+            SubtitleCue {
+                start: Duration::from_secs(1),
+                end: Duration::from_millis(3500),
+                text: "Hello world".to_string(),
+            },
+            SubtitleCue {
+                start: Duration::from_secs(3),
+                end: Duration::from_secs(5),
+                text: "Second line\nwith two lines".to_string(),
+            },
+            SubtitleCue {
+                start: Duration::from_millis(62250),
+                end: Duration::from_secs(64),
+                text: "Fish & chips".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_parse_vtt() {
+    let subtitles = Subtitles::open(sample("sample.vtt")).unwrap();
+    // The header, the note and the style are not cues, the settings after the end are ignored
+    assert_eq!(
+        subtitles.cues(),
+        &[
+            SubtitleCue {
+                start: Duration::from_secs(1),
+                end: Duration::from_secs(3),
+                text: "Hello there".to_string(),
+            },
+            SubtitleCue {
+                start: Duration::from_secs(3600),
+                end: Duration::from_millis(3601500),
+                text: "An hour later".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_text_at() {
+    let subtitles = Subtitles::open(sample("sample.srt")).unwrap();
+    assert_eq!(subtitles.text_at(Duration::from_millis(500)), None);
+    assert_eq!(
+        subtitles.text_at(Duration::from_secs(2)).as_deref(),
+        Some("Hello world")
+    );
+    // The overlapping cues are shown together
+    assert_eq!(
+        subtitles.text_at(Duration::from_millis(3200)).as_deref(),
+        Some("Hello world\nSecond line\nwith two lines")
+    );
+    // A cue is hidden at its end
+    assert_eq!(
+        subtitles.text_at(Duration::from_secs(5)).as_deref(),
+        None
+    );
+}
+
+#[test]
+fn test_strip_tags() {
+    let subtitles = Subtitles::parse_srt(
+        "1\n00:00:01,000 --> 00:00:02,000\n<i>a < b</i> {not a tag} <3 {\\an8}x\n\n\
+         2\n00:00:02,000 --> 00:00:03,000\n<c.yellow>Hi</c> <v Roger>there<00:00:02.500> <B>you</B>\n",
+    );
+    // Only the known tags are removed, the other brackets are text
+    let texts = subtitles
+        .cues()
+        .iter()
+        .map(|cue| cue.text.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["a < b {not a tag} <3 x", "Hi there you"]);
+}
+
+#[test]
+fn test_insert() {
+    let mut subtitles = Subtitles::default();
+    for (start, text) in [(3, "second"), (1, "first"), (5, "")] {
+        subtitles.insert(SubtitleCue {
+            start: Duration::from_secs(start),
+            end: Duration::from_secs(start + 1),
+            text: text.to_string(),
+        });
+    }
+    // The cues are sorted by their start, the empty cue is dropped
+    let texts = subtitles
+        .cues()
+        .iter()
+        .map(|cue| cue.text.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["first", "second"]);
+    assert_eq!(
+        subtitles.text_at(Duration::from_millis(3500)).as_deref(),
+        Some("second")
+    );
+}
+
+#[test]
+fn test_open_missing_file() {
+    assert!(matches!(
+        Subtitles::open(sample("missing.srt")),
+        Err(video_decoder::Error::IOError(_))
+    ));
+}